@@ -32,6 +32,31 @@ extern "C" {
 /// @return The character read as an unsigned byte.
 uint8_t dbs_uart_read_char(void);
 
+/// Read one character from UART2, waiting at most ms milliseconds.
+///
+/// Services the transmit DMA queue while waiting. Pass 0 for a
+/// non-blocking check.
+///
+/// @param ms Maximum time to wait, in milliseconds
+/// @return The character (0-255), or -1 on timeout.
+int32_t dbs_uart_read_char_timeout(uint32_t ms);
+
+/// Read available characters from UART2 without blocking.
+///
+/// Copies up to max_len bytes from the UART2 receive buffer into buf and
+/// returns immediately. Use this to read a whole line at once instead of
+/// calling dbs_uart_read_char() for each character.
+///
+/// @param buf Buffer to receive the bytes
+/// @param max_len Size of buf in bytes
+/// @return Number of bytes copied (0 if none were available)
+size_t dbs_uart_read(uint8_t *buf, size_t max_len);
+
+/// Return the number of received UART2 characters waiting to be read.
+///
+/// @return Number of bytes available to dbs_uart_read()
+size_t dbs_uart_rx_available(void);
+
 /// Write data to UART2.
 ///
 /// Queues the data for transmission via DMA. The write is non-blocking;
@@ -51,6 +76,12 @@ void dbs_uart_write(const uint8_t *data, size_t len);
 /// by dbs_uart_write() when needed.
 void dbs_uart_tick(void);
 
+/// Block until all queued UART2 transmit data has been sent.
+///
+/// Services the TX DMA queue until it is empty and the last byte has left
+/// the UART. Call this before a reset or before printing a final prompt.
+void dbs_uart_flush(void);
+
 // ============================================================================
 // Timer Functions
 // ============================================================================
@@ -71,14 +102,95 @@ void dbs_timer_sleep_ms(uint32_t ms);
 uint64_t dbs_timer_millis(void);
 
 // ============================================================================
-// GPIO Functions (placeholder - not yet implemented)
+// System Functions
 // ============================================================================
 
-// GPIO functions will be added in Phase 3.5
-// Examples:
-//   dbs_gpio_set_level(pin, level)
-//   dbs_gpio_get_level(pin)
-//   dbs_gpio_set_dir(pin, direction)
+/// Reset the SoC, as if the board had been power cycled.
+///
+/// Flushes queued UART output, then writes the reset key to the SYSCTRL
+/// SFR_RCURST0 register. The bootloader runs again and restarts the
+/// firmware. Use this to implement machine.reset() in MicroPython.
+void dbs_reset(void) __attribute__((noreturn));
+
+/// Return the reset source flags latched at boot.
+///
+/// These are the SYSCTRL SFR_RCUSRCFR bits (0x40040088), read and cleared
+/// during startup, so each boot reports only the reset that started it.
+/// 0 means no flag was set, as after power-on. The meaning of the
+/// individual bits is not documented yet, so they are returned raw.
+///
+/// @return Reset source flags (bits 15:0)
+uint32_t dbs_reset_cause(void);
+
+/// Return a pseudo-random 32-bit value.
+///
+/// This is a software PRNG seeded from the tick timer, not a hardware
+/// random source. Do not use it for keys or other security purposes.
+uint32_t dbs_random_u32(void);
+
+/// Start the watchdog with a timeout in milliseconds.
+///
+/// Resets the SoC if dbs_watchdog_feed() is not called within the timeout.
+/// This is a software watchdog driven by TIMER0, so it takes over TIMER0
+/// and can't catch code that hangs with interrupts disabled.
+void dbs_watchdog_start(uint32_t timeout_ms);
+
+/// Feed the watchdog, moving its deadline to the timeout from now.
+void dbs_watchdog_feed(void);
+
+// ============================================================================
+// GPIO Functions
+// ============================================================================
+//
+// Pins are named by integer port and pin numbers:
+// - port 0 = port B (PB0-PB15)
+// - port 1 = port C (PC0-PC15)
+// - port 2 = port A (PA0-PA15, not on the dabao headers)
+// - pin 0-15
+// For example, PB12 is (0, 12) and the PROG button PC13 is (1, 13).
+//
+// Out-of-range arguments do nothing and return -1. So do the configuration
+// functions for pins claimed by a driver, such as the UART2 pins PB13/PB14.
+
+/// Configure a GPIO pin (AF0) as an output.
+///
+/// @param port Port number (0 = B, 1 = C, 2 = A)
+/// @param pin Pin number (0-15)
+/// @return 0 on success, -1 if port or pin is out of range or claimed
+int32_t dbs_gpio_set_output(uint8_t port, uint8_t pin);
+
+/// Set a GPIO output pin high.
+///
+/// @param port Port number (0 = B, 1 = C, 2 = A)
+/// @param pin Pin number (0-15)
+/// @return 0 on success, -1 if port or pin is out of range
+int32_t dbs_gpio_set(uint8_t port, uint8_t pin);
+
+/// Set a GPIO output pin low.
+///
+/// @param port Port number (0 = B, 1 = C, 2 = A)
+/// @param pin Pin number (0-15)
+/// @return 0 on success, -1 if port or pin is out of range
+int32_t dbs_gpio_clear(uint8_t port, uint8_t pin);
+
+/// Read the input level of a GPIO pin.
+///
+/// @param port Port number (0 = B, 1 = C, 2 = A)
+/// @param pin Pin number (0-15)
+/// @return 1 if high, 0 if low, -1 if port or pin is out of range
+int32_t dbs_gpio_read(uint8_t port, uint8_t pin);
+
+/// Select the alternate function of a GPIO pin.
+///
+/// AF0 is GPIO, AF1-AF3 route peripherals such as UART2, I2C, SPI, and
+/// PWM to the pin (see the AF enum in src/gpio.rs).
+///
+/// @param port Port number (0 = B, 1 = C, 2 = A)
+/// @param pin Pin number (0-15)
+/// @param af Alternate function (0-3)
+/// @return 0 on success, -1 if port, pin, or af is out of range, or the
+///         pin is claimed
+int32_t dbs_gpio_set_af(uint8_t port, uint8_t pin, uint8_t af);
 
 #ifdef __cplusplus
 }
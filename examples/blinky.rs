@@ -7,7 +7,7 @@
 extern crate baochip_sdk;
 
 use baochip_sdk::{d11ctime, gpio};
-use gpio::{AF, GpioPin};
+use gpio::{DEBUG_LED, Led};
 
 /// Blinky example for bao1x dabao evaluation board
 ///
@@ -16,22 +16,15 @@ use gpio::{AF, GpioPin};
 
 #[unsafe(no_mangle)]
 pub extern "C" fn main() -> ! {
-    // Configure PB12 as GPIO output
-    gpio::set_alternate_function(GpioPin::PortB(gpio::PB12), AF::AF0);
-    gpio::clear(GpioPin::PortB(gpio::PB12));
-    gpio::enable_output(GpioPin::PortB(gpio::PB12));
+    // Configure PB12 as GPIO output, starting with the LED off
+    let mut led = Led::on_pin(DEBUG_LED);
+    led.off();
 
     // Set heartbeat timer for 1 second interval
-    d11ctime::set_interval(d11ctime::millis_to_cycles(1000));
-
-    let mut last_beat = d11ctime::read_heartbeat();
+    d11ctime::set_interval(d11ctime::seconds_to_cycles(1));
 
     loop {
-        let beat = d11ctime::read_heartbeat();
-        if beat != last_beat {
-            // Toggle LED
-            gpio::toggle(GpioPin::PortB(gpio::PB12));
-            last_beat = beat;
-        }
+        d11ctime::wait_for_beat();
+        led.toggle();
     }
 }
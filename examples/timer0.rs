@@ -27,21 +27,21 @@
 //! - Callback runs in interrupt context - keep it short!
 //! - timer0::set_alarm_ms() handles all initialization
 //! - uart::tick() services UART DMA while waiting for button
+//! - gpio::DebouncedInput filters out button contact bounce
 //! - Timestamps show reliable interrupt timing
 
 #![no_std]
 #![no_main]
 extern crate baochip_sdk;
-use baochip_sdk::{gpio, log, sleep, ticktimer, timer0, uart};
-use gpio::{AF, GpioPin};
+use baochip_sdk::{gpio, log, ticktimer, timer0, uart};
+use gpio::{DebouncedInput, Edge, GpioPin};
 use ticktimer::millis;
 
 #[unsafe(no_mangle)]
 pub extern "C" fn main() -> ! {
-    // Configure PC13 (PROG button) as input with pull-up
-    gpio::set_alternate_function(GpioPin::PortC(gpio::PC13), AF::AF0);
-    gpio::disable_output(GpioPin::PortC(gpio::PC13));
-    gpio::enable_pullup(GpioPin::PortC(gpio::PC13));
+    // Configure PC13 (PROG button) as a debounced input with pull-up
+    let mut button =
+        DebouncedInput::new(GpioPin::PortC(gpio::PC13), 10).with_pullup();
 
     loop {
         // Print timestamp and start timer for interrupt callback
@@ -50,17 +50,10 @@ pub extern "C" fn main() -> ! {
         // Arm 2-second alarm - callback will fire in interrupt context
         timer0::set_alarm_ms(2000, alarm_callback);
 
-        // Wait for button release (active low, so 0=pressed, 1=released)
-        while gpio::read_input(GpioPin::PortC(gpio::PC13)) == 0 {
+        // Wait for a button press (active low, so a falling edge)
+        while button.update() != Some(Edge::Falling) {
             uart::tick();
         }
-        sleep(10);
-
-        // Wait for button press
-        while gpio::read_input(GpioPin::PortC(gpio::PC13)) != 0 {
-            uart::tick();
-        }
-        sleep(10);
     }
 }
 
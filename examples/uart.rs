@@ -4,40 +4,45 @@
 #![no_std]
 #![no_main]
 extern crate baochip_sdk;
-use baochip_sdk::{gpio, log, sleep, ticktimer, uart};
-use gpio::{AF, GpioPin};
+use baochip_sdk::{gpio, logln, sched, ticktimer};
+use gpio::{DebouncedInput, Edge, GpioPin};
+use sched::Task;
 
 /// UART example for bao1x dabao evaluation board
 ///
-/// Demonstrates UART2 and the ticktimer module by repeatedly printing
-/// "hello, world!" with the current millisecond timestamp (from the TICKTIMER
-/// peripheral). Waits for button press/release cycles on the PROG button
-/// (PC13) between prints, using crate::sleep() for debouncing. Uses
-/// uart::tick() to service the DMA TX queue.
+/// Demonstrates UART2 and the ticktimer module by printing "hello, world!"
+/// with the current millisecond timestamp (from the TICKTIMER peripheral)
+/// at startup and on each press of the PROG button (PC13), using
+/// gpio::DebouncedInput for debouncing. The button is polled by a sched
+/// task, and sched::run() services the DMA TX queue with uart::tick().
 #[unsafe(no_mangle)]
 pub extern "C" fn main() -> ! {
-    // Configure PC13 (PROG button) as input with pull-up
-    gpio::set_alternate_function(GpioPin::PortC(gpio::PC13), AF::AF0);
-    gpio::disable_output(GpioPin::PortC(gpio::PC13));
-    gpio::enable_pullup(GpioPin::PortC(gpio::PC13));
+    // Configure PC13 (PROG button) as a debounced input with pull-up
+    let button =
+        DebouncedInput::new(GpioPin::PortC(gpio::PC13), 10).with_pullup();
+    unsafe { BUTTON = Some(button) };
 
     // UART2 initialization happens at boot time in crate::init()
 
-    loop {
-        // Print message prefix
-        let ms = ticktimer::millis();
-        log!("hello, world! [millis() = {}]\r\n", ms);
+    say_hello();
+    let _ = Task::every(1, poll_button);
+    sched::run();
+}
 
-        // Wait until PC13 is high (button released)
-        while gpio::read_input(GpioPin::PortC(gpio::PC13)) == 0 {
-            uart::tick();
-        }
-        sleep(10); // debounce
+// Debounced PROG button, owned by poll_button() once main() sets it
+static mut BUTTON: Option<DebouncedInput> = None;
 
-        // Wait until PC13 is low (button pressed)
-        while gpio::read_input(GpioPin::PortC(gpio::PC13)) != 0 {
-            uart::tick();
-        }
-        sleep(10); // debounce
+// Task: print again on each button press (active low, so a falling edge)
+fn poll_button() {
+    let button = &raw mut BUTTON;
+    if let Some(button) = unsafe { (*button).as_mut() }
+        && button.update() == Some(Edge::Falling)
+    {
+        say_hello();
     }
 }
+
+fn say_hello() {
+    let ms = ticktimer::millis();
+    logln!("hello, world! [millis() = {}]", ms);
+}
@@ -6,9 +6,13 @@
 extern crate baochip_sdk;
 use baochip_sdk::{log, sleep, usb};
 
-/// USB ACM serial example for bao1x dabao evaluation board
+/// USB ACM bring-up example for bao1x dabao evaluation board
 ///
-/// TODO: finish this comment
+/// Runs the USB phases that work so far: the IRQARRAY1 pending bit test,
+/// controller detection, and a dry run of CDC-ACM enumeration through the
+/// EP0 request handlers. The controller layer is not written yet, so the
+/// board does not show up as a serial port. Instead, this feeds the SETUP
+/// packets a host would send and logs the responses.
 #[unsafe(no_mangle)]
 pub extern "C" fn main() -> ! {
     log!("starting IRQARRAY1_EV_PENDING test\r\n");
@@ -19,10 +23,64 @@ pub extern "C" fn main() -> ! {
     sleep(20);
     log!("calling usb::detect().\r\n");
     sleep(20);
-    usb::detect();
+    match usb::detect() {
+        Ok(_) => log!("USB controller detected\r\n"),
+        Err(e) => log!("USB detect failed: {:?}\r\n", e),
+    }
     log!("made it past detect().\r\n");
 
+    enumerate_dry_run();
+
     loop {
-        sleep(10);
+        sleep(1000);
+    }
+}
+
+// Walk through the control requests of a typical host enumeration
+fn enumerate_dry_run() {
+    let mut buf = [0u8; usb::EP0_BUF_SIZE];
+    usb::bus_reset(false);
+    usb::set_line_coding_callback(on_line_coding);
+
+    // GET_DESCRIPTOR(device), first 8 bytes, then the whole thing
+    request(&mut buf, [0x80, 0x06, 0x00, 0x01, 0, 0, 8, 0]);
+    request(&mut buf, [0x80, 0x06, 0x00, 0x01, 0, 0, 18, 0]);
+
+    // SET_ADDRESS(5), applied after the status stage
+    request(&mut buf, [0x00, 0x05, 5, 0, 0, 0, 0, 0]);
+    if let Some(addr) = usb::status_stage_done() {
+        log!("address: {}\r\n", addr);
+    }
+
+    // GET_DESCRIPTOR(configuration), header then full length
+    request(&mut buf, [0x80, 0x06, 0x00, 0x02, 0, 0, 9, 0]);
+    request(&mut buf, [0x80, 0x06, 0x00, 0x02, 0, 0, 0xff, 0]);
+
+    // GET_DESCRIPTOR(string 2), which is the product name
+    request(&mut buf, [0x80, 0x06, 0x02, 0x03, 0x09, 0x04, 0xff, 0]);
+
+    // SET_CONFIGURATION(1)
+    request(&mut buf, [0x00, 0x09, 1, 0, 0, 0, 0, 0]);
+    log!("configured: {}\r\n", usb::is_configured());
+
+    // SET_LINE_CODING(1 Mbps, 8N1), then raise DTR
+    request(&mut buf, [0x21, 0x20, 0, 0, 0, 0, 7, 0]);
+    usb::handle_control_out(&[0x40, 0x42, 0x0f, 0x00, 0, 0, 8]);
+    request(&mut buf, [0x21, 0x22, 0x01, 0, 0, 0, 0, 0]);
+    log!("dtr: {}\r\n", usb::dtr());
+}
+
+// Send one SETUP packet through the EP0 handler and log the response
+fn request(buf: &mut [u8; usb::EP0_BUF_SIZE], setup: [u8; 8]) {
+    let setup = usb::SetupPacket::from_bytes(&setup);
+    match usb::handle_setup(&setup, buf) {
+        usb::ControlResponse::Data(len) => {
+            baochip_sdk::log::hexdump("IN data", &buf[..len]);
+        }
+        other => log!("{:?}\r\n", other),
     }
 }
+
+fn on_line_coding(coding: usb::LineCoding) {
+    log!("line coding: {:?}\r\n", coding);
+}
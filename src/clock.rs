@@ -0,0 +1,180 @@
+// SPDX-License-Identifier: MIT
+// SPDX-FileCopyrightText: Copyright 2026 Sam Blenny
+//
+//! System clock configuration for bao1x dabao evaluation board
+//!
+//! Tracks the ACLK and PERCLK frequencies at runtime, so drivers that
+//! derive divisors from them (TICKTIMER, TIMER0, UART, I2C, PWM) stay
+//! correct if the clocks are not the bootloader defaults.
+//!
+//! # Usage
+//!
+//! ```ignore
+//! use baochip_sdk::clock;
+//!
+//! // Check the clock the drivers are using
+//! let hz = clock::aclk_hz();
+//!
+//! // A custom bootloader left ACLK at 200 MHz
+//! clock::set_aclk_hz(200_000_000);
+//! ```
+//!
+//! # Hardware Details
+//!
+//! The bootloader sets up the PLL before jumping to the firmware, leaving:
+//! - ACLK: 350 MHz (CPU, TICKTIMER, TIMER0, D11CTIME)
+//! - PERCLK: 100 MHz (uDMA UART/I2C/SPI, PWM)
+//!
+//! The PLL and clock dividers live in SYSCTRL (base 0x40040000):
+//! - SFR_CGUSEL0 (0x10): Clock source select
+//! - SFR_CGUFD_CFGFDCR_0_4_0..4 (0x14-0x24): Fractional dividers for the
+//!   ACLK, HCLK, ICLK, PCLK clock tree
+//! - SFR_CGUSET (0x2c): Latches new divider and select settings
+//! - SFR_IPCPLLMN (0xa0), SFR_IPCPLLF (0xa4), SFR_IPCPLLQ (0xa8): PLL
+//!   M/N, fractional, and Q divider settings
+//! - SFR_IPCEN (0x94), SFR_IPCARIPFLOW (0x90): Analog IP enable and
+//!   update sequencing
+//!
+//! The register descriptions this SDK is built from give these registers
+//! only as opaque 16 or 32-bit values, without the field layouts or the
+//! update sequence. Writing them blind risks leaving the chip without a
+//! clock, so this module does not program the PLL.
+//!
+//! If something else (e.g. a custom bootloader) has changed the clocks,
+//! call set_aclk_hz() or set_perclk_hz() to tell the SDK the new
+//! frequencies. set_aclk_hz() updates the TICKTIMER tick rate right away,
+//! without resetting `millis()`. Drivers that compute divisors in their
+//! own init() (UART, I2C) pick up the new PERCLK the next time they are
+//! initialized.
+//!
+//! # D11CTIME
+//!
+//! `d11ctime::seconds_to_cycles()` and `millis_to_cycles()` are `const fn`
+//! so they can be used in constants, which means they use the 350 MHz
+//! default rather than aclk_hz(). Use `aclk_hz()` directly to compute
+//! D11CTIME intervals when running at another frequency.
+//!
+//! # uDMA Clock Gating
+//!
+//! Each uDMA peripheral (UART, SPIM, I2C, ...) has its own clock enable bit
+//! in the uDMA control block's REG_CG register (0x50100000), and its
+//! registers don't respond until that bit is set. The bit number is the
+//! peripheral ID, which is also the position of the peripheral's register
+//! block after REG_CG (ID `n` is at 0x50101000 + n * 0x1000):
+//!
+//! | ID    | Peripheral | ID    | Peripheral |
+//! |-------|------------|-------|------------|
+//! | 0-3   | UART0-3    | 15    | FILTER     |
+//! | 4-7   | SPIM0-3    | 16    | SCIF       |
+//! | 8-11  | I2C0-3     | 17-18 | SPIS0-1    |
+//! | 12    | SDIO       | 19    | ADC        |
+//! | 13    | I2S        |       |            |
+//! | 14    | CAMERA     |       |            |
+//!
+//! The `UDMA_CG_*` constants hold these bits as masks. Drivers for a
+//! numbered instance shift the first one, e.g. `UDMA_CG_I2C0 << bus`. The
+//! UART, SPIM, and I2C bits are used by the drivers in this SDK. The others
+//! follow the same ordering but are untested. (The register descriptions
+//! list REG_CG as a 6-bit field, which is too narrow for the I2C bits the
+//! drivers rely on, so that width is not trusted here.)
+//!
+//! udma_clock_enable() and udma_clock_disable() do the read-modify-write
+//! in a critical section, so drivers gating different peripherals from
+//! main code and interrupt handlers don't lose each other's bits.
+//!
+//! # API Design
+//!
+//! - aclk_hz(): Current ACLK frequency
+//! - perclk_hz(): Current PERCLK frequency
+//! - set_aclk_hz(): Record an ACLK frequency set up outside the SDK
+//! - set_perclk_hz(): Record a PERCLK frequency set up outside the SDK
+//! - udma_clock_enable(): Turn on the clock of uDMA peripherals
+//! - udma_clock_disable(): Turn off the clock of uDMA peripherals
+//! - `UDMA_CG_*`: Clock enable masks for the uDMA peripherals
+
+use crate::interrupt;
+use crate::mmio;
+use crate::pac;
+use core::ptr;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// PERCLK frequency left by the bootloader.
+pub const PERCLK_DEFAULT_HZ: u32 = pac::PERCLK_HZ;
+
+// uDMA control block clock gate register
+const UDMA_REG_CG: usize = pac::UDMA_CTRL_BASE;
+
+/// uDMA clock enable masks, by peripheral ID (see "uDMA Clock Gating").
+pub const UDMA_CG_UART0: u32 = 1 << 0; // UART1-3 follow it
+pub const UDMA_CG_SPIM0: u32 = 1 << 4; // SPIM1-3 follow it
+pub const UDMA_CG_I2C0: u32 = 1 << 8; // I2C1-3 follow it
+pub const UDMA_CG_SDIO: u32 = 1 << 12;
+pub const UDMA_CG_I2S: u32 = 1 << 13;
+pub const UDMA_CG_CAMERA: u32 = 1 << 14;
+pub const UDMA_CG_FILTER: u32 = 1 << 15;
+pub const UDMA_CG_SCIF: u32 = 1 << 16;
+pub const UDMA_CG_SPIS0: u32 = 1 << 17; // SPIS1 follows it
+pub const UDMA_CG_ADC: u32 = 1 << 19;
+
+// ============================================================================
+// Internal State
+// ============================================================================
+
+static mut ACLK: u32 = crate::ACLK_HZ;
+static mut PERCLK: u32 = PERCLK_DEFAULT_HZ;
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Return the ACLK frequency the drivers are using, in Hz.
+pub fn aclk_hz() -> u32 {
+    unsafe { ptr::read_volatile(&raw const ACLK) }
+}
+
+/// Return the PERCLK frequency the drivers are using, in Hz.
+pub fn perclk_hz() -> u32 {
+    unsafe { ptr::read_volatile(&raw const PERCLK) }
+}
+
+/// Record that ACLK is running at `hz`, without touching the PLL.
+///
+/// Use this when the clock was changed outside the SDK. Sets the
+/// TICKTIMER for 1 microsecond ticks at the new frequency, without
+/// resetting `millis()` (or, under the TICKTIMER fallback, recomputes the
+/// TIMER0 tick period). TIMER0 alarms started after this call use the new
+/// frequency.
+pub fn set_aclk_hz(hz: u32) {
+    unsafe { ptr::write_volatile(&raw mut ACLK, hz) };
+    crate::ticktimer::set_tick_rate();
+}
+
+/// Record that PERCLK is running at `hz`, without touching the PLL.
+///
+/// Call `uart::init()` and `i2c::init()` again afterwards so they
+/// recompute their divisors.
+pub fn set_perclk_hz(hz: u32) {
+    unsafe { ptr::write_volatile(&raw mut PERCLK, hz) };
+}
+
+/// Turn on the clock of the uDMA peripherals in `periph_bits`.
+///
+/// `periph_bits` is one or more `UDMA_CG_*` masks ORed together. Other
+/// peripherals' clocks are left as they are. The register write is
+/// complete when this returns, so the peripheral can be configured next.
+pub fn udma_clock_enable(periph_bits: u32) {
+    let _cs = interrupt::critical_section();
+    unsafe { mmio::modify32(UDMA_REG_CG, 0, periph_bits) };
+}
+
+/// Turn off the clock of the uDMA peripherals in `periph_bits`.
+///
+/// Stop any DMA transfer on the peripheral first. Its registers ignore
+/// writes until the clock is enabled again.
+pub fn udma_clock_disable(periph_bits: u32) {
+    let _cs = interrupt::critical_section();
+    unsafe { mmio::modify32(UDMA_REG_CG, periph_bits, 0) };
+}
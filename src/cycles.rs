@@ -0,0 +1,146 @@
+// SPDX-License-Identifier: MIT
+// SPDX-FileCopyrightText: Copyright 2026 Sam Blenny
+//
+//! CPU cycle counter for bao1x dabao evaluation board
+//!
+//! Provides cycle-accurate timing for micro-benchmarks and short busy
+//! delays for bit-banged protocols, using the RISC-V `mcycle` counter CSR.
+//!
+//! # Usage
+//!
+//! ```ignore
+//! use baochip_sdk::cycles::{self, Stopwatch};
+//!
+//! let sw = Stopwatch::start();
+//! // ... code to measure ...
+//! let n = sw.elapsed_cycles();
+//! log!("{} cycles ({} ns)\r\n", n, cycles::cycles_to_ns(n));
+//! ```
+//!
+//! # Hardware Details
+//!
+//! `mcycle` (CSR 0xb00) and `mcycleh` (CSR 0xb80) form a 64-bit count of
+//! CPU clock cycles. The CPU runs from ACLK, so at 350 MHz one cycle is
+//! about 2.86ns and the count wraps after about 1,670 years. On RV32 the
+//! two halves have to be read separately, so `now()` reads high, low, high
+//! and retries if the high word changed, the same as `ticktimer::micros()`.
+//!
+//! The counter keeps running through interrupts, so a measurement that
+//! spans an interrupt includes the handler's cycles. Disable interrupts
+//! around the measured code for the most repeatable numbers.
+//!
+//! # Short Delays
+//!
+//! delay_cycles() spins until `mcycle` has advanced by the requested
+//! count, and delay_ns() converts nanoseconds to cycles first (rounding
+//! up). The counter is read with `csrr` in inline asm, which the compiler
+//! can't remove or merge, so the loop is not optimized away. Only the low
+//! 32 bits are read, with a wrapping subtraction, so a delay is correct
+//! even when it spans a wrap of the low word.
+//!
+//! The loop overhead sets a floor: each delay costs at least the call, two
+//! counter reads, and a compare, so a request for fewer cycles than that
+//! still takes that long. This is on the order of ten cycles (about 30ns
+//! at 350 MHz) but has not been measured on hardware. Time an empty
+//! delay_cycles(0) with Stopwatch for the real figure. Interrupts that
+//! arrive during a delay lengthen it, so disable them around timing that
+//! must not stretch.
+//!
+//! # API Design
+//!
+//! - now(): Read the raw 64-bit cycle count
+//! - Stopwatch: start() and elapsed_cycles() for measuring a span
+//! - cycles_to_ns(): Convert a cycle count to nanoseconds at the current
+//!   ACLK frequency
+//! - delay_cycles(), delay_ns(): Busy-wait for a number of cycles or
+//!   nanoseconds
+
+use crate::clock;
+use core::arch::asm;
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Read the current 64-bit CPU cycle count.
+pub fn now() -> u64 {
+    loop {
+        let hi: u32;
+        let lo: u32;
+        let hi2: u32;
+        unsafe {
+            asm!("csrr {0}, mcycleh", out(reg) hi);
+            asm!("csrr {0}, mcycle", out(reg) lo);
+            asm!("csrr {0}, mcycleh", out(reg) hi2);
+        }
+        if hi == hi2 {
+            return ((hi as u64) << 32) | lo as u64;
+        }
+    }
+}
+
+// Read the low 32 bits of the cycle counter
+#[inline(always)]
+pub(crate) fn mcycle() -> u32 {
+    let lo: u32;
+    unsafe { asm!("csrr {0}, mcycle", out(reg) lo) };
+    lo
+}
+
+/// Busy-wait for at least `cycles` CPU cycles.
+///
+/// See "Short Delays" in the module docs for the minimum delay.
+#[inline]
+pub fn delay_cycles(cycles: u32) {
+    let start = mcycle();
+    while mcycle().wrapping_sub(start) < cycles {}
+}
+
+/// Busy-wait for at least `ns` nanoseconds, using `clock::aclk_hz()`.
+///
+/// Rounds the cycle count up, so at 350 MHz delay_ns(10) waits 4 cycles
+/// (about 11.4ns) plus the loop overhead.
+#[inline]
+pub fn delay_ns(ns: u32) {
+    let cycles = (ns as u64 * clock::aclk_hz() as u64).div_ceil(1_000_000_000);
+    delay_cycles(cycles.min(u32::MAX as u64) as u32);
+}
+
+/// Convert a cycle count to nanoseconds, using `clock::aclk_hz()`.
+///
+/// Rounds down. The intermediate product is 128 bits wide, so this does not
+/// overflow for any cycle count.
+pub fn cycles_to_ns(cycles: u64) -> u64 {
+    (cycles as u128 * 1_000_000_000 / clock::aclk_hz() as u128) as u64
+}
+
+/// Cycle-counting stopwatch for micro-benchmarks.
+///
+/// The reads of the counter themselves take a few cycles, so subtract the
+/// result of timing an empty span when measuring very short code.
+#[derive(Clone, Copy)]
+pub struct Stopwatch {
+    start: u64,
+}
+
+impl Stopwatch {
+    /// Start timing from now.
+    pub fn start() -> Self {
+        Stopwatch { start: now() }
+    }
+
+    /// Return the number of cycles since start() (or the last restart()).
+    pub fn elapsed_cycles(&self) -> u64 {
+        now().wrapping_sub(self.start)
+    }
+
+    /// Return the time since start() in nanoseconds.
+    pub fn elapsed_ns(&self) -> u64 {
+        cycles_to_ns(self.elapsed_cycles())
+    }
+
+    /// Reset the start time to now.
+    pub fn restart(&mut self) {
+        self.start = now();
+    }
+}
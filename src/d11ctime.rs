@@ -18,21 +18,49 @@
 //! - HEARTBEAT: Read-only register with heartbeat status in bit 0.
 //!   Toggles each time the interval expires.
 //!
+//! # Interrupts
+//!
+//! D11CTIME has no interrupt. Its register block is just CONTROL and
+//! HEARTBEAT (no EV_STATUS/EV_PENDING/EV_ENABLE registers), and it has no
+//! IRQ number in the bao1x interrupt map, so there is no IRQARRAY bank or
+//! MIM bit to dispatch a heartbeat callback from. The heartbeat can only be
+//! polled, as wait_for_beat() does.
+//!
+//! For a periodic interrupt, use `timer0::set_periodic_ms()`. The TICKTIMER
+//! block does have an alarm event (MSLEEP_TARGET0/1 with EV_ENABLE bit 0,
+//! IRQ 20), which could be re-armed from its handler to give a second
+//! periodic source independent of TIMER0, but this SDK does not drive it
+//! yet.
+//!
 //! # Example
 //!
 //! To set a 1-second interval at 350 MHz:
 //! 1. Write 350_000_000 to CONTROL
 //! 2. Poll HEARTBEAT bit 0 to detect toggles
+//!
+//! Or, with the helpers in this module:
+//! ```ignore
+//! d11ctime::set_interval(d11ctime::seconds_to_cycles(1));
+//! let beat = d11ctime::read_heartbeat();
+//! ```
+//!
+//! # Naming
+//!
+//! Functions are named without a `d11ctime_` prefix, since callers already
+//! write the module path (`d11ctime::set_interval`). Time conversion helpers
+//! are named `<unit>_to_cycles`.
+
+use crate::pac;
 
 // ============================================================================
 // Constants
 // ============================================================================
 
-const D11CTIME_BASE: usize = 0xe0000000;
+const D11CTIME_BASE: usize = pac::D11CTIME_BASE;
 const CONTROL: *mut u32 = D11CTIME_BASE as *mut u32;
 const HEARTBEAT: *const u32 = (D11CTIME_BASE + 4) as *const u32;
 
-pub const ACLK_FREQ_HZ: u32 = 350_000_000;
+pub const ACLK_FREQ_HZ: u32 = pac::ACLK_HZ;
 
 // ============================================================================
 // Register Access Functions
@@ -74,6 +102,33 @@ pub fn read_heartbeat() -> u32 {
     unsafe { core::ptr::read_volatile(HEARTBEAT) & 1 }
 }
 
+// ============================================================================
+// Blocking Waits
+// ============================================================================
+
+/// Wait for the next heartbeat toggle.
+///
+/// Records the current heartbeat bit, then spins until it changes, calling
+/// uart::tick() to service the TX DMA queue while waiting. Returns right
+/// after the interval expires, so calling this in a loop gives one
+/// iteration per interval.
+pub fn wait_for_beat() {
+    let last_beat = read_heartbeat();
+    while read_heartbeat() == last_beat {
+        crate::uart::tick();
+    }
+}
+
+/// Wait for `n` heartbeat toggles.
+///
+/// Same as calling `wait_for_beat()` `n` times. Returns immediately if `n`
+/// is 0.
+pub fn wait_beats(n: u32) {
+    for _ in 0..n {
+        wait_for_beat();
+    }
+}
+
 // ============================================================================
 // Helper Functions
 // ============================================================================
@@ -88,3 +143,15 @@ pub fn read_heartbeat() -> u32 {
 pub const fn millis_to_cycles(millis: u32) -> u32 {
     (ACLK_FREQ_HZ / 1000) * millis
 }
+
+/// Calculate ACLK cycles for a given time interval in seconds.
+///
+/// The seconds counterpart of `millis_to_cycles()`. The 32-bit CONTROL
+/// register holds at most about 12.27 seconds of cycles at 350 MHz, so
+/// longer intervals saturate at `u32::MAX`.
+///
+/// Example: seconds_to_cycles(1) = 350,000,000 (1 second)
+#[inline]
+pub const fn seconds_to_cycles(seconds: u32) -> u32 {
+    ACLK_FREQ_HZ.saturating_mul(seconds)
+}
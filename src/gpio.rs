@@ -3,10 +3,10 @@
 //
 //! GPIO support for bao1x dabao evaluation board
 //!
-//! Provides direct, minimal register access for GPIO control on ports B
-//! and C. Pin operations are type-safe: each pin constant (PB13, PC3, etc.)
-//! is bound to its port, preventing accidental port mismatches at compile
-//! time.
+//! Provides direct, minimal register access for GPIO control on ports A,
+//! B, and C. Pin operations are type-safe: each pin constant (PB13, PC3,
+//! etc.) is bound to its port, preventing accidental port mismatches at
+//! compile time.
 //!
 //! # Hardware Register Names
 //!
@@ -14,20 +14,14 @@
 //! Baochip documentation, but we use the more intuitive name "gpio"
 //! throughout this module.
 //!
-//! # Unimplemented Features
+//! # Port A
 //!
-//! The GPIO hardware supports several additional features that are not yet
-//! exposed by this driver:
-//!
-//! - Drive strength control (GPIOCFG_DRVSEL) - configure output drive
-//!   current (2mA, 4mA, 8mA, 12mA)
-//! - Slew rate control (GPIOCFG_RATCLR) - slow down output transitions
-//! - Schmitt trigger (GPIOCFG_SCHM) - add hysteresis to inputs
-//! - Interrupt support (INTCR, INTFR) - generate CPU interrupts on pin
-//!   state changes
-//!
-//! These features can be added as needed. The current implementation focuses
-//! on basic GPIO output, input, and alternate function operations.
+//! The IOX block has register slots for ports A through F, with port A
+//! first. The dabao board only breaks out port B and port C pins, so on
+//! dabao the port A pins (PA0-PA15) are there for custom boards built on
+//! the bao1x package. The register descriptions don't say which port A
+//! pads are bonded out, so writes to an unbonded pin are harmless but have
+//! no visible effect, and reads return an undefined level.
 //!
 //! # Registers
 //!
@@ -42,9 +36,77 @@
 //! - GPIOPU: Pull-up register. Writing 1 enables internal pull-up,
 //!   writing 0 disables it. Only effective when pin is configured as input.
 //!
+//!   The IOX block has no pull-down register and no pull-direction select
+//!   bit, so internal pull-down is not supported. For an input that must
+//!   idle low (e.g. an active-high sensor that floats when disconnected),
+//!   wire an external pull-down resistor (10kΩ or so) to GND.
+//!
 //! - GPIOIN: Input register (read-only). Reflects the current state of
 //!   pins configured as inputs.
 //!
+//! There are no separate set, clear, or toggle registers, so changing one
+//! pin means a read-modify-write of the whole GPIOOUT register. To keep an
+//! interrupt handler that writes the same port (e.g. the trap handler's
+//! PB12 debug LED) from being overwritten by a stale read, `set()`,
+//! `clear()`, and `toggle()` disable interrupts for the few instructions
+//! of the read-modify-write. The cost is a small amount of added interrupt
+//! latency on every pin write.
+//!
+//! There is no open-drain configuration bit either. An open-drain output
+//! (drive low, release high) for a shared bus like bit-banged I2C is
+//! emulated by leaving the GPIOOUT bit at 0 and switching GPIOOE:
+//! `set_open_drain_low()` enables the output to pull the line low, and
+//! `set_open_drain_release()` disables it so the line floats and a pull-up
+//! brings it high. The pin is never driven high, so two devices pulling
+//! against each other can't short. Use `enable_pullup()` or, for bus
+//! speeds above a few kHz, an external pull-up (a few kΩ), since the
+//! internal pull-up strength is not documented.
+//!
+//! Pad configuration registers (one per port):
+//!
+//! - GPIOCFG_SCHM: Schmitt trigger enable. Writing 1 adds input hysteresis,
+//!   writing 0 disables it. Only effective when pin is configured as input.
+//!
+//! - GPIOCFG_DRVSEL: Drive strength select. Each pin has a 2-bit field at
+//!   bit position `pin * 2` (00 = 2mA, 01 = 4mA, 10 = 8mA, 11 = 12mA).
+//!
+//! - GPIOCFG_RATCLR: Slew rate control. Writing 1 selects the slow
+//!   (rate-limited) output edge, writing 0 selects the fast edge (default).
+//!
+//! # Interrupts
+//!
+//! The IOX block has 8 interrupt channels. Each channel has an INTCR
+//! register that selects one pin and a trigger edge:
+//!
+//! - INTCR bits [6:0]: Pin select (`port * 16 + pin`, with port A = 0,
+//!   port B = 1, port C = 2)
+//! - INTCR bits [8:7]: Trigger mode (00 = rising edge, 01 = falling edge)
+//! - INTCR bit 9: Channel enable
+//!
+//! INTFR bits [7:0] flag which channels have fired (write 1 to clear).
+//!
+//! All 8 channels share the IOXIRQ event, which is bit 0 of IRQARRAY10
+//! (base 0xe0006000). IRQARRAY10 is enabled by MIM bit 10, which is set at
+//! boot by `interrupt::irq_setup()`. The trap handler dispatches IRQARRAY10
+//! events to the callbacks registered with `set_gpio_callback()`.
+//!
+//! # Pin Claims
+//!
+//! A driver that owns pins (e.g. `uart::init()` with PB13/PB14) claims
+//! them with `try_claim()`, which records them in a static bitmap and
+//! returns a `PinToken`. In debug builds, the configuration functions
+//! (`set_alternate_function()`, `enable_output()`, `disable_output()`,
+//! `enable_pullup()`, `disable_pullup()`, and `configure()` through them)
+//! panic if the pin is claimed, so reusing a UART pin as GPIO shows up as a
+//! panic message naming the problem instead of serial output that quietly
+//! stops. Release builds skip the check. `set()`, `clear()`, and reads are
+//! not checked.
+//!
+//! A token holder that needs to reconfigure its own pins (as
+//! `uart::send_break()` does) releases the claim first and claims again
+//! afterwards. Dropping a token without `release()` leaves the pin
+//! claimed.
+//!
 //! # Usage Examples
 //!
 //! Configure the PROG button input (PC13):
@@ -56,6 +118,17 @@
 //! gpio::enable_pullup(GpioPin::PortC(gpio::PC13));
 //! ```
 //!
+//! Or, as a single call:
+//! ```ignore
+//! use gpio::{Direction, GpioPin, PinConfig, Pull, AF};
+//!
+//! gpio::configure(GpioPin::PortC(gpio::PC13), PinConfig {
+//!     af: AF::AF0,
+//!     direction: Direction::Input,
+//!     pull: Pull::Up,
+//! });
+//! ```
+//!
 //! Configure an LED output pin (PB12):
 //! The dabao board does not have a built-in LED. To test output, wire an LED
 //! to PB12 with a current-limiting resistor (330Ω or 470Ω) to GND:
@@ -67,6 +140,81 @@
 //! gpio::set(GpioPin::PortB(gpio::PB12));
 //! ```
 //!
+//! Or, with the `Led` helper:
+//! ```ignore
+//! let mut led = gpio::Led::on_pin(gpio::DEBUG_LED);
+//! led.on();
+//! ```
+//!
+//! Call a function when the PROG button (PC13) is pressed:
+//! ```ignore
+//! use gpio::{Edge, GpioPin};
+//!
+//! fn button_pressed() {
+//!     // This runs in interrupt context
+//! }
+//!
+//! gpio::set_gpio_callback(GpioPin::PortC(gpio::PC13), button_pressed);
+//! gpio::enable_interrupt(GpioPin::PortC(gpio::PC13), Edge::Falling);
+//! ```
+//!
+//! Or, use the owned pin handles, which do the same configuration steps:
+//! ```ignore
+//! use gpio::{GpioPin, Input, Output};
+//!
+//! let button = Input::new(GpioPin::PortC(gpio::PC13)).with_pullup();
+//! let mut led = Output::new(GpioPin::PortB(gpio::PB12));
+//! if button.is_low() {
+//!     led.set_high();
+//! }
+//! ```
+//!
+//! The handles can also invert the level for active-low wiring, so the
+//! code reads in terms of "pressed" and "on" (see `Polarity`):
+//! ```ignore
+//! use gpio::{GpioPin, Input, Output, Polarity};
+//!
+//! let button = GpioPin::PortC(gpio::PC13);
+//! let button = Input::new_with_polarity(button, Polarity::ActiveLow)
+//!     .with_pullup();
+//! let relay = GpioPin::PortB(gpio::PB11);
+//! let mut relay = Output::new_with_polarity(relay, Polarity::ActiveLow);
+//! if button.is_high() {
+//!     relay.set_high(); // pressed: drive PB11 low to energize the relay
+//! }
+//! ```
+//!
+//! Scan a 4x4 matrix keypad with columns on PC0-PC3 (outputs, idle high)
+//! and rows on PC4-PC7 (inputs with pull-ups). Driving one column low pulls
+//! the rows of its pressed keys low, and one snapshot per column gives a
+//! consistent view of all four rows:
+//! ```ignore
+//! use gpio::{GpioPin, GpioPort};
+//!
+//! const COLS: [GpioPin; 4] = [
+//!     GpioPin::PortC(gpio::PC0), GpioPin::PortC(gpio::PC1),
+//!     GpioPin::PortC(gpio::PC2), GpioPin::PortC(gpio::PC3),
+//! ];
+//! const ROWS: [GpioPin; 4] = [
+//!     GpioPin::PortC(gpio::PC4), GpioPin::PortC(gpio::PC5),
+//!     GpioPin::PortC(gpio::PC6), GpioPin::PortC(gpio::PC7),
+//! ];
+//!
+//! // COLS configured with Direction::OutputHigh, ROWS with Pull::Up
+//! let mut pressed = 0u16; // bit (col * 4 + row)
+//! for (c, col) in COLS.iter().enumerate() {
+//!     gpio::clear(*col);
+//!     cycles::delay_ns(1_000); // let the row lines settle
+//!     let snapshot = gpio::snapshot_inputs(GpioPort::PortC);
+//!     gpio::set(*col);
+//!     for (r, row) in ROWS.iter().enumerate() {
+//!         if !gpio::pin_set_in_snapshot(snapshot, *row) {
+//!             pressed |= 1 << (c * 4 + r);
+//!         }
+//!     }
+//! }
+//! ```
+//!
 //! # API Design
 //!
 //! The public API consists of:
@@ -77,17 +225,88 @@
 //! - `disable_output()`: Configure pin as input
 //! - `enable_pullup()`: Enable internal pull-up
 //! - `disable_pullup()`: Disable internal pull-up
-//! - `read_input()`: Read current input state of a pin
+//! - `set_open_drain_low()`, `set_open_drain_release()`: Emulated
+//!   open-drain output (pull low, or release to high-Z)
+//! - `is_high()`, `is_low()`: Read current input state of a pin
+//! - `read_input()`: Same, as 1 or 0 (kept for compatibility)
+//! - `is_output()`: Check whether a pin's output driver is enabled
+//! - `get_output_state()`: Read back the value a pin is set to drive
 //! - `set_alternate_function()`: Configure pin for peripheral functions
+//! - `configure()`: Set alternate function, direction, and pull-up in one
+//!   call, from a `PinConfig`
+//! - `read_port()`: Read all input pins of a port at once
+//! - `snapshot_inputs()`, `pin_set_in_snapshot()`: Read a port once, then
+//!   decode pins from that one reading (e.g. for keypad scanning)
+//! - `write_port()`: Write several output pins of a port at once
+//! - `set_mask()`, `clear_mask()`, `toggle_mask()`: Change several output
+//!   pins of a port together
+//! - `Port`: Handle for a whole port, from `crate::take_peripherals()`
+//! - `set_drive_strength()`: Configure output drive current
+//! - `set_slew_rate()`: Select slow or fast output edges
+//! - `enable_schmitt()`: Enable input hysteresis
+//! - `disable_schmitt()`: Disable input hysteresis
+//! - `set_gpio_callback()`: Register a pin-change interrupt callback
+//! - `set_gpio_callback_ctx()`: Same, with a context pointer for C callers
+//! - `enable_interrupt()`: Enable pin-change interrupt on an edge
+//! - `disable_interrupt()`: Disable pin-change interrupt
+//! - `Output`, `Input`: Owned pin handles wrapping the functions above
+//! - `Polarity`: Active-high or active-low logic for `Output` and `Input`
+//! - `Led`: Output handle with LED helpers (on, off, toggle, blink)
+//! - `DebouncedInput`: Input handle that reports debounced edges
+//! - `DEBUG_LED`: The PB12 test LED pin, also lit on a panic or fatal trap
+//! - `try_claim()`, `release()`, `is_claimed()`: Reserve pins for a driver
+//!
+//! With the `embedded-hal` feature enabled, `Output` implements
+//! `embedded_hal::digital::OutputPin` and `Input` implements
+//! `embedded_hal::digital::InputPin`, so the handles can be passed to
+//! HAL-generic driver crates. The operations are infallible, so the error
+//! type is `core::convert::Infallible`. The trait methods follow the
+//! handle's `Polarity` like the inherent ones, so a driver calling
+//! `set_high()` on an `ActiveLow` output drives the pin low.
+
+use crate::interrupt::{self, Callback};
+use crate::irqarray::IRQARRAY10;
+use crate::mmio;
+use crate::pac;
+use crate::ticktimer;
+use core::ffi::c_void;
 
+#[derive(Clone, Copy)]
+pub struct PortAPin(u16);
+#[derive(Clone, Copy)]
 pub struct PortBPin(u16);
+#[derive(Clone, Copy)]
 pub struct PortCPin(u16);
 
+#[derive(Clone, Copy)]
 pub enum GpioPin {
+    PortA(PortAPin),
     PortB(PortBPin),
     PortC(PortCPin),
 }
 
+impl GpioPin {
+    /// Build a pin from integer port and pin numbers.
+    ///
+    /// Port 0 is port B, port 1 is port C, and port 2 is port A (numbered
+    /// last so that existing C callers keep their port numbers). Returns
+    /// None if the port is not 0-2, or the pin is not 0-15. This is meant
+    /// for adapting integer arguments (e.g. from C code); Rust code should
+    /// use the pin constants like `GpioPin::PortB(PB12)`.
+    pub fn from_numbers(port: u8, pin: u8) -> Option<GpioPin> {
+        if pin > 15 {
+            return None;
+        }
+        let mask = 1 << pin;
+        match port {
+            0 => Some(GpioPin::PortB(PortBPin(mask))),
+            1 => Some(GpioPin::PortC(PortCPin(mask))),
+            2 => Some(GpioPin::PortA(PortAPin(mask))),
+            _ => None,
+        }
+    }
+}
+
 pub enum AF {
     AF0 = 0, // GPIO (default)
     AF1 = 1, // UART2, I2C0, I2C1, CAM, SPIM2
@@ -95,20 +314,95 @@ pub enum AF {
     AF3 = 3, // Timer PWM outputs
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    Rising = 0,
+    Falling = 1,
+}
+
+/// Pin direction for `configure()`.
+///
+/// The output variants give the level to drive, which is written before
+/// the output driver is enabled so the pin doesn't glitch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Input,
+    OutputLow,
+    OutputHigh,
+}
+
+/// Internal pull resistor for `configure()`.
+///
+/// There is no `Down` variant because the hardware has no pull-down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pull {
+    None,
+    Up,
+}
+
+/// Complete pin setup, applied in one step by `configure()`.
+pub struct PinConfig {
+    pub af: AF,
+    pub direction: Direction,
+    pub pull: Pull,
+}
+
+pub enum DriveStrength {
+    Ma2 = 0,  // 2mA (default)
+    Ma4 = 1,  // 4mA
+    Ma8 = 2,  // 8mA
+    Ma12 = 3, // 12mA
+}
+
+// Every bit of ports B and C has a constant. Pins marked "no header pin" are
+// bao1x pads that the dabao board does not break out to its headers, so they
+// can't be wired to anything there. They are listed for completeness and for
+// use on other bao1x boards.
+// Port A has a full set of IOX registers, but none of its pins reach the
+// dabao headers. The register descriptions give all 16 bits without saying
+// which pads are bonded out on the package, so these are only useful on a
+// board that routes them.
+pub const PA0: PortAPin = PortAPin(1 << 0); // no header pin
+pub const PA1: PortAPin = PortAPin(1 << 1); // no header pin
+pub const PA2: PortAPin = PortAPin(1 << 2); // no header pin
+pub const PA3: PortAPin = PortAPin(1 << 3); // no header pin
+pub const PA4: PortAPin = PortAPin(1 << 4); // no header pin
+pub const PA5: PortAPin = PortAPin(1 << 5); // no header pin
+pub const PA6: PortAPin = PortAPin(1 << 6); // no header pin
+pub const PA7: PortAPin = PortAPin(1 << 7); // no header pin
+pub const PA8: PortAPin = PortAPin(1 << 8); // no header pin
+pub const PA9: PortAPin = PortAPin(1 << 9); // no header pin
+pub const PA10: PortAPin = PortAPin(1 << 10); // no header pin
+pub const PA11: PortAPin = PortAPin(1 << 11); // no header pin
+pub const PA12: PortAPin = PortAPin(1 << 12); // no header pin
+pub const PA13: PortAPin = PortAPin(1 << 13); // no header pin
+pub const PA14: PortAPin = PortAPin(1 << 14); // no header pin
+pub const PA15: PortAPin = PortAPin(1 << 15); // no header pin
+
+pub const PB0: PortBPin = PortBPin(1 << 0); // no header pin
 pub const PB1: PortBPin = PortBPin(1 << 1);
 pub const PB2: PortBPin = PortBPin(1 << 2);
 pub const PB3: PortBPin = PortBPin(1 << 3);
 pub const PB4: PortBPin = PortBPin(1 << 4);
 pub const PB5: PortBPin = PortBPin(1 << 5);
+pub const PB6: PortBPin = PortBPin(1 << 6); // no header pin
+pub const PB7: PortBPin = PortBPin(1 << 7); // no header pin
+pub const PB8: PortBPin = PortBPin(1 << 8); // no header pin
+pub const PB9: PortBPin = PortBPin(1 << 9); // no header pin
+pub const PB10: PortBPin = PortBPin(1 << 10); // no header pin
 pub const PB11: PortBPin = PortBPin(1 << 11);
 pub const PB12: PortBPin = PortBPin(1 << 12);
 pub const PB13: PortBPin = PortBPin(1 << 13);
 pub const PB14: PortBPin = PortBPin(1 << 14);
+pub const PB15: PortBPin = PortBPin(1 << 15); // no header pin
 
 pub const PC0: PortCPin = PortCPin(1 << 0);
 pub const PC1: PortCPin = PortCPin(1 << 1);
 pub const PC2: PortCPin = PortCPin(1 << 2);
 pub const PC3: PortCPin = PortCPin(1 << 3);
+pub const PC4: PortCPin = PortCPin(1 << 4); // no header pin
+pub const PC5: PortCPin = PortCPin(1 << 5); // no header pin
+pub const PC6: PortCPin = PortCPin(1 << 6); // no header pin
 pub const PC7: PortCPin = PortCPin(1 << 7);
 pub const PC8: PortCPin = PortCPin(1 << 8);
 pub const PC9: PortCPin = PortCPin(1 << 9);
@@ -116,50 +410,121 @@ pub const PC10: PortCPin = PortCPin(1 << 10);
 pub const PC11: PortCPin = PortCPin(1 << 11);
 pub const PC12: PortCPin = PortCPin(1 << 12);
 pub const PC13: PortCPin = PortCPin(1 << 13); // PROG button on dabao
+pub const PC14: PortCPin = PortCPin(1 << 14); // no header pin
+pub const PC15: PortCPin = PortCPin(1 << 15); // no header pin
 
-enum GpioPort {
-    PortB = 0,
-    PortC = 4,
+#[derive(Clone, Copy)]
+pub enum GpioPort {
+    PortA = 0,
+    PortB = 4,
+    PortC = 8,
 }
 
-// GPIO register base addresses
+// GPIO register base addresses (see the table at pac::IOX_BASE)
 //
 // Each register is accessed via BASE_ADDRESS + GpioPort offset.
-// GpioPort::PortB = 0, GpioPort::PortC = 4
-//
-// Register    | Port B      | Port C
-// ------------|-------------|-------------
-// GPIOOUT     | 0x5012f134  | 0x5012f138
-// GPIOOE      | 0x5012f14c  | 0x5012f150
-// GPIOPU      | 0x5012f164  | 0x5012f168
-// GPIOIN      | 0x5012f17c  | 0x5012f180
-
-const GPIOOUT_BASE: *mut u16 = 0x5012f134 as *mut u16;
-const GPIOOE_BASE: *mut u16 = 0x5012f14c as *mut u16;
-const GPIOPU_BASE: *mut u16 = 0x5012f164 as *mut u16;
-const GPIOIN_BASE: *mut u16 = 0x5012f17c as *mut u16;
+// GpioPort::PortA = 0, GpioPort::PortB = 4, GpioPort::PortC = 8
+
+const GPIOOUT_BASE: usize = pac::IOX_BASE + 0x130;
+const GPIOOE_BASE: usize = pac::IOX_BASE + 0x148;
+const GPIOPU_BASE: usize = pac::IOX_BASE + 0x160;
+const GPIOIN_BASE: usize = pac::IOX_BASE + 0x178;
+
+// Pad configuration registers (32-bit, same GpioPort offsets as above)
+const GPIOCFG_SCHM_BASE: usize = pac::IOX_BASE + 0x230;
+const GPIOCFG_RATCLR_BASE: usize = pac::IOX_BASE + 0x248;
+const GPIOCFG_DRVSEL_BASE: usize = pac::IOX_BASE + 0x260;
+
+// Interrupt channel registers
+const INTCR_BASE: usize = pac::IOX_BASE + 0x100; // INTCR0..INTCR7
+const INTFR: usize = pac::IOX_BASE + 0x120;
+const INT_CHANNELS: usize = 8;
+const INTCR_MODE_SHIFT: u32 = 7;
+const INTCR_EN: u32 = 1 << 9;
+
+// IOXIRQ event in IRQARRAY10
+const IOXIRQ_BIT: u32 = pac::IRQARRAY10_IOXIRQ;
 
 // Alternate function select registers
-const AFSELBL: *mut u16 = 0x5012f008 as *mut u16;
-const AFSELBH: *mut u16 = 0x5012f00c as *mut u16;
-const AFSELCL: *mut u16 = 0x5012f010 as *mut u16;
-const AFSELCH: *mut u16 = 0x5012f014 as *mut u16;
+const AFSELAL: usize = pac::IOX_BASE;
+const AFSELAH: usize = pac::IOX_BASE + 0x004;
+const AFSELBL: usize = pac::IOX_BASE + 0x008;
+const AFSELBH: usize = pac::IOX_BASE + 0x00c;
+const AFSELCL: usize = pac::IOX_BASE + 0x010;
+const AFSELCH: usize = pac::IOX_BASE + 0x014;
+
+// ============================================================================
+// Interrupt Channel State
+// ============================================================================
+
+// INT_CHANNEL_PIN[i] holds the INTCR pin select value bound to channel i,
+// or INT_CHANNEL_FREE if the channel is unused.
+const INT_CHANNEL_FREE: u8 = 0xff;
+static mut INT_CHANNEL_PIN: [u8; INT_CHANNELS] = [INT_CHANNEL_FREE; 8];
+static mut INT_CALLBACKS: [Option<Callback>; INT_CHANNELS] = [None; 8];
+
+// ============================================================================
+// Pin Claim State
+// ============================================================================
+
+// One bit per claimed pin, indexed by claim_index()
+static mut CLAIMED: [u16; 3] = [0; 3];
 
 // ============================================================================
 // Helper Functions
 // ============================================================================
 
-fn register_addr(base: *mut u16, port: GpioPort) -> *mut u16 {
-    (base as usize + port as usize) as *mut u16
+fn claim_index(port: GpioPort) -> usize {
+    match port {
+        GpioPort::PortA => 0,
+        GpioPort::PortB => 1,
+        GpioPort::PortC => 2,
+    }
+}
+
+// Panic (in debug builds) if a driver has claimed this pin
+#[inline]
+fn debug_check_unclaimed(pin: GpioPin) {
+    debug_assert!(!is_claimed(pin), "gpio: pin is claimed by a driver");
+}
+
+fn register_addr(base: usize, port: GpioPort) -> usize {
+    base + port as usize
 }
 
 fn gpio_pin_to_parts(pin: GpioPin) -> (GpioPort, u16) {
     match pin {
+        GpioPin::PortA(PortAPin(mask)) => (GpioPort::PortA, mask),
         GpioPin::PortB(PortBPin(mask)) => (GpioPort::PortB, mask),
         GpioPin::PortC(PortCPin(mask)) => (GpioPort::PortC, mask),
     }
 }
 
+fn intcr_pin_select(port: GpioPort, mask: u16) -> u8 {
+    let port_num = match port {
+        GpioPort::PortA => 0,
+        GpioPort::PortB => 1,
+        GpioPort::PortC => 2,
+    };
+    port_num * 16 + pin_number_from_mask(mask)
+}
+
+// Find the interrupt channel bound to a pin, optionally binding a free one
+fn int_channel(select: u8, allocate: bool) -> Option<usize> {
+    unsafe {
+        let pins = &raw mut INT_CHANNEL_PIN;
+        if let Some(ch) = (*pins).iter().position(|&p| p == select) {
+            return Some(ch);
+        }
+        if !allocate {
+            return None;
+        }
+        let ch = (*pins).iter().position(|&p| p == INT_CHANNEL_FREE)?;
+        (*pins)[ch] = select;
+        Some(ch)
+    }
+}
+
 fn pin_number_from_mask(mask: u16) -> u8 {
     // Find which bit is set in the mask (assumes only one bit set)
     for i in 0..16 {
@@ -170,6 +535,23 @@ fn pin_number_from_mask(mask: u16) -> u8 {
     0
 }
 
+// Read-modify-write GPIOOUT with interrupts disabled, because the IOX block
+// has no atomic set/clear registers. `f` maps (current value, pin mask) to
+// the new register value.
+#[inline]
+fn modify_output(pin: GpioPin, f: impl FnOnce(u16, u16) -> u16) {
+    let (port, mask) = gpio_pin_to_parts(pin);
+    modify_port(port, |current| f(current, mask));
+}
+
+// Read-modify-write a whole port's GPIOOUT with interrupts disabled
+#[inline]
+fn modify_port(port: GpioPort, f: impl FnOnce(u16) -> u16) {
+    let addr = register_addr(GPIOOUT_BASE, port);
+    let _cs = interrupt::critical_section();
+    unsafe { mmio::write16(addr, f(mmio::read16(addr))) }
+}
+
 // ============================================================================
 // Public API - GPIO Output Control
 // ============================================================================
@@ -181,17 +563,12 @@ fn pin_number_from_mask(mask: u16) -> u8 {
 ///
 /// # Safety
 ///
-/// This function is safe to call because the firmware runs single-threaded.
-/// Concurrent GPIO access from multiple threads would cause data races, but
-/// that is not possible in this environment.
+/// This function is safe to call because the firmware runs single-threaded
+/// and the read-modify-write of GPIOOUT runs with interrupts disabled, so
+/// an interrupt handler that writes the same port cannot be interleaved.
 #[inline]
 pub fn set(pin: GpioPin) {
-    unsafe {
-        let (port, mask) = gpio_pin_to_parts(pin);
-        let addr = register_addr(GPIOOUT_BASE, port);
-        let current = core::ptr::read_volatile(addr);
-        core::ptr::write_volatile(addr, current | mask);
-    }
+    modify_output(pin, |current, mask| current | mask);
 }
 
 /// Set pin output low.
@@ -201,17 +578,12 @@ pub fn set(pin: GpioPin) {
 ///
 /// # Safety
 ///
-/// This function is safe to call because the firmware runs single-threaded.
-/// Concurrent GPIO access from multiple threads would cause data races, but
-/// that is not possible in this environment.
+/// This function is safe to call because the firmware runs single-threaded
+/// and the read-modify-write of GPIOOUT runs with interrupts disabled, so
+/// an interrupt handler that writes the same port cannot be interleaved.
 #[inline]
 pub fn clear(pin: GpioPin) {
-    unsafe {
-        let (port, mask) = gpio_pin_to_parts(pin);
-        let addr = register_addr(GPIOOUT_BASE, port);
-        let current = core::ptr::read_volatile(addr);
-        core::ptr::write_volatile(addr, current & !mask);
-    }
+    modify_output(pin, |current, mask| current & !mask);
 }
 
 /// Toggle pin output state.
@@ -222,16 +594,168 @@ pub fn clear(pin: GpioPin) {
 ///
 /// # Safety
 ///
+/// This function is safe to call because the firmware runs single-threaded
+/// and the read-modify-write of GPIOOUT runs with interrupts disabled, so
+/// an interrupt handler that writes the same port cannot be interleaved.
+#[inline]
+pub fn toggle(pin: GpioPin) {
+    modify_output(pin, |current, mask| current ^ mask);
+}
+
+// ============================================================================
+// Public API - Whole-Port Access
+// ============================================================================
+
+/// Read the input state of every pin in a port.
+///
+/// Returns the full 16-bit GPIOIN value for the port, where bit N reflects
+/// pin N. Only bits for pins configured as inputs are meaningful.
+///
+/// # Safety
+///
 /// This function is safe to call because the firmware runs single-threaded.
 /// Concurrent GPIO access from multiple threads would cause data races, but
 /// that is not possible in this environment.
 #[inline]
-pub fn toggle(pin: GpioPin) {
-    unsafe {
-        let (port, mask) = gpio_pin_to_parts(pin);
-        let addr = register_addr(GPIOOUT_BASE, port);
-        let current = core::ptr::read_volatile(addr);
-        core::ptr::write_volatile(addr, current ^ mask);
+pub fn read_port(port: GpioPort) -> u16 {
+    unsafe { mmio::read16(register_addr(GPIOIN_BASE, port)) }
+}
+
+/// Read a port's inputs once, for decoding with `pin_set_in_snapshot()`.
+///
+/// This is the same single GPIOIN read as `read_port()`. Decoding several
+/// pins from one snapshot means they all reflect the same instant, where
+/// separate `is_high()` calls could see a line change between reads (e.g.
+/// rows of a keypad scan, right after driving a column).
+#[inline]
+pub fn snapshot_inputs(port: GpioPort) -> u16 {
+    read_port(port)
+}
+
+/// Return true if `pin` was high in `snapshot`.
+///
+/// `snapshot` must come from `snapshot_inputs()` (or `read_port()`) for
+/// the port `pin` is on. The snapshot is a plain `u16`, so a pin from
+/// another port can't be detected and just tests the same bit number.
+#[inline]
+pub fn pin_set_in_snapshot(snapshot: u16, pin: GpioPin) -> bool {
+    snapshot & gpio_pin_to_parts(pin).1 != 0
+}
+
+/// Write several output pins of a port with one register write.
+///
+/// Pins whose bit is set in `mask` take the corresponding bit of `value`.
+/// Pins outside `mask` keep their current output state. This is useful for
+/// parallel buses where all data lines should change together, e.g. to put
+/// a byte on PC0-PC7:
+/// ```ignore
+/// write_port(GpioPort::PortC, byte as u16, 0x00ff);
+/// ```
+///
+/// # Safety
+///
+/// This function is safe to call because the firmware runs single-threaded
+/// and the read-modify-write of GPIOOUT runs with interrupts disabled, so
+/// an interrupt handler that writes the same port cannot be interleaved.
+#[inline]
+pub fn write_port(port: GpioPort, value: u16, mask: u16) {
+    modify_port(port, |current| (current & !mask) | (value & mask));
+}
+
+/// Drive every pin in `mask` high with one register write.
+///
+/// Bit N of `mask` selects pin N of `port`. All selected pins change at
+/// the same moment, e.g. to energize two stepper coils on PC0 and PC1:
+/// ```ignore
+/// set_mask(GpioPort::PortC, 0b0011);
+/// ```
+///
+/// # Safety
+///
+/// This function is safe to call because the firmware runs single-threaded
+/// and the read-modify-write of GPIOOUT runs with interrupts disabled, so
+/// an interrupt handler that writes the same port cannot be interleaved.
+#[inline]
+pub fn set_mask(port: GpioPort, mask: u16) {
+    modify_port(port, |current| current | mask);
+}
+
+/// Drive every pin in `mask` low with one register write.
+///
+/// Bit N of `mask` selects pin N of `port`. Pins outside `mask` keep their
+/// current output state.
+///
+/// # Safety
+///
+/// This function is safe to call because the firmware runs single-threaded
+/// and the read-modify-write of GPIOOUT runs with interrupts disabled, so
+/// an interrupt handler that writes the same port cannot be interleaved.
+#[inline]
+pub fn clear_mask(port: GpioPort, mask: u16) {
+    modify_port(port, |current| current & !mask);
+}
+
+/// Toggle every pin in `mask` with one register write.
+///
+/// Bit N of `mask` selects pin N of `port`. Pins outside `mask` keep their
+/// current output state.
+///
+/// # Safety
+///
+/// This function is safe to call because the firmware runs single-threaded
+/// and the read-modify-write of GPIOOUT runs with interrupts disabled, so
+/// an interrupt handler that writes the same port cannot be interleaved.
+#[inline]
+pub fn toggle_mask(port: GpioPort, mask: u16) {
+    modify_port(port, |current| current ^ mask);
+}
+
+/// Handle for one whole GPIO port, as returned in `crate::Peripherals`.
+///
+/// Wraps the whole-port functions above. Only `crate::take_peripherals()`
+/// creates these, so there is one handle per port.
+pub struct Port {
+    port: GpioPort,
+}
+
+impl Port {
+    pub(crate) const fn new(port: GpioPort) -> Self {
+        Port { port }
+    }
+
+    /// Return which port this handle is for.
+    pub fn port(&self) -> GpioPort {
+        self.port
+    }
+
+    /// Read the input state of every pin (see `read_port()`).
+    #[inline]
+    pub fn read(&self) -> u16 {
+        read_port(self.port)
+    }
+
+    /// Write the pins in `mask` to `value` (see `write_port()`).
+    #[inline]
+    pub fn write(&mut self, value: u16, mask: u16) {
+        write_port(self.port, value, mask);
+    }
+
+    /// Set the pins in `mask` high.
+    #[inline]
+    pub fn set_mask(&mut self, mask: u16) {
+        set_mask(self.port, mask);
+    }
+
+    /// Set the pins in `mask` low.
+    #[inline]
+    pub fn clear_mask(&mut self, mask: u16) {
+        clear_mask(self.port, mask);
+    }
+
+    /// Toggle the pins in `mask`.
+    #[inline]
+    pub fn toggle_mask(&mut self, mask: u16) {
+        toggle_mask(self.port, mask);
     }
 }
 
@@ -251,11 +775,11 @@ pub fn toggle(pin: GpioPin) {
 /// that is not possible in this environment.
 #[inline]
 pub fn enable_output(pin: GpioPin) {
+    debug_check_unclaimed(pin);
     unsafe {
         let (port, mask) = gpio_pin_to_parts(pin);
         let addr = register_addr(GPIOOE_BASE, port);
-        let current = core::ptr::read_volatile(addr);
-        core::ptr::write_volatile(addr, current | mask);
+        mmio::modify16(addr, 0, mask);
     }
 }
 
@@ -271,18 +795,19 @@ pub fn enable_output(pin: GpioPin) {
 /// that is not possible in this environment.
 #[inline]
 pub fn disable_output(pin: GpioPin) {
+    debug_check_unclaimed(pin);
     unsafe {
         let (port, mask) = gpio_pin_to_parts(pin);
         let addr = register_addr(GPIOOE_BASE, port);
-        let current = core::ptr::read_volatile(addr);
-        core::ptr::write_volatile(addr, current & !mask);
+        mmio::modify16(addr, mask, 0);
     }
 }
 
 /// Enable internal pull-up on this pin.
 ///
 /// The pull-up is only effective when the pin is configured as an input
-/// via `disable_output()`.
+/// via `disable_output()`. There is no matching pull-down function because
+/// the hardware does not support internal pull-down.
 ///
 /// # Safety
 ///
@@ -291,11 +816,11 @@ pub fn disable_output(pin: GpioPin) {
 /// that is not possible in this environment.
 #[inline]
 pub fn enable_pullup(pin: GpioPin) {
+    debug_check_unclaimed(pin);
     unsafe {
         let (port, mask) = gpio_pin_to_parts(pin);
         let addr = register_addr(GPIOPU_BASE, port);
-        let current = core::ptr::read_volatile(addr);
-        core::ptr::write_volatile(addr, current | mask);
+        mmio::modify16(addr, 0, mask);
     }
 }
 
@@ -308,18 +833,18 @@ pub fn enable_pullup(pin: GpioPin) {
 /// that is not possible in this environment.
 #[inline]
 pub fn disable_pullup(pin: GpioPin) {
+    debug_check_unclaimed(pin);
     unsafe {
         let (port, mask) = gpio_pin_to_parts(pin);
         let addr = register_addr(GPIOPU_BASE, port);
-        let current = core::ptr::read_volatile(addr);
-        core::ptr::write_volatile(addr, current & !mask);
+        mmio::modify16(addr, mask, 0);
     }
 }
 
-/// Read the input state of a pin.
+/// Return true if the pin reads high.
 ///
-/// Returns 1 if the pin is high, 0 if the pin is low. Only meaningful for
-/// pins configured as inputs via `disable_output()`.
+/// Only meaningful for pins configured as inputs via `disable_output()`
+/// (for an output, it reads back the level on the pad).
 ///
 /// # Safety
 ///
@@ -327,15 +852,92 @@ pub fn disable_pullup(pin: GpioPin) {
 /// Concurrent GPIO access from multiple threads would cause data races, but
 /// that is not possible in this environment.
 #[inline]
-pub fn read_input(pin: GpioPin) -> u16 {
+pub fn is_high(pin: GpioPin) -> bool {
     unsafe {
         let (port, mask) = gpio_pin_to_parts(pin);
         let addr = register_addr(GPIOIN_BASE, port);
-        let value = core::ptr::read_volatile(addr);
-        if (value & mask) != 0 { 1 } else { 0 }
+        (mmio::read16(addr) & mask) != 0
+    }
+}
+
+/// Return true if the pin reads low.
+///
+/// The opposite of `is_high()`, for active-low inputs such as buttons with
+/// pull-ups: `while gpio::is_low(button) { ... }`.
+#[inline]
+pub fn is_low(pin: GpioPin) -> bool {
+    !is_high(pin)
+}
+
+/// Read the input state of a pin as a number.
+///
+/// Returns 1 if the pin is high, 0 if the pin is low. Kept for existing
+/// callers. New code should use `is_high()` or `is_low()`.
+#[inline]
+pub fn read_input(pin: GpioPin) -> u16 {
+    is_high(pin) as u16
+}
+
+/// Return true if the pin's output driver is enabled.
+///
+/// Reads the pin's bit in GPIOOE, as set by `enable_output()` and cleared
+/// by `disable_output()`. This does not check the alternate function, so a
+/// pin can report true here while a peripheral (AF1-AF3) controls it.
+///
+/// # Safety
+///
+/// This function is safe to call because the firmware runs single-threaded.
+/// Concurrent GPIO access from multiple threads would cause data races, but
+/// that is not possible in this environment.
+#[inline]
+pub fn is_output(pin: GpioPin) -> bool {
+    unsafe {
+        let (port, mask) = gpio_pin_to_parts(pin);
+        let addr = register_addr(GPIOOE_BASE, port);
+        (mmio::read16(addr) & mask) != 0
     }
 }
 
+/// Return true if the pin's output value is set high.
+///
+/// Reads the pin's bit in GPIOOUT, as set by `set()`, `clear()`, and
+/// `toggle()`. This is the value the pin drives when `is_output()` is true,
+/// not the level on the pad. Use `is_high()` for that.
+///
+/// # Safety
+///
+/// This function is safe to call because the firmware runs single-threaded.
+/// Concurrent GPIO access from multiple threads would cause data races, but
+/// that is not possible in this environment.
+#[inline]
+pub fn get_output_state(pin: GpioPin) -> bool {
+    unsafe {
+        let (port, mask) = gpio_pin_to_parts(pin);
+        let addr = register_addr(GPIOOUT_BASE, port);
+        (mmio::read16(addr) & mask) != 0
+    }
+}
+
+/// Pull an emulated open-drain pin low.
+///
+/// Writes 0 to the pin's GPIOOUT bit, then enables the output driver, so
+/// the pin goes straight from high-Z to low without driving high. The pin
+/// must already be set to AF0. See the module docs for why open-drain is
+/// emulated.
+pub fn set_open_drain_low(pin: GpioPin) {
+    clear(pin);
+    enable_output(pin);
+}
+
+/// Release an emulated open-drain pin to high-Z.
+///
+/// Disables the output driver, so a pull-up brings the line high unless
+/// another device holds it low. Read the actual line level with
+/// `is_high()`.
+pub fn set_open_drain_release(pin: GpioPin) {
+    disable_output(pin);
+}
+
 // ============================================================================
 // Public API - Alternate Function Selection
 // ============================================================================
@@ -358,6 +960,7 @@ pub fn read_input(pin: GpioPin) -> u16 {
 /// Concurrent GPIO access from multiple threads would cause data races, but
 /// that is not possible in this environment.
 pub fn set_alternate_function(pin: GpioPin, af: AF) {
+    debug_check_unclaimed(pin);
     unsafe {
         let (port, mask) = gpio_pin_to_parts(pin);
         let pin_num = pin_number_from_mask(mask);
@@ -365,6 +968,13 @@ pub fn set_alternate_function(pin: GpioPin, af: AF) {
 
         // Determine which AFSEL register to use
         let reg = match port {
+            GpioPort::PortA => {
+                if pin_num < 8 {
+                    AFSELAL
+                } else {
+                    AFSELAH
+                }
+            }
             GpioPort::PortB => {
                 if pin_num < 8 {
                     AFSELBL
@@ -381,14 +991,668 @@ pub fn set_alternate_function(pin: GpioPin, af: AF) {
             }
         };
 
-        // Clear the 2-bit field for this pin and write new value
-        let current = core::ptr::read_volatile(reg);
+        // Clear the 2-bit field for this pin and write new value. The mmio
+        // fence ensures the AF register is set before any GPIO
+        // configuration follows.
         let mask_2bit = 0b11u16 << bit_pos;
-        let new_val = (current & !mask_2bit) | ((af as u16) << bit_pos);
-        core::ptr::write_volatile(reg, new_val);
-        // Ensure AF register is set before any GPIO configuration follows
-        core::sync::atomic::compiler_fence(
-            core::sync::atomic::Ordering::SeqCst,
+        mmio::modify16(reg, mask_2bit, (af as u16) << bit_pos);
+    }
+}
+
+/// Apply a complete pin configuration in one call.
+///
+/// Replaces the usual sequence of `set_alternate_function()`,
+/// `enable_output()`/`disable_output()`, and `enable_pullup()`/
+/// `disable_pullup()`, doing the steps in an order that avoids glitches:
+///
+/// 1. Select the alternate function (this is fenced, so it lands before
+///    the remaining writes).
+/// 2. Set the pull-up, so an input doesn't float while it is switched.
+/// 3. For an input, disable the output driver. For an output, write the
+///    initial level first, then enable the output driver.
+///
+/// Example: PROG button input and LED output:
+/// ```ignore
+/// use gpio::{Direction, GpioPin, PinConfig, Pull, AF};
+///
+/// gpio::configure(GpioPin::PortC(gpio::PC13), PinConfig {
+///     af: AF::AF0,
+///     direction: Direction::Input,
+///     pull: Pull::Up,
+/// });
+/// gpio::configure(GpioPin::PortB(gpio::PB12), PinConfig {
+///     af: AF::AF0,
+///     direction: Direction::OutputLow,
+///     pull: Pull::None,
+/// });
+/// ```
+pub fn configure(pin: GpioPin, config: PinConfig) {
+    set_alternate_function(pin, config.af);
+    match config.pull {
+        Pull::None => disable_pullup(pin),
+        Pull::Up => enable_pullup(pin),
+    }
+    match config.direction {
+        Direction::Input => disable_output(pin),
+        Direction::OutputLow => {
+            clear(pin);
+            enable_output(pin);
+        }
+        Direction::OutputHigh => {
+            set(pin);
+            enable_output(pin);
+        }
+    }
+}
+
+// ============================================================================
+// Public API - Pin Claims
+// ============================================================================
+
+/// Proof that a pin is claimed, returned by `try_claim()`.
+///
+/// Not Clone or Copy, so there is only ever one token per claim. Pass it
+/// to `release()` to free the pin.
+pub struct PinToken {
+    pin: GpioPin,
+}
+
+impl PinToken {
+    /// Return the claimed pin.
+    pub fn pin(&self) -> GpioPin {
+        self.pin
+    }
+}
+
+/// Claim a pin for exclusive use by a driver.
+///
+/// Returns None if the pin is already claimed. See "Pin Claims" in the
+/// module docs for what a claim checks.
+pub fn try_claim(pin: GpioPin) -> Option<PinToken> {
+    let (port, mask) = gpio_pin_to_parts(pin);
+    let _cs = interrupt::critical_section();
+    unsafe {
+        let claimed = &raw mut CLAIMED;
+        let bits = &mut (*claimed)[claim_index(port)];
+        if *bits & mask != 0 {
+            return None;
+        }
+        *bits |= mask;
+    }
+    Some(PinToken { pin })
+}
+
+/// Release a claim made with `try_claim()`.
+pub fn release(token: PinToken) {
+    let (port, mask) = gpio_pin_to_parts(token.pin);
+    let _cs = interrupt::critical_section();
+    unsafe {
+        let claimed = &raw mut CLAIMED;
+        (*claimed)[claim_index(port)] &= !mask;
+    }
+}
+
+/// Return true if a driver has claimed this pin.
+pub fn is_claimed(pin: GpioPin) -> bool {
+    let (port, mask) = gpio_pin_to_parts(pin);
+    unsafe { CLAIMED[claim_index(port)] & mask != 0 }
+}
+
+// ============================================================================
+// Public API - Pad Configuration
+// ============================================================================
+
+/// Set the output drive strength for a pin.
+///
+/// Selects the output drive current (2mA, 4mA, 8mA, or 12mA) by writing
+/// the pin's 2-bit field in the GPIOCFG_DRVSEL register for its port. The
+/// reset default is 2mA. Higher drive strength gives faster edges into
+/// capacitive loads at the cost of more ringing and EMI.
+///
+/// Example: Drive a level shifter from PB12 at 12mA:
+/// ```ignore
+/// set_drive_strength(GpioPin::PortB(PB12), DriveStrength::Ma12);
+/// ```
+///
+/// # Safety
+///
+/// This function is safe to call because the firmware runs single-threaded.
+/// Concurrent GPIO access from multiple threads would cause data races, but
+/// that is not possible in this environment.
+pub fn set_drive_strength(pin: GpioPin, strength: DriveStrength) {
+    unsafe {
+        let (port, mask) = gpio_pin_to_parts(pin);
+        let pin_num = pin_number_from_mask(mask);
+        let bit_pos = (pin_num as u32) * 2;
+        let reg = register_addr(GPIOCFG_DRVSEL_BASE, port);
+
+        // Clear the 2-bit field for this pin and write new value
+        let mask_2bit = 0b11u32 << bit_pos;
+        mmio::modify32(reg, mask_2bit, (strength as u32) << bit_pos);
+    }
+}
+
+/// Enable or disable slew rate limiting for a pin.
+///
+/// When `slow` is true, sets the pin's bit in the GPIOCFG_RATCLR register
+/// so output transitions are rate-limited. This reduces ringing and EMI on
+/// fast-toggling lines. When `slow` is false, clears the bit to restore the
+/// default fast edges.
+///
+/// # Safety
+///
+/// This function is safe to call because the firmware runs single-threaded.
+/// Concurrent GPIO access from multiple threads would cause data races, but
+/// that is not possible in this environment.
+pub fn set_slew_rate(pin: GpioPin, slow: bool) {
+    unsafe {
+        let (port, mask) = gpio_pin_to_parts(pin);
+        let addr = register_addr(GPIOCFG_RATCLR_BASE, port);
+        if slow {
+            mmio::modify16(addr, 0, mask);
+        } else {
+            mmio::modify16(addr, mask, 0);
+        }
+    }
+}
+
+/// Enable the Schmitt trigger (input hysteresis) on this pin.
+///
+/// Hysteresis rejects noise on slow or bouncy input edges, such as a
+/// mechanical switch. Only meaningful when the pin is configured as an
+/// input via `disable_output()`.
+///
+/// # Safety
+///
+/// This function is safe to call because the firmware runs single-threaded.
+/// Concurrent GPIO access from multiple threads would cause data races, but
+/// that is not possible in this environment.
+pub fn enable_schmitt(pin: GpioPin) {
+    unsafe {
+        let (port, mask) = gpio_pin_to_parts(pin);
+        let addr = register_addr(GPIOCFG_SCHM_BASE, port);
+        mmio::modify16(addr, 0, mask);
+    }
+}
+
+/// Disable the Schmitt trigger (input hysteresis) on this pin.
+///
+/// Only meaningful when the pin is configured as an input via
+/// `disable_output()`.
+///
+/// # Safety
+///
+/// This function is safe to call because the firmware runs single-threaded.
+/// Concurrent GPIO access from multiple threads would cause data races, but
+/// that is not possible in this environment.
+pub fn disable_schmitt(pin: GpioPin) {
+    unsafe {
+        let (port, mask) = gpio_pin_to_parts(pin);
+        let addr = register_addr(GPIOCFG_SCHM_BASE, port);
+        mmio::modify16(addr, mask, 0);
+    }
+}
+
+// ============================================================================
+// Public API - Pin-Change Interrupts
+// ============================================================================
+
+/// Register a callback for pin-change interrupts on this pin.
+///
+/// Binds the pin to one of the 8 IOX interrupt channels (if it isn't bound
+/// already) and stores the callback. The callback runs in interrupt context
+/// when the pin's trigger edge is detected, so keep it short. Call
+/// `enable_interrupt()` to start receiving interrupts.
+///
+/// Returns false if all 8 interrupt channels are bound to other pins.
+pub fn set_gpio_callback(pin: GpioPin, callback: fn()) -> bool {
+    store_callback(pin, Callback::Plain(callback))
+}
+
+/// Register a callback with a context pointer for pin-change interrupts.
+///
+/// Works like `set_gpio_callback()`, but `callback` is a C-ABI function
+/// that receives `ctx` each time it runs. `ctx` is opaque to this module
+/// and must stay valid while the callback is registered.
+///
+/// Returns false if all 8 interrupt channels are bound to other pins.
+pub fn set_gpio_callback_ctx(
+    pin: GpioPin,
+    callback: extern "C" fn(*mut c_void),
+    ctx: *mut c_void,
+) -> bool {
+    store_callback(pin, Callback::Context(callback, ctx))
+}
+
+// Bind the pin to an interrupt channel and store its callback
+fn store_callback(pin: GpioPin, callback: Callback) -> bool {
+    let (port, mask) = gpio_pin_to_parts(pin);
+    match int_channel(intcr_pin_select(port, mask), true) {
+        Some(ch) => {
+            unsafe {
+                INT_CALLBACKS[ch] = Some(callback);
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+/// Enable pin-change interrupts on this pin for the given edge.
+///
+/// Configures the pin's INTCR channel for the trigger edge and enables it,
+/// then enables the IOXIRQ event in IRQARRAY10. Register a callback with
+/// `set_gpio_callback()` first, or interrupts will be cleared and ignored.
+///
+/// Returns false if all 8 interrupt channels are bound to other pins.
+///
+/// # Safety
+///
+/// This function is safe to call because the firmware runs single-threaded.
+/// Concurrent GPIO access from multiple threads would cause data races, but
+/// that is not possible in this environment.
+pub fn enable_interrupt(pin: GpioPin, edge: Edge) -> bool {
+    let (port, mask) = gpio_pin_to_parts(pin);
+    let select = intcr_pin_select(port, mask);
+    let Some(ch) = int_channel(select, true) else {
+        return false;
+    };
+    unsafe {
+        // Clear any stale flag for this channel before enabling it
+        mmio::write32(INTFR, 1 << ch);
+        let value =
+            (select as u32) | ((edge as u32) << INTCR_MODE_SHIFT) | INTCR_EN;
+        mmio::write32(INTCR_BASE + ch * 4, value);
+    }
+    // Enable IOXIRQ event signalling in IRQARRAY10
+    IRQARRAY10.enable(IOXIRQ_BIT);
+    true
+}
+
+/// Disable pin-change interrupts on this pin.
+///
+/// Clears the enable bit of the pin's INTCR channel. The channel stays
+/// bound to the pin along with its callback, so `enable_interrupt()` can
+/// re-enable it later.
+///
+/// # Safety
+///
+/// This function is safe to call because the firmware runs single-threaded.
+/// Concurrent GPIO access from multiple threads would cause data races, but
+/// that is not possible in this environment.
+pub fn disable_interrupt(pin: GpioPin) {
+    let (port, mask) = gpio_pin_to_parts(pin);
+    if let Some(ch) = int_channel(intcr_pin_select(port, mask), false) {
+        unsafe {
+            mmio::modify32(INTCR_BASE + ch * 4, INTCR_EN, 0);
+            mmio::write32(INTFR, 1 << ch);
+        }
+    }
+}
+
+/// Clear and return the pending interrupt channel flags (for interrupt
+/// handler use)
+pub(crate) fn take_pending_interrupts() -> u32 {
+    unsafe {
+        let flags = mmio::read32(INTFR) & 0xff;
+        mmio::write32(INTFR, flags); // write 1 to clear!
+        IRQARRAY10.clear_pending(IOXIRQ_BIT);
+        flags
+    }
+}
+
+/// Retrieve the callback for an interrupt channel (for interrupt handler
+/// use)
+pub(crate) fn get_callback(channel: usize) -> Option<Callback> {
+    unsafe { INT_CALLBACKS[channel] }
+}
+
+// ============================================================================
+// Public API - Owned Pin Handles
+// ============================================================================
+
+/// Logical polarity of an owned pin handle
+///
+/// With `ActiveLow`, the `Output` and `Input` handles invert the physical
+/// level, so `set_high()` and `is_high()` mean "active" (e.g. relay
+/// energized, button pressed) whichever way the circuit is wired.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Polarity {
+    /// High level is active (the default)
+    #[default]
+    ActiveHigh,
+    /// Low level is active, e.g. a button to GND with a pull-up
+    ActiveLow,
+}
+
+/// GPIO output pin handle.
+///
+/// Wraps a `GpioPin` that has been configured as a GPIO output. The methods
+/// call the free functions of this module, so the two styles can be mixed.
+/// The level methods work in logical terms (see `Polarity`).
+pub struct Output {
+    pin: GpioPin,
+    polarity: Polarity,
+}
+
+impl Output {
+    /// Configure a pin as a GPIO output, initially driven low.
+    ///
+    /// Selects AF0, clears the output value, then enables the output
+    /// driver so the pin doesn't glitch high during setup.
+    pub fn new(pin: GpioPin) -> Self {
+        Self::new_with_polarity(pin, Polarity::ActiveHigh)
+    }
+
+    /// Configure a pin as a GPIO output, initially inactive.
+    ///
+    /// Like `new()`, but for `Polarity::ActiveLow` the pin starts out
+    /// driven high, so an active-low load (like a relay board) is never
+    /// switched on during setup. `set_high()` then drives it low.
+    pub fn new_with_polarity(pin: GpioPin, polarity: Polarity) -> Self {
+        let direction = match polarity {
+            Polarity::ActiveHigh => Direction::OutputLow,
+            Polarity::ActiveLow => Direction::OutputHigh,
+        };
+        configure(pin, PinConfig { af: AF::AF0, direction, pull: Pull::None });
+        Output { pin, polarity }
+    }
+
+    /// Return the polarity this handle was created with.
+    #[inline]
+    pub fn polarity(&self) -> Polarity {
+        self.polarity
+    }
+
+    /// Make the pin active (drive it high, or low if active-low).
+    #[inline]
+    pub fn set_high(&mut self) {
+        match self.polarity {
+            Polarity::ActiveHigh => set(self.pin),
+            Polarity::ActiveLow => clear(self.pin),
+        }
+    }
+
+    /// Make the pin inactive (drive it low, or high if active-low).
+    #[inline]
+    pub fn set_low(&mut self) {
+        match self.polarity {
+            Polarity::ActiveHigh => clear(self.pin),
+            Polarity::ActiveLow => set(self.pin),
+        }
+    }
+
+    /// Toggle the pin output state.
+    #[inline]
+    pub fn toggle(&mut self) {
+        toggle(self.pin);
+    }
+}
+
+/// The usual test LED pin, which the panic handler and fatal traps light.
+///
+/// The dabao board has no built-in LED, so this is where the examples
+/// expect an external LED (wired to GND through a 330Ω or 470Ω resistor).
+pub const DEBUG_LED: GpioPin = GpioPin::PortB(PB12);
+
+/// LED on a GPIO output pin.
+///
+/// A thin convenience over the free functions for the common case of an
+/// LED wired from the pin to GND, so on() drives the pin high.
+///
+/// ```ignore
+/// use gpio::{DEBUG_LED, Led};
+///
+/// let mut led = Led::on_pin(DEBUG_LED);
+/// led.blink_blocking(500, 3);
+/// ```
+pub struct Led {
+    pin: GpioPin,
+}
+
+impl Led {
+    /// Configure `pin` as a GPIO output for an LED, leaving its level as
+    /// it was.
+    ///
+    /// Selects AF0 and enables the output driver. Unlike `Output::new()`,
+    /// this does not clear the pin first, so an LED that is already lit
+    /// stays lit.
+    pub fn on_pin(pin: GpioPin) -> Self {
+        set_alternate_function(pin, AF::AF0);
+        enable_output(pin);
+        Led { pin }
+    }
+
+    /// Turn the LED on (drive the pin high).
+    #[inline]
+    pub fn on(&mut self) {
+        set(self.pin);
+    }
+
+    /// Turn the LED off (drive the pin low).
+    #[inline]
+    pub fn off(&mut self) {
+        clear(self.pin);
+    }
+
+    /// Toggle the LED.
+    #[inline]
+    pub fn toggle(&mut self) {
+        toggle(self.pin);
+    }
+
+    /// Blink `count` times, `period_ms` per on/off cycle, then leave the
+    /// LED off.
+    ///
+    /// Each cycle is on for half the period and off for the other half.
+    /// Blocks for `count * period_ms` milliseconds, waiting with
+    /// `crate::sleep()` so UART TX keeps being serviced.
+    pub fn blink_blocking(&mut self, period_ms: u32, count: u32) {
+        let on_ms = period_ms / 2;
+        for _ in 0..count {
+            self.on();
+            crate::sleep(on_ms);
+            self.off();
+            crate::sleep(period_ms - on_ms);
+        }
+    }
+}
+
+/// GPIO input pin handle.
+///
+/// Wraps a `GpioPin` that has been configured as a GPIO input. The methods
+/// call the free functions of this module, so the two styles can be mixed.
+/// The level methods work in logical terms (see `Polarity`).
+pub struct Input {
+    pin: GpioPin,
+    polarity: Polarity,
+}
+
+impl Input {
+    /// Configure a pin as a GPIO input with the pull-up disabled.
+    pub fn new(pin: GpioPin) -> Self {
+        Self::new_with_polarity(pin, Polarity::ActiveHigh)
+    }
+
+    /// Configure a pin as a GPIO input with the given polarity.
+    ///
+    /// For an active-low button, `is_high()` returns true while it is
+    /// pressed (pulling the pin low).
+    pub fn new_with_polarity(pin: GpioPin, polarity: Polarity) -> Self {
+        configure(
+            pin,
+            PinConfig {
+                af: AF::AF0,
+                direction: Direction::Input,
+                pull: Pull::None,
+            },
         );
+        Input { pin, polarity }
+    }
+
+    /// Return the polarity this handle was created with.
+    #[inline]
+    pub fn polarity(&self) -> Polarity {
+        self.polarity
+    }
+
+    /// Enable the internal pull-up (builder style).
+    pub fn with_pullup(self) -> Self {
+        enable_pullup(self.pin);
+        self
+    }
+
+    /// Return true if the pin is active (reads high, or low if
+    /// active-low).
+    #[inline]
+    pub fn is_high(&self) -> bool {
+        is_high(self.pin) != (self.polarity == Polarity::ActiveLow)
+    }
+
+    /// Return true if the pin is inactive.
+    #[inline]
+    pub fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+}
+
+/// Debounced GPIO input, for mechanical buttons and switches.
+///
+/// Samples the pin each time update() is called and reports an edge only
+/// once the new level has held for the whole debounce window, measured
+/// with `ticktimer::millis()`. Contact bounce shorter than the window is
+/// ignored. Call update() often (every few ms or faster), e.g. from the
+/// main loop.
+///
+/// ```ignore
+/// use gpio::{DebouncedInput, Edge, GpioPin};
+///
+/// // PROG button: active low with pull-up, 10ms debounce
+/// let mut button =
+///     DebouncedInput::new(GpioPin::PortC(gpio::PC13), 10).with_pullup();
+/// loop {
+///     if button.update() == Some(Edge::Falling) {
+///         // Button pressed
+///     }
+///     uart::tick();
+/// }
+/// ```
+pub struct DebouncedInput {
+    pin: GpioPin,
+    window_ms: u32,
+    stable: bool,    // Debounced level
+    candidate: bool, // Last sampled level
+    since_ms: u64,   // millis() when the pin last changed level
+}
+
+impl DebouncedInput {
+    /// Configure `pin` as a GPIO input with the pull-up disabled, debounced
+    /// over `window_ms` milliseconds.
+    ///
+    /// The current pin level is taken as the starting debounced level, so
+    /// the first update() does not report an edge.
+    pub fn new(pin: GpioPin, window_ms: u32) -> Self {
+        set_alternate_function(pin, AF::AF0);
+        disable_output(pin);
+        disable_pullup(pin);
+        let level = is_high(pin);
+        DebouncedInput {
+            pin,
+            window_ms,
+            stable: level,
+            candidate: level,
+            since_ms: ticktimer::millis(),
+        }
+    }
+
+    /// Enable the internal pull-up (builder style).
+    ///
+    /// Also re-reads the starting level, since enabling the pull-up can
+    /// change it.
+    pub fn with_pullup(mut self) -> Self {
+        enable_pullup(self.pin);
+        let level = is_high(self.pin);
+        self.stable = level;
+        self.candidate = level;
+        self.since_ms = ticktimer::millis();
+        self
+    }
+
+    /// Sample the pin, returning an edge once a new level has been stable
+    /// for the debounce window.
+    ///
+    /// Returns `Some(Edge::Rising)` or `Some(Edge::Falling)` exactly once
+    /// per debounced change, and None otherwise.
+    pub fn update(&mut self) -> Option<Edge> {
+        let level = is_high(self.pin);
+        let now = ticktimer::millis();
+        if level != self.candidate {
+            // Level changed (or bounced): restart the window
+            self.candidate = level;
+            self.since_ms = now;
+            return None;
+        }
+        if level == self.stable
+            || now.saturating_sub(self.since_ms) < self.window_ms as u64
+        {
+            return None;
+        }
+        self.stable = level;
+        Some(if level { Edge::Rising } else { Edge::Falling })
+    }
+
+    /// Return true if the debounced level is high.
+    #[inline]
+    pub fn is_high(&self) -> bool {
+        self.stable
+    }
+
+    /// Return true if the debounced level is low.
+    #[inline]
+    pub fn is_low(&self) -> bool {
+        !self.stable
+    }
+}
+
+// ============================================================================
+// embedded-hal Traits (feature = "embedded-hal")
+// ============================================================================
+
+#[cfg(feature = "embedded-hal")]
+mod hal {
+    use super::{Input, Output};
+    use core::convert::Infallible;
+    use embedded_hal::digital::{ErrorType, InputPin, OutputPin};
+
+    impl ErrorType for Output {
+        type Error = Infallible;
+    }
+
+    // Logical levels, like the inherent methods (see `Polarity`)
+    impl OutputPin for Output {
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            Output::set_low(self);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            Output::set_high(self);
+            Ok(())
+        }
+    }
+
+    impl ErrorType for Input {
+        type Error = Infallible;
+    }
+
+    // Logical levels, like the inherent methods (see `Polarity`)
+    impl InputPin for Input {
+        fn is_high(&mut self) -> Result<bool, Infallible> {
+            Ok(Input::is_high(self))
+        }
+
+        fn is_low(&mut self) -> Result<bool, Infallible> {
+            Ok(Input::is_low(self))
+        }
     }
 }
@@ -0,0 +1,424 @@
+// SPDX-License-Identifier: MIT
+// SPDX-FileCopyrightText: Copyright 2026 Sam Blenny
+//
+//! I2C master driver for bao1x dabao evaluation board
+//!
+//! Provides blocking, DMA-based I2C master transfers for talking to sensors
+//! and other simple peripherals. One bus is active at a time, selected by
+//! init().
+//!
+//! # Usage
+//!
+//! Configure the bus pins as alternate function AF1 with pull-ups, then
+//! initialize the bus and read a register from a sensor:
+//!
+//! ```ignore
+//! use baochip_sdk::{gpio, i2c};
+//! use gpio::{AF, GpioPin};
+//! use i2c::I2cBus;
+//!
+//! // Replace these with the SCL and SDA pads for the bus (see below)
+//! let scl = GpioPin::PortB(gpio::PB11);
+//! let sda = GpioPin::PortB(gpio::PB12);
+//! for pin in [scl, sda] {
+//!     gpio::set_alternate_function(pin, AF::AF1);
+//!     gpio::enable_pullup(pin);
+//! }
+//!
+//! i2c::init(I2cBus::I2c0, 100_000);
+//!
+//! // Read 2 bytes from register 0x00 of a sensor at address 0x48
+//! let mut temp = [0u8; 2];
+//! match i2c::write_read(0x48, &[0x00], &mut temp) {
+//!     Ok(()) => { /* use temp */ }
+//!     Err(i2c::I2cError::Nack) => { /* no device at 0x48 */ }
+//!     Err(_) => { /* bus problem */ }
+//! }
+//! ```
+//!
+//! # Hardware Details
+//!
+//! The bao1x has four uDMA I2C controllers (I2C0-I2C3). Their SDA and SCL
+//! signals reach the GPIO pads through the IOX alternate function mux:
+//! - I2C0, I2C1: alternate function AF1 (see the `AF` enum in gpio)
+//! - I2C2, I2C3: not listed for any alternate function in the AF table
+//!
+//! Unlike UART2 on PB13/PB14, the pad assignment for each I2C bus is not
+//! recorded in the register descriptions this SDK is built from, so this
+//! module does not configure pins itself. Check the bao1x pinout for the
+//! SDA/SCL pads of the bus you want, then set both pads to AF1 before
+//! calling init(). I2C is open-drain, so the bus needs pull-up resistors.
+//! The internal pull-ups (`gpio::enable_pullup()`) are weak. They are fine
+//! for short wires at 100 kHz, but add external 2.2k-4.7k resistors for
+//! 400 kHz or longer wires.
+//!
+//! # Register Map
+//!
+//! Each controller has the same register layout at its own base address:
+//! - I2C0: 0x50109000
+//! - I2C1: 0x5010a000
+//! - I2C2: 0x5010b000
+//! - I2C3: 0x5010c000
+//!
+//! | Offset | Register  | Use                                      |
+//! |--------|-----------|------------------------------------------|
+//! | 0x00   | RX_SADDR  | RX DMA buffer address (0 when done)      |
+//! | 0x04   | RX_SIZE   | RX DMA length in bytes                   |
+//! | 0x08   | RX_CFG    | Bit 4: start RX DMA, bit 6: clear        |
+//! | 0x10   | TX_SADDR  | TX DMA buffer address                    |
+//! | 0x14   | TX_SIZE   | TX DMA length in bytes                   |
+//! | 0x18   | TX_CFG    | Bit 4: start TX DMA, bit 6: clear        |
+//! | 0x20   | CMD_SADDR | Command buffer address                   |
+//! | 0x24   | CMD_SIZE  | Command buffer length in bytes           |
+//! | 0x28   | CMD_CFG   | Bit 4: start command DMA, bit 6: clear   |
+//! | 0x30   | STATUS    | Bit 0: busy, bit 1: arbitration lost     |
+//! | 0x38   | ACK       | Bit 0: last byte was NACKed              |
+//!
+//! Each bus is clocked by a uDMA clock gate bit (REG_CG bits 8-11 for
+//! I2C0-I2C3, base 0x50100000), which init() turns on.
+//!
+//! # Command Stream
+//!
+//! The controller does not have START/STOP/data registers. Instead, it runs
+//! a list of 32-bit commands fetched by the command DMA channel. The opcode
+//! is in bits [31:28] and any argument is in the low bits:
+//! - 0x0 START: Send a start (or repeated start) condition
+//! - 0x2 STOP: Send a stop condition
+//! - 0x4 RD_ACK: Read a byte into the RX channel and ACK it
+//! - 0x6 RD_NACK: Read a byte into the RX channel and NACK it
+//! - 0x7 WRB: Write the byte in bits [7:0]
+//! - 0x8 WR: Write the next byte from the TX channel
+//! - 0xC RPT: Repeat the next command `n` times (n in bits [15:0])
+//! - 0xE CFG: Set the SCL clock divider (bits [15:0])
+//!
+//! The opcodes follow the PULP udma_i2c controller that the bao1x uDMA is
+//! based on, since they are not part of the register descriptions.
+//!
+//! # Clock Configuration
+//!
+//! The controllers are clocked by PERCLK (100 MHz). The CFG divider counts
+//! PERCLK cycles per quarter SCL period:
+//! `divider = PERCLK / (4 * baud) = 100_000_000 / (4 * 100_000) = 250`
+//!
+//! # DMA and Memory Layout
+//!
+//! DMA buffers live in IFRAM0, just above the UART and `serial` TX buffers
+//! (0x50000000 - 0x500007FF):
+//! - 0x50000800: Command buffer (64 bytes). The last word of this area,
+//!   0x500008FC, is the UART polled TX byte (see `uart::write_byte_blocking`)
+//! - 0x50000900: TX buffer (256 bytes)
+//! - 0x50000A00: RX buffer (256 bytes)
+//!
+//! Data is copied between these buffers and the caller's slices, so each
+//! direction of a transfer is limited to `MAX_TRANSFER` (256) bytes.
+//!
+//! # Errors
+//!
+//! Every transfer waits for the command DMA to finish and the controller to
+//! go idle, then checks STATUS and ACK:
+//! - `I2cError::Nack`: The address or a data byte was not acknowledged
+//! - `I2cError::ArbitrationLost`: Another master took over the bus
+//! - `I2cError::Timeout`: The transfer did not finish within 100 ms (for
+//!   example, a device holding SCL low)
+//! - `I2cError::TooLong`: A buffer is longer than `MAX_TRANSFER`
+//!
+//! After an error, the driver stops the RX, TX and command DMA channels
+//! with their CFG clear bits, so nothing left over from the failed transfer
+//! reaches the next one, then sends a STOP to release the bus.
+//!
+//! # API Design
+//!
+//! - init(): Enable a bus and set its clock rate
+//! - write(): Write bytes to a device
+//! - read(): Read bytes from a device
+//! - write_read(): Write bytes, then read bytes after a repeated start
+//!   (the usual way to read a sensor register)
+
+use crate::clock;
+use crate::pac;
+use crate::ticktimer::Deadline;
+use core::ptr;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+// I2C controller base addresses
+const I2C0_BASE: usize = pac::UDMA_I2C0_BASE;
+const I2C_BASE_STRIDE: usize = pac::UDMA_STRIDE;
+
+// I2C register offsets
+const REG_RX_SADDR: usize = 0x00;
+const REG_RX_SIZE: usize = 0x04;
+const REG_RX_CFG: usize = 0x08;
+const REG_TX_SADDR: usize = 0x10;
+const REG_TX_SIZE: usize = 0x14;
+const REG_TX_CFG: usize = 0x18;
+const REG_CMD_SADDR: usize = 0x20;
+const REG_CMD_SIZE: usize = 0x24;
+const REG_CMD_CFG: usize = 0x28;
+const REG_STATUS: usize = 0x30;
+const REG_ACK: usize = 0x38;
+
+// Channel configuration bits
+const CFG_EN: u32 = pac::UDMA_CFG_EN;
+const CFG_CLR: u32 = pac::UDMA_CFG_CLR;
+
+// STATUS register bits
+const STATUS_BUSY: u32 = 1 << 0;
+const STATUS_AL: u32 = 1 << 1;
+
+// ACK register bits
+const ACK_NACK: u32 = 1 << 0;
+
+// Command opcodes (bits [31:28])
+const CMD_START: u32 = 0x0 << 28;
+const CMD_STOP: u32 = 0x2 << 28;
+const CMD_RD_ACK: u32 = 0x4 << 28;
+const CMD_RD_NACK: u32 = 0x6 << 28;
+const CMD_WRB: u32 = 0x7 << 28;
+const CMD_WR: u32 = 0x8 << 28;
+const CMD_RPT: u32 = 0xC << 28;
+const CMD_CFG: u32 = 0xE << 28;
+
+// IFRAM DMA buffers (above the UART TX buffers)
+const IFRAM_CMD_ADDR: usize = pac::IFRAM_I2C_CMD;
+const IFRAM_TX_ADDR: usize = pac::IFRAM_I2C_TX;
+const IFRAM_RX_ADDR: usize = pac::IFRAM_I2C_RX;
+const CMD_BUF_WORDS: usize = 16;
+
+/// Longest write or read, in bytes, that fits in one DMA buffer.
+pub const MAX_TRANSFER: usize = 256;
+
+// Clock configuration
+const DEFAULT_BAUD: u32 = 100_000;
+
+// Time allowed for one transfer before giving up
+const TIMEOUT_MS: u32 = 100;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// I2C controller selection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum I2cBus {
+    I2c0 = 0,
+    I2c1 = 1,
+    I2c2 = 2,
+    I2c3 = 3,
+}
+
+/// Errors returned by I2C transfers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum I2cError {
+    /// The device did not acknowledge its address or a data byte
+    Nack,
+    /// Another master won arbitration for the bus
+    ArbitrationLost,
+    /// The transfer did not finish in time
+    Timeout,
+    /// A buffer is longer than `MAX_TRANSFER`
+    TooLong,
+}
+
+// ============================================================================
+// Internal State
+// ============================================================================
+
+static mut I2C_BASE: usize = I2C0_BASE;
+static mut I2C_DIVIDER: u32 = divider(clock::PERCLK_DEFAULT_HZ, DEFAULT_BAUD);
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Enable an I2C bus and select it for later transfers.
+///
+/// Turns on the bus clock and sets the SCL rate to `baud` Hz (typically
+/// 100_000 or 400_000). Configure the SDA/SCL pins as AF1 first (see the
+/// module docs). Calling init() again switches to a different bus or rate.
+pub fn init(bus: I2cBus, baud: u32) {
+    unsafe {
+        // Enable the bus clock via uDMA control
+        clock::udma_clock_enable(clock::UDMA_CG_I2C0 << bus as u32);
+
+        I2C_BASE = I2C0_BASE + bus as usize * I2C_BASE_STRIDE;
+        I2C_DIVIDER = divider(clock::perclk_hz(), baud);
+    }
+}
+
+/// Write `data` to the device at 7-bit address `addr`.
+///
+/// Sends START, the address with the write bit, each byte of `data`, then
+/// STOP. An empty `data` just checks whether the device acknowledges its
+/// address.
+pub fn write(addr: u8, data: &[u8]) -> Result<(), I2cError> {
+    write_read(addr, data, &mut [])
+}
+
+/// Read `buf.len()` bytes from the device at 7-bit address `addr`.
+///
+/// Sends START and the address with the read bit, reads the bytes (ACKing
+/// all but the last), then sends STOP. Does nothing if `buf` is empty.
+pub fn read(addr: u8, buf: &mut [u8]) -> Result<(), I2cError> {
+    if buf.is_empty() {
+        return Ok(());
+    }
+    write_read_inner(addr, None, buf)
+}
+
+/// Write `data`, then read into `buf` after a repeated start.
+///
+/// The bus is not released between the two halves, so no other master can
+/// get in between. This is the usual way to read a device register: write
+/// the register number, then read its value. If `buf` is empty, this is
+/// the same as write().
+pub fn write_read(
+    addr: u8,
+    data: &[u8],
+    buf: &mut [u8],
+) -> Result<(), I2cError> {
+    write_read_inner(addr, Some(data), buf)
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+// Compute the CFG clock divider for an SCL rate in Hz
+const fn divider(perclk_hz: u32, baud: u32) -> u32 {
+    let div = perclk_hz / (4 * if baud == 0 { 1 } else { baud });
+    if div > 0xffff { 0xffff } else { div }
+}
+
+// Build and run the command stream for an optional write followed by an
+// optional read
+fn write_read_inner(
+    addr: u8,
+    data: Option<&[u8]>,
+    buf: &mut [u8],
+) -> Result<(), I2cError> {
+    let tx_len = data.map_or(0, |d| d.len());
+    if tx_len > MAX_TRANSFER || buf.len() > MAX_TRANSFER {
+        return Err(I2cError::TooLong);
+    }
+
+    let mut cmds = [0u32; CMD_BUF_WORDS];
+    let mut n = 0;
+    let mut push = |cmd: u32| {
+        cmds[n] = cmd;
+        n += 1;
+    };
+    push(CMD_CFG | unsafe { I2C_DIVIDER });
+
+    // Write phase: address with R/W = 0, then the data bytes from TX DMA
+    if let Some(data) = data {
+        push(CMD_START);
+        push(CMD_WRB | ((addr as u32) << 1));
+        if !data.is_empty() {
+            push(CMD_RPT | data.len() as u32);
+            push(CMD_WR);
+        }
+    }
+
+    // Read phase: (repeated) start, address with R/W = 1, then ACK every
+    // byte except the last, which gets a NACK to end the read
+    if !buf.is_empty() {
+        push(CMD_START);
+        push(CMD_WRB | ((addr as u32) << 1) | 1);
+        if buf.len() > 1 {
+            push(CMD_RPT | (buf.len() - 1) as u32);
+            push(CMD_RD_ACK);
+        }
+        push(CMD_RD_NACK);
+    }
+    push(CMD_STOP);
+
+    unsafe {
+        // Copy TX data into IFRAM
+        if let Some(data) = data {
+            for (i, &b) in data.iter().enumerate() {
+                ptr::write_volatile((IFRAM_TX_ADDR + i) as *mut u8, b);
+            }
+        }
+        core::sync::atomic::compiler_fence(
+            core::sync::atomic::Ordering::SeqCst,
+        );
+
+        // Arm the data channels before starting the command stream
+        if !buf.is_empty() {
+            write_reg(REG_RX_SADDR, IFRAM_RX_ADDR as u32);
+            write_reg(REG_RX_SIZE, buf.len() as u32);
+            write_reg(REG_RX_CFG, CFG_EN);
+        }
+        if tx_len > 0 {
+            write_reg(REG_TX_SADDR, IFRAM_TX_ADDR as u32);
+            write_reg(REG_TX_SIZE, tx_len as u32);
+            write_reg(REG_TX_CFG, CFG_EN);
+        }
+    }
+
+    let result = run_commands(&cmds[..n]);
+    if result.is_err() {
+        // Disarm the data channels, so their stale address and size can't
+        // feed leftover data into the next transfer, and stop the command
+        // stream that may still be running after a timeout
+        write_reg(REG_RX_CFG, CFG_CLR);
+        write_reg(REG_TX_CFG, CFG_CLR);
+        write_reg(REG_CMD_CFG, CFG_CLR);
+        // Release the bus so the next transfer starts from idle
+        let _ = run_commands(&[CMD_STOP]);
+        return result;
+    }
+
+    // Copy RX data out of IFRAM
+    core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::Acquire);
+    for (i, b) in buf.iter_mut().enumerate() {
+        *b = unsafe { ptr::read_volatile((IFRAM_RX_ADDR + i) as *const u8) };
+    }
+    Ok(())
+}
+
+// Send a command stream and wait for it to finish
+fn run_commands(cmds: &[u32]) -> Result<(), I2cError> {
+    unsafe {
+        for (i, &cmd) in cmds.iter().enumerate() {
+            ptr::write_volatile((IFRAM_CMD_ADDR + i * 4) as *mut u32, cmd);
+        }
+        core::sync::atomic::compiler_fence(
+            core::sync::atomic::Ordering::SeqCst,
+        );
+        write_reg(REG_CMD_SADDR, IFRAM_CMD_ADDR as u32);
+        write_reg(REG_CMD_SIZE, (cmds.len() * 4) as u32);
+        write_reg(REG_CMD_CFG, CFG_EN);
+    }
+
+    let deadline = Deadline::after_ms(TIMEOUT_MS);
+    loop {
+        let status = read_reg(REG_STATUS);
+        if status & STATUS_AL != 0 {
+            return Err(I2cError::ArbitrationLost);
+        }
+        if read_reg(REG_ACK) & ACK_NACK != 0 {
+            return Err(I2cError::Nack);
+        }
+        // CMD_SADDR reads 0 once the command DMA has finished
+        let done = read_reg(REG_CMD_SADDR) == 0 && status & STATUS_BUSY == 0;
+        if done {
+            return Ok(());
+        }
+        if deadline.is_expired() {
+            return Err(I2cError::Timeout);
+        }
+        core::hint::spin_loop();
+    }
+}
+
+fn read_reg(offset: usize) -> u32 {
+    unsafe { ptr::read_volatile((I2C_BASE + offset) as *const u32) }
+}
+
+fn write_reg(offset: usize, value: u32) {
+    unsafe { ptr::write_volatile((I2C_BASE + offset) as *mut u32, value) }
+}
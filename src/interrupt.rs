@@ -20,6 +20,7 @@
 //! 1. RISC-V global: mstatus.MIE + mie.MEIP
 //! 2. VexRiscv custom: MIM (Machine Interrupt Mask)
 //! 3. IRQARRAY event masks: EV_ENABLE bits for individual event sources
+//!    (see the `irqarray` module)
 //! 4. Peripheral event enable bits (not all peripherals generate interrupts)
 //!
 //! # Usage
@@ -33,9 +34,56 @@
 //!
 //! // (irq_setup() is called automatically at boot via lib.rs::init())
 //! ```
+//!
+//! # Handler Registration
+//!
+//! The trap handler dispatches external interrupts through a table indexed
+//! by `IrqSource`. `irq_setup()` registers the built-in TIMER0, GPIO, and
+//! UART handlers. Drivers for other sources call `register_handler()`,
+//! which also sets the source's MIM bit:
+//!
+//! ```ignore
+//! fn usb_irq() {
+//!     // Clear the IRQARRAY1 pending bit, then service the controller
+//! }
+//! interrupt::register_handler(interrupt::IrqSource::IrqArray1, usb_irq);
+//! ```
+//!
+//! # Software Interrupts
+//!
+//! `trigger_soft_irq()` raises events in an IRQARRAY bank through its
+//! EV_SOFT register, so a registered handler can be exercised on demand
+//! during bring-up. `clear_soft_irq()` cleans up if the handler didn't
+//! clear the pending bits itself.
+//!
+//! # Statistics
+//!
+//! The trap handler counts dispatches per source. `set_irq_timing(true)`
+//! also records the last, min, and max handler duration in `mcycle` CPU
+//! cycles. `irq_stats()` returns a snapshot and `reset_irq_stats()` zeroes
+//! it. Durations cover only the handler call, not trap entry and exit.
+//!
+//! # Enable State
+//!
+//! When interrupts stop firing, one of the enable layers above is usually
+//! off. `irq_state()` reads layers 1 and 2 plus the VexRiscv pending bits
+//! in one snapshot, and `log_irq_state()` logs it as one line:
+//!
+//! ```text
+//! irq: MIE=1 MEIE=1 MIM=40000422 pending=00000000 blocked=00000000
+//! ```
+//!
+//! A source that is pending but `blocked` has its MIM bit clear. A source
+//! that is never pending has its event disabled further down, in the
+//! IRQARRAY bank's EV_ENABLE mask or at the peripheral.
 
+use crate::irqarray::IrqArray;
+use crate::log::Level;
+use crate::uart::PolledUart;
 use core::arch::asm;
 use core::arch::naked_asm;
+use core::ffi::c_void;
+use core::fmt::Write;
 
 // ====================================================================
 // External Symbols from Linker Script
@@ -68,17 +116,237 @@ const MCAUSE_LOAD_ACCESS: u32 = 0x0000_0005; // Memory load caused fault
 const MCAUSE_EXTERNAL_INT: u32 = 0x8000_000B; // External interrupt code
 
 // ====================================================================
-// Bit Masks for VexRISCV MIP (pending interrupt event bitfield)
+// Interrupt Sources (VexRISCV MIP and MIM bit numbers)
+// ====================================================================
+
+/// Top-level interrupt source
+///
+/// Each value is the source's bit number in both the VexRISCV MIP pending
+/// bitfield and the MIM enable mask. Sources are listed at
+/// https://ci.betrusted.io/bao1x-cpu/interrupts.html
+#[derive(Clone, Copy)]
+pub enum IrqSource {
+    IrqArray0 = 0,
+    IrqArray1 = 1, // USBC (USB controller) is bit 0 of bank
+    IrqArray2 = 2,
+    IrqArray3 = 3,
+    IrqArray4 = 4,
+    IrqArray5 = 5, // UART2_RX_CHAR is bit 10 of bank
+    IrqArray6 = 6,
+    IrqArray7 = 7,
+    IrqArray8 = 8,
+    IrqArray9 = 9,
+    IrqArray10 = 10, // IOXIRQ (GPIO) is bit 0 of bank
+    IrqArray11 = 11,
+    IrqArray12 = 12,
+    IrqArray13 = 13,
+    IrqArray14 = 14,
+    IrqArray15 = 15,
+    IrqArray16 = 16,
+    IrqArray17 = 17,
+    IrqArray18 = 18,
+    IrqArray19 = 19,
+    Ticktimer = 20,
+    Susres = 21,
+    Mailbox = 22,
+    MbClient = 23,
+    Timer0 = 30,
+}
+
+// Registered handlers, indexed by IrqSource bit number
+static mut IRQ_HANDLERS: [Option<fn()>; 32] = [None; 32];
+
+// ====================================================================
+// Interrupt Statistics
+// ====================================================================
+
+/// Dispatch count and handler duration for one interrupt source
+///
+/// The cycle fields are only updated while timing is enabled with
+/// `set_irq_timing(true)`. `min_cycles` is 0 until the first timed call.
+#[derive(Clone, Copy, Default)]
+pub struct SourceStats {
+    /// Number of times the handler was called
+    pub count: u32,
+    /// Duration of the most recent timed call, in CPU cycles
+    pub last_cycles: u32,
+    /// Shortest timed call, in CPU cycles
+    pub min_cycles: u32,
+    /// Longest timed call, in CPU cycles
+    pub max_cycles: u32,
+}
+
+/// Snapshot of per-source interrupt statistics, returned by `irq_stats()`
+#[derive(Clone, Copy)]
+pub struct IrqStats {
+    /// Per-source statistics, indexed by IrqSource bit number
+    pub sources: [SourceStats; 32],
+    /// Number of pending sources that had no registered handler
+    pub unhandled: u32,
+}
+
+impl IrqStats {
+    const EMPTY: IrqStats = IrqStats {
+        sources: [SourceStats {
+            count: 0,
+            last_cycles: 0,
+            min_cycles: 0,
+            max_cycles: 0,
+        }; 32],
+        unhandled: 0,
+    };
+
+    /// Statistics for one source
+    pub fn source(&self, source: IrqSource) -> SourceStats {
+        self.sources[source as usize]
+    }
+}
+
+// Updated by the trap handler, so other access needs a critical section
+static mut IRQ_STATS: IrqStats = IrqStats::EMPTY;
+static mut IRQ_TIMING: bool = false;
+
+/// Return a copy of the interrupt statistics collected so far
+pub fn irq_stats() -> IrqStats {
+    let _cs = critical_section();
+    unsafe { IRQ_STATS }
+}
+
+/// Zero all interrupt counts and durations
+pub fn reset_irq_stats() {
+    let _cs = critical_section();
+    unsafe {
+        IRQ_STATS = IrqStats::EMPTY;
+    }
+}
+
+/// Enable or disable handler duration measurement (off by default)
+///
+/// When enabled, the trap handler reads the `mcycle` CSR before and after
+/// each handler call. Counts are kept either way.
+pub fn set_irq_timing(enabled: bool) {
+    unsafe {
+        IRQ_TIMING = enabled;
+    }
+}
+
+// Call a handler from the trap handler, updating its statistics
+#[inline(always)]
+fn dispatch(bit: u32, handler: fn()) {
+    // Borrow the stats only after the handler returns, in case it calls
+    // reset_irq_stats()
+    let timing = unsafe { IRQ_TIMING };
+    let start = if timing { crate::cycles::mcycle() } else { 0 };
+    handler();
+    let d =
+        if timing { crate::cycles::mcycle().wrapping_sub(start) } else { 0 };
+    let p = &raw mut IRQ_STATS;
+    let s = unsafe { &mut (*p).sources[bit as usize] };
+    s.count = s.count.wrapping_add(1);
+    if timing {
+        s.last_cycles = d;
+        if s.min_cycles == 0 || d < s.min_cycles {
+            s.min_cycles = d;
+        }
+        if d > s.max_cycles {
+            s.max_cycles = d;
+        }
+    }
+}
+
+// ====================================================================
+// Interrupt Enable State
 // ====================================================================
 
-const VEX_MIP_TIMER0_BIT: u32 = 1 << 30; // TIMER0 alarm event bit
+/// Snapshot of the CPU-level interrupt enable state, from `irq_state()`
+///
+/// Covers layers 1 and 2 of the enable tree (see the module docs). The
+/// `mim` and `pending` masks are indexed by IrqSource bit number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IrqState {
+    /// mstatus.MIE: interrupts are globally enabled
+    pub global_enabled: bool,
+    /// mie.MEIE (bit 11): external interrupts are enabled
+    pub external_enabled: bool,
+    /// MIM: sources allowed to interrupt the CPU
+    pub mim: u32,
+    /// VexRiscv MIP: sources with a pending event
+    pub pending: u32,
+}
+
+impl IrqState {
+    /// Sources that are pending but masked off in MIM
+    pub fn blocked(&self) -> u32 {
+        self.pending & !self.mim
+    }
+
+    /// True if a pending, unmasked source can reach the trap handler
+    pub fn can_interrupt(&self) -> bool {
+        self.global_enabled && self.external_enabled
+    }
+}
+
+/// Read the current interrupt enable and pending state
+///
+/// Called from a handler or inside a critical section, `global_enabled`
+/// reads false, since MIE is cleared there.
+pub fn irq_state() -> IrqState {
+    IrqState {
+        global_enabled: csr_read(MSTATUS) & MSTATUS_MIE != 0,
+        external_enabled: csr_read(MIE) & MIE_MEIP != 0,
+        mim: csr_read_mim(),
+        pending: csr_read(VEX_MIP),
+    }
+}
+
+/// Log `irq_state()` as one line at Level::Info (see the module docs)
+pub fn log_irq_state() {
+    let s = irq_state();
+    crate::logln!(
+        "irq: MIE={} MEIE={} MIM={:08x} pending={:08x} blocked={:08x}",
+        s.global_enabled as u8,
+        s.external_enabled as u8,
+        s.mim,
+        s.pending,
+        s.blocked(),
+    );
+}
 
 // ====================================================================
-// MIM Register Bit Masks (Machine Interrupt Mask - enable IRQARRAY banks)
+// Interrupt Callbacks
 // ====================================================================
 
-// const MIM_BIT_TICKTIMER: u32 = 1 << 20;
-const MIM_BIT_TIMER0: u32 = 1 << 30;
+/// Callback registered with a driver and invoked from the trap handler
+///
+/// `Context` carries an opaque pointer for C callers (e.g. MicroPython's
+/// callback dispatcher) that need to pass state without global statics.
+/// `Rearm` returns whether a periodic source should keep running.
+#[derive(Clone, Copy)]
+pub(crate) enum Callback {
+    Plain(fn()),
+    Context(extern "C" fn(*mut c_void), *mut c_void),
+    Rearm(fn() -> bool),
+}
+
+impl Callback {
+    /// Invoke the callback, passing its context pointer if it has one
+    ///
+    /// Returns false if a `Rearm` callback asked to stop, or true otherwise.
+    #[inline]
+    pub(crate) fn call(self) -> bool {
+        match self {
+            Callback::Plain(f) => {
+                f();
+                true
+            }
+            Callback::Context(f, ctx) => {
+                f(ctx);
+                true
+            }
+            Callback::Rearm(f) => f(),
+        }
+    }
+}
 
 // ====================================================================
 // CSR Helper Functions (No External Dependencies)
@@ -140,6 +408,16 @@ fn csr_clear(csr: u32, bits: u32) {
     }
 }
 
+/// Read VexRiscv custom MIM (Machine Interrupt Mask) register (0xBC0).
+#[inline]
+fn csr_read_mim() -> u32 {
+    let result: u32;
+    unsafe {
+        asm!("csrr {0}, 0xbc0", out(reg) result);
+    }
+    result
+}
+
 /// Write VexRiscv custom MIM (Machine Interrupt Mask) register (0xBC0).
 ///
 /// MIM is not a standard RISC-V CSR. It is specific to VexRiscv and controls
@@ -196,7 +474,68 @@ pub fn irq_setup() {
     csr_set(MIE, MIE_MEIP);
 
     // Enable TIMER0 events
-    csr_set_mim(MIM_BIT_TIMER0);
+    register_handler(IrqSource::Timer0, timer0_handler);
+
+    // Enable IRQARRAY10 events (GPIO pin-change interrupts)
+    register_handler(IrqSource::IrqArray10, gpio_handler);
+
+    // Enable IRQARRAY5 events (UART2 RX interrupts)
+    register_handler(IrqSource::IrqArray5, uart_handler);
+
+    // Enable IRQARRAY1 events (USB controller). The USBC event itself stays
+    // disabled in IRQARRAY1 until the USB driver can service it.
+    register_handler(IrqSource::IrqArray1, usb_handler);
+}
+
+/// Register the handler for a top-level interrupt source
+///
+/// Stores `handler` in the dispatch table and sets the source's MIM bit so
+/// it can interrupt the CPU. The trap handler calls `handler` (in interrupt
+/// context) whenever the source's MIP bit is pending. Registering again
+/// replaces the previous handler.
+///
+/// The handler is responsible for clearing the pending event at the
+/// peripheral or IRQARRAY bank, or it will be called again immediately.
+pub fn register_handler(source: IrqSource, handler: fn()) {
+    let _cs = critical_section();
+    unsafe {
+        IRQ_HANDLERS[source as usize] = Some(handler);
+    }
+    csr_set_mim(1 << source as u32);
+}
+
+/// Raise software events in the IRQARRAY bank behind `source`.
+///
+/// Pulses the EV_SOFT bits in `events` (set, then clear), which latches
+/// them in EV_PENDING just as if the peripheral had signalled them, the
+/// behavior `usb::pending_write_test()` confirmed on bank 1. If the events
+/// are enabled in the bank's EV_ENABLE mask and a handler is registered
+/// for `source`, the trap handler then runs it, so interrupt paths can be
+/// tested without the real hardware event. `events` selects the bits the
+/// handler looks at, e.g. bit 10 for UART2_RX_CHAR on IrqArray5.
+///
+/// Returns false, doing nothing, for sources that are not IRQARRAY banks
+/// (TIMER0, TICKTIMER, SUSRES and the mailboxes have no EV_SOFT register).
+pub fn trigger_soft_irq(source: IrqSource, events: u32) -> bool {
+    let Some(bank) = IrqArray::for_source(source) else {
+        return false;
+    };
+    bank.soft_trigger(events);
+    bank.soft_release(events);
+    true
+}
+
+/// Undo `trigger_soft_irq()` for events the handler did not clear.
+///
+/// Clears the EV_SOFT bits in `events` and any of them still latched in
+/// EV_PENDING. Returns false for sources that are not IRQARRAY banks.
+pub fn clear_soft_irq(source: IrqSource, events: u32) -> bool {
+    let Some(bank) = IrqArray::for_source(source) else {
+        return false;
+    };
+    bank.soft_release(events);
+    bank.clear_pending(events);
+    true
 }
 
 /// Enable all interrupts
@@ -218,6 +557,88 @@ pub fn disable_irqs() -> bool {
     was_enabled
 }
 
+/// Guard that keeps interrupts disabled while it is alive
+///
+/// Returned by `critical_section()`. Dropping the guard restores the
+/// interrupt enable state from before it was created, so early returns and
+/// `?` can't leave interrupts disabled by mistake. Nested guards are fine:
+/// only the outermost one re-enables interrupts.
+pub struct CriticalSection {
+    was_enabled: bool,
+}
+
+impl Drop for CriticalSection {
+    #[inline]
+    fn drop(&mut self) {
+        if self.was_enabled {
+            enable_irqs();
+        }
+    }
+}
+
+/// Disable interrupts until the returned guard is dropped
+///
+/// ```ignore
+/// {
+///     let _cs = interrupt::critical_section();
+///     // ... update state shared with an interrupt handler ...
+/// } // interrupts are restored here
+/// ```
+#[inline]
+pub fn critical_section() -> CriticalSection {
+    CriticalSection { was_enabled: disable_irqs() }
+}
+
+// ====================================================================
+// Trap Frame
+// ====================================================================
+
+/// Registers saved by the `_trap` entry point
+///
+/// The layout matches the 36-word frame that `_trap` stores on the scratch
+/// stack, so `_trap` can pass a pointer to it straight to
+/// `_trap_handler_rust`.
+#[repr(C)]
+pub struct TrapFrame {
+    /// x1 through x31 (`regs[0]` is x1/ra, `regs[1]` is x2/sp at the time
+    /// of the trap, and so on)
+    pub regs: [u32; 31],
+    /// Address of the trapping instruction (or the instruction to resume
+    /// at, for interrupts)
+    pub mepc: u32,
+    /// mstatus at trap entry, before MIE was cleared
+    pub mstatus: u32,
+    _padding: [u32; 3],
+}
+
+// RISC-V ABI names for x1-x31, in TrapFrame::regs order
+const REG_NAMES: [&str; 31] = [
+    "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2",
+    "a3", "a4", "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8",
+    "s9", "s10", "s11", "t3", "t4", "t5", "t6",
+];
+
+/// Print the saved registers for a post-mortem after a fatal exception
+///
+/// Uses polled UART output (see `uart::write_polled()`), since the trap
+/// may have interrupted code that was in the middle of updating the TX
+/// queue.
+fn dump_trap_frame(frame: &TrapFrame) {
+    let mut out = PolledUart;
+    let _ = write!(
+        out,
+        "  mepc=0x{:08x} ra=0x{:08x} sp=0x{:08x}\r\n",
+        frame.mepc, frame.regs[0], frame.regs[1]
+    );
+    for (i, chunk) in frame.regs.chunks(4).enumerate() {
+        let _ = out.write_str("  ");
+        for (j, value) in chunk.iter().enumerate() {
+            let _ = write!(out, "{:>3}=0x{:08x} ", REG_NAMES[i * 4 + j], value);
+        }
+        let _ = out.write_str("\r\n");
+    }
+}
+
 // ====================================================================
 // Trap Handler Assembly Entry Point
 // ====================================================================
@@ -295,6 +716,8 @@ pub unsafe extern "C" fn _trap() -> ! {
         // Call to Rust: Dispatch interrupt handlers
         // =========================================
 
+        // Pass a pointer to the saved registers (TrapFrame) as the argument
+        "mv     a0, sp",
         "call   {1}", // sym _trap_handler_rust
 
         // ========================================================
@@ -373,16 +796,12 @@ pub unsafe extern "C" fn _trap() -> ! {
 /// Rust-level trap handler dispatcher
 ///
 /// Reads mcause to determine interrupt type, checks IRQARRAY0 pending
-/// events, and dispatches to appropriate handler.
-pub extern "C" fn _trap_handler_rust() {
-    // Debug: Turn on LED at PB12 to indicate trap was hit
-    crate::gpio::set_alternate_function(
-        crate::gpio::GpioPin::PortB(crate::gpio::PB12),
-        crate::gpio::AF::AF0,
-    );
-    crate::gpio::enable_output(crate::gpio::GpioPin::PortB(crate::gpio::PB12));
-    crate::gpio::set(crate::gpio::GpioPin::PortB(crate::gpio::PB12));
-
+/// events, and dispatches to appropriate handler. `frame` points to the
+/// registers saved by `_trap`, which are logged on a fatal exception.
+///
+/// Interrupts leave the debug LED (PB12) alone, so the pin stays free for
+/// other uses. A fatal exception lights it before halting.
+pub extern "C" fn _trap_handler_rust(frame: &TrapFrame) {
     // Read mcause and mip for dispatch
     let mcause = csr_read(MCAUSE);
 
@@ -401,32 +820,61 @@ pub extern "C" fn _trap_handler_rust() {
 
         let pending = csr_read(VEX_MIP);
 
-        // Check for TIMER0 event
-        if pending & VEX_MIP_TIMER0_BIT != 0 {
-            timer0_handler();
-        } else {
-            // Add more event checks here as needed (UART, USB, etc.)
-            crate::log!("  TRAP: external vex_mip=0x{:08x}\r\n", pending);
-            crate::sleep(2);
+        // Dispatch each pending source to its registered handler
+        let mut bits = pending;
+        let mut unhandled: u32 = 0;
+        while bits != 0 {
+            let bit = bits.trailing_zeros();
+            bits &= !(1 << bit);
+            match unsafe { IRQ_HANDLERS[bit as usize] } {
+                Some(handler) => dispatch(bit, handler),
+                None => unhandled |= 1 << bit,
+            }
+        }
+        if unhandled != 0 {
+            unsafe {
+                let p = &raw mut IRQ_STATS;
+                let n = &mut (*p).unhandled;
+                *n = n.wrapping_add(unhandled.count_ones());
+            }
+        }
+        if unhandled != 0 && crate::log::enabled(Level::Warn) {
+            let _ = write!(
+                PolledUart,
+                "  TRAP: external vex_mip=0x{:08x}\r\n",
+                unhandled
+            );
         }
     } else if mcause == MCAUSE_ILLEGAL_INST {
-        crate::log!("\r\nTRAP: illegal instruction\r\n");
-        crate::sleep(2);
+        crate::fatal_led_on();
+        let _ = PolledUart.write_str("\r\nTRAP: illegal instruction\r\n");
+        dump_trap_frame(frame);
         loop {}
     } else if mcause == MCAUSE_LOAD_ACCESS {
+        crate::fatal_led_on();
         let mtval = csr_read(MTVAL);
-        crate::log!("\r\nTRAP: load access, mtval=0x{:08x}", mtval);
-        crate::sleep(2);
+        let _ = write!(
+            PolledUart,
+            "\r\nTRAP: load access, mtval=0x{:08x}\r\n",
+            mtval
+        );
+        dump_trap_frame(frame);
         loop {}
     } else {
         // Unknown exception
-        crate::log!("\r\nTRAP: mcause=0x{:08x}\r\n", mcause);
-        crate::sleep(2);
+        crate::fatal_led_on();
+        let mtval = csr_read(MTVAL);
+        let _ = write!(
+            PolledUart,
+            "\r\nTRAP: mcause=0x{:08x} mtval=0x{:08x}\r\n",
+            mcause, mtval
+        );
+        dump_trap_frame(frame);
         loop {}
     }
 
-    // Turn off LED before returning
-    crate::gpio::clear(crate::gpio::GpioPin::PortB(crate::gpio::PB12));
+    // Make sure no handler overflowed a stack
+    crate::check_stack();
 }
 
 // ====================================================================
@@ -436,14 +884,73 @@ pub extern "C" fn _trap_handler_rust() {
 /// Handle TIMER0 interrupt
 ///
 /// Called from trap dispatcher when TIMER0 fires.
-/// Clears pending bit to allow next interrupt.
+/// Clears pending bit to allow next interrupt. In periodic mode the timer
+/// keeps running, so only the pending bit is cleared, unless the callback
+/// returns false to stop it.
 #[inline]
 fn timer0_handler() {
-    // Clear pending bit and ensure timer won't accidentally re-trigger
-    crate::timer0::stop_and_clear();
+    let periodic = crate::timer0::is_periodic();
+    if periodic {
+        crate::timer0::clear_pending();
+    } else {
+        // Clear pending bit and ensure timer won't accidentally re-trigger
+        crate::timer0::stop_and_clear();
+    }
 
     // Invoke callback if registered
-    if let Some(callback) = crate::timer0::get_callback() {
-        callback();
+    if let Some(callback) = crate::timer0::get_callback()
+        && !callback.call()
+        && periodic
+    {
+        crate::timer0::cancel();
     }
 }
+
+// ====================================================================
+// GPIO Interrupt Handler
+// ====================================================================
+
+/// Handle IRQARRAY10 (IOXIRQ) interrupt
+///
+/// Called from trap dispatcher when a GPIO pin-change interrupt fires.
+/// Clears the INTFR channel flags, then invokes the callback registered
+/// for each channel that fired.
+#[inline]
+fn gpio_handler() {
+    let flags = crate::gpio::take_pending_interrupts();
+    for channel in 0..8 {
+        if flags & (1 << channel) != 0
+            && let Some(callback) = crate::gpio::get_callback(channel)
+        {
+            callback.call();
+        }
+    }
+}
+
+// ====================================================================
+// UART Interrupt Handler
+// ====================================================================
+
+/// Handle IRQARRAY5 (UART RX_CHAR) interrupt
+///
+/// Called from trap dispatcher when UART2, or a UART set up with the
+/// serial module, has received data. Moves the received bytes into the RX
+/// ring buffers.
+#[inline]
+fn uart_handler() {
+    crate::uart::handle_rx_interrupt();
+    crate::serial::handle_rx_interrupt();
+}
+
+// ====================================================================
+// USB Interrupt Handler
+// ====================================================================
+
+/// Handle IRQARRAY1 (USBC) interrupt
+///
+/// Called from trap dispatcher when the USB controller has posted events.
+/// Processes them in the USB driver.
+#[inline]
+fn usb_handler() {
+    crate::usb::handle_interrupt();
+}
@@ -0,0 +1,239 @@
+// SPDX-License-Identifier: MIT
+// SPDX-FileCopyrightText: Copyright 2026 Sam Blenny
+//
+//! IRQARRAY event banks for bao1x dabao evaluation board
+//!
+//! Each IRQARRAY bank is an EventManager that collects up to 32 peripheral
+//! events into one CPU interrupt source. This module wraps the bank
+//! registers so drivers don't each keep their own raw pointer constants.
+//!
+//! # Registers
+//!
+//! Each bank has the same layout at its base address:
+//!
+//! - EV_SOFT (0x00): Software event sources. Setting a bit raises that
+//!   event as if the peripheral had signalled it.
+//! - EV_EDGE_TRIGGERED (0x04), EV_POLARITY (0x08): Event trigger mode
+//!   (not used by this SDK, left at their reset values)
+//! - EV_STATUS (0x0c): Current level of each event source (read-only)
+//! - EV_PENDING (0x10): Latched events. Write 1 to clear (RW1C). Confirmed
+//!   on hardware for bank 1 by `usb::pending_write_test()`.
+//! - EV_ENABLE (0x14): Events allowed to interrupt the CPU
+//!
+//! # Banks
+//!
+//! Bank N is interrupt source N (`interrupt::IrqSource::IrqArrayN`), so it
+//! is enabled by MIM bit N, which `interrupt::register_handler()` sets.
+//!
+//! Bank | Base       | MIM bit | Events used by this SDK
+//! -----|------------|---------|-------------------------------------------
+//!  0   | 0xe0004000 |  0      |
+//!  1   | 0xe0005000 |  1      | bit 0: USBC (USB controller)
+//!  2   | 0xe0010000 |  2      |
+//!  3   | 0xe0011000 |  3      |
+//!  4   | 0xe0012000 |  4      |
+//!  5   | 0xe0013000 |  5      | bit 4n+2: UARTn_RX_CHAR (UART2 is bit 10)
+//!  6   | 0xe0014000 |  6      |
+//!  7   | 0xe0015000 |  7      |
+//!  8   | 0xe0016000 |  8      |
+//!  9   | 0xe0017000 |  9      |
+//! 10   | 0xe0006000 | 10      | bit 0: IOXIRQ (GPIO pin-change)
+//! 11   | 0xe0007000 | 11      |
+//! 12   | 0xe0008000 | 12      |
+//! 13   | 0xe0009000 | 13      |
+//! 14   | 0xe000a000 | 14      |
+//! 15   | 0xe000b000 | 15      |
+//! 16   | 0xe000c000 | 16      |
+//! 17   | 0xe000d000 | 17      |
+//! 18   | 0xe000e000 | 18      |
+//! 19   | 0xe000f000 | 19      |
+//!
+//! The bases are not in bank order: banks 10-19 come before banks 2-9.
+//!
+//! # Usage
+//!
+//! ```ignore
+//! use baochip_sdk::irqarray::IRQARRAY5;
+//!
+//! const UART2_RX_CHAR_BIT: u32 = 1 << 10;
+//!
+//! IRQARRAY5.clear_pending(UART2_RX_CHAR_BIT);
+//! IRQARRAY5.enable(UART2_RX_CHAR_BIT);
+//! ```
+//!
+//! # API Design
+//!
+//! - IRQARRAY0 - IRQARRAY19: One handle per bank
+//! - IrqArray::for_source(): Bank for an `interrupt::IrqSource`
+//! - IrqArray::pending(): Read the latched events
+//! - IrqArray::clear_pending(): Clear latched events (RW1C)
+//! - IrqArray::status(): Read the current event levels
+//! - IrqArray::enable(), disable(): Change which events interrupt the CPU
+//! - IrqArray::enabled(): Read the event enable mask
+//! - IrqArray::soft_trigger(), soft_release(): Set or clear software events
+
+use crate::interrupt::{self, IrqSource};
+use crate::pac;
+use core::ptr;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+// Register offsets within a bank
+const EV_SOFT: usize = 0x00;
+const EV_STATUS: usize = 0x0c;
+const EV_PENDING: usize = 0x10;
+const EV_ENABLE: usize = 0x14;
+
+pub const IRQARRAY0: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[0]);
+pub const IRQARRAY1: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[1]);
+pub const IRQARRAY2: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[2]);
+pub const IRQARRAY3: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[3]);
+pub const IRQARRAY4: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[4]);
+pub const IRQARRAY5: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[5]);
+pub const IRQARRAY6: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[6]);
+pub const IRQARRAY7: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[7]);
+pub const IRQARRAY8: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[8]);
+pub const IRQARRAY9: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[9]);
+pub const IRQARRAY10: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[10]);
+pub const IRQARRAY11: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[11]);
+pub const IRQARRAY12: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[12]);
+pub const IRQARRAY13: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[13]);
+pub const IRQARRAY14: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[14]);
+pub const IRQARRAY15: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[15]);
+pub const IRQARRAY16: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[16]);
+pub const IRQARRAY17: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[17]);
+pub const IRQARRAY18: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[18]);
+pub const IRQARRAY19: IrqArray = IrqArray::at(pac::IRQARRAY_BASES[19]);
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Handle for one IRQARRAY bank.
+///
+/// A handle is just the bank's base address, so it is Copy and the bank
+/// constants above can be used directly. The read-modify-write methods run
+/// with interrupts disabled, so drivers sharing a bank (e.g. `uart` and
+/// `serial` on bank 5) can't lose each other's enable bits.
+#[derive(Clone, Copy)]
+pub struct IrqArray {
+    base: usize,
+}
+
+impl IrqArray {
+    const fn at(base: usize) -> Self {
+        IrqArray { base }
+    }
+
+    /// Return the bank behind an interrupt source, or None for the sources
+    /// that are not IRQARRAY banks (TICKTIMER, SUSRES, mailbox, TIMER0).
+    pub const fn for_source(source: IrqSource) -> Option<IrqArray> {
+        Some(match source {
+            IrqSource::IrqArray0 => IRQARRAY0,
+            IrqSource::IrqArray1 => IRQARRAY1,
+            IrqSource::IrqArray2 => IRQARRAY2,
+            IrqSource::IrqArray3 => IRQARRAY3,
+            IrqSource::IrqArray4 => IRQARRAY4,
+            IrqSource::IrqArray5 => IRQARRAY5,
+            IrqSource::IrqArray6 => IRQARRAY6,
+            IrqSource::IrqArray7 => IRQARRAY7,
+            IrqSource::IrqArray8 => IRQARRAY8,
+            IrqSource::IrqArray9 => IRQARRAY9,
+            IrqSource::IrqArray10 => IRQARRAY10,
+            IrqSource::IrqArray11 => IRQARRAY11,
+            IrqSource::IrqArray12 => IRQARRAY12,
+            IrqSource::IrqArray13 => IRQARRAY13,
+            IrqSource::IrqArray14 => IRQARRAY14,
+            IrqSource::IrqArray15 => IRQARRAY15,
+            IrqSource::IrqArray16 => IRQARRAY16,
+            IrqSource::IrqArray17 => IRQARRAY17,
+            IrqSource::IrqArray18 => IRQARRAY18,
+            IrqSource::IrqArray19 => IRQARRAY19,
+            IrqSource::Ticktimer
+            | IrqSource::Susres
+            | IrqSource::Mailbox
+            | IrqSource::MbClient
+            | IrqSource::Timer0 => return None,
+        })
+    }
+
+    /// Return the base address of the bank's registers.
+    pub const fn base(&self) -> usize {
+        self.base
+    }
+
+    fn reg(&self, offset: usize) -> *mut u32 {
+        (self.base + offset) as *mut u32
+    }
+
+    /// Read EV_PENDING, the events latched since they were last cleared.
+    #[inline]
+    pub fn pending(&self) -> u32 {
+        unsafe { ptr::read_volatile(self.reg(EV_PENDING)) }
+    }
+
+    /// Clear the latched events in `mask` (EV_PENDING is write 1 to clear).
+    #[inline]
+    pub fn clear_pending(&self, mask: u32) {
+        unsafe { ptr::write_volatile(self.reg(EV_PENDING), mask) };
+    }
+
+    /// Read EV_STATUS, the current level of each event source.
+    #[inline]
+    pub fn status(&self) -> u32 {
+        unsafe { ptr::read_volatile(self.reg(EV_STATUS)) }
+    }
+
+    /// Read EV_ENABLE, the events allowed to interrupt the CPU.
+    #[inline]
+    pub fn enabled(&self) -> u32 {
+        unsafe { ptr::read_volatile(self.reg(EV_ENABLE)) }
+    }
+
+    /// Allow the events in `mask` to interrupt the CPU.
+    ///
+    /// Other enable bits are left as they are. The bank's MIM bit must
+    /// also be set (see `interrupt::register_handler()`) for the interrupt
+    /// to reach the CPU.
+    pub fn enable(&self, mask: u32) {
+        self.modify(EV_ENABLE, |en| en | mask);
+    }
+
+    /// Stop the events in `mask` from interrupting the CPU.
+    ///
+    /// The events still latch in EV_PENDING.
+    pub fn disable(&self, mask: u32) {
+        self.modify(EV_ENABLE, |en| en & !mask);
+    }
+
+    /// Raise the software events in `mask` by setting their EV_SOFT bits.
+    ///
+    /// The bits stay set until soft_release(), and the events latch in
+    /// EV_PENDING as if the peripheral had signalled them. This is the
+    /// sequence `usb::pending_write_test()` verified on bank 1.
+    pub fn soft_trigger(&self, mask: u32) {
+        self.modify(EV_SOFT, |soft| soft | mask);
+    }
+
+    /// Clear the EV_SOFT bits in `mask` set by soft_trigger().
+    ///
+    /// Does not clear EV_PENDING; use clear_pending() for that.
+    pub fn soft_release(&self, mask: u32) {
+        self.modify(EV_SOFT, |soft| soft & !mask);
+    }
+
+    // Read-modify-write a bank register with interrupts disabled
+    fn modify(&self, offset: usize, f: impl FnOnce(u32) -> u32) {
+        let _cs = interrupt::critical_section();
+        unsafe {
+            let reg = self.reg(offset);
+            ptr::write_volatile(reg, f(ptr::read_volatile(reg)));
+        }
+        // Ensure the bank is configured before any code that relies on it
+        core::sync::atomic::compiler_fence(
+            core::sync::atomic::Ordering::SeqCst,
+        );
+    }
+}
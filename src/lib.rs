@@ -2,25 +2,70 @@
 // SPDX-FileCopyrightText: Copyright 2026 Sam Blenny
 //
 //! Bare-metal initialization and hardware drivers for bao1x dabao
-#![no_std]
-#![no_main]
+#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(test), no_main)]
 
 // System clock frequency (ACLK domain)
-pub const ACLK_HZ: u32 = 350_000_000;
+pub const ACLK_HZ: u32 = pac::ACLK_HZ;
 
+// Host unit tests (`cargo test --lib --target <host triple>`) build only the
+// modules that don't touch the hardware. The rest of the crate needs the
+// RISC-V target for its inline asm and linker symbols, so everything else
+// here is `cfg(not(test))`.
+
+#[cfg(not(test))]
+pub mod clock;
+#[cfg(not(test))]
+pub mod cycles;
+#[cfg(not(test))]
 pub mod d11ctime;
+#[cfg(not(test))]
 pub mod gpio;
+#[cfg(not(test))]
+pub mod i2c;
+#[cfg(not(test))]
 pub mod interrupt;
+#[cfg(not(test))]
+pub mod irqarray;
+#[cfg(not(test))]
 pub mod log;
+pub mod mmio;
+pub mod pac;
+#[cfg(not(test))]
+pub mod prng;
+#[cfg(not(test))]
+pub mod pwm;
+pub mod queue;
+#[cfg(not(test))]
+pub mod sched;
+#[cfg(not(test))]
+pub mod serial;
+#[cfg(not(test))]
+pub mod spi;
+#[cfg(not(test))]
 pub mod ticktimer;
+#[cfg(not(test))]
 pub mod timer0;
+#[cfg(not(test))]
+pub mod timers;
+mod txblocks;
+#[cfg(not(test))]
 pub mod uart;
+#[cfg(not(test))]
 pub mod usb;
+#[cfg(not(test))]
+pub mod watchdog;
 
+#[cfg(not(test))]
 use core::arch::asm;
+#[cfg(not(test))]
+use core::fmt::Write;
+#[cfg(not(test))]
 use core::panic::PanicInfo;
+#[cfg(not(test))]
 use gpio::{AF, GpioPin};
 
+#[cfg(not(test))]
 unsafe extern "C" {
     fn _data_lma(); //  Start .data in FLASH (ReRAM)
     fn _data_vma(); //  Start .data in SRAM
@@ -32,11 +77,17 @@ unsafe extern "C" {
     fn main() -> !;
 }
 
+// Stack guard sentinel, written at the low end of each stack at boot
+#[cfg(not(test))]
+const STACK_SENTINEL: u32 = 0x4b415453; // look for "STAK" in hexdump
+
 // This exists to help verify .data is linked properly
+#[cfg(not(test))]
 #[allow(dead_code)]
 static mut TEST_DATA: u32 = 0x41544144; // look for "DATA" in hexdump
 
 /// Boot entry point (bootloader jumps here)
+#[cfg(not(test))]
 #[unsafe(no_mangle)]
 pub extern "C" fn _start() -> ! {
     // Set stack pointer to end of the stack section (from link.x).
@@ -61,17 +112,168 @@ pub extern "C" fn _start() -> ! {
 ///
 /// Blocks until the specified time has elapsed, calling uart::tick()
 /// periodically to service the TX DMA queue.
+#[cfg(not(test))]
 pub fn sleep(ms: u32) {
-    let end_time = ticktimer::millis() + ms as u64;
-    while ticktimer::millis() < end_time {
+    let end_ms = ticktimer::deadline_ms(ms);
+    while ticktimer::millis() < end_ms {
+        uart::tick();
+    }
+}
+
+/// Sleep until `predicate` returns true or `max_ms` milliseconds pass.
+///
+/// Calls `predicate` and uart::tick() in a loop, like `sleep()`. Returns
+/// true as soon as the predicate holds (including on the first check), or
+/// false if the time runs out first. The predicate is FnMut, so it can
+/// update state such as a `gpio::DebouncedInput`:
+///
+/// ```ignore
+/// // Wait up to 2s for the PROG button to be pressed
+/// let pressed =
+///     sleep_until(|| button.update() == Some(gpio::Edge::Falling), 2000);
+/// ```
+#[cfg(not(test))]
+pub fn sleep_until(mut predicate: impl FnMut() -> bool, max_ms: u32) -> bool {
+    let deadline = ticktimer::Deadline::after_ms(max_ms);
+    loop {
+        if predicate() {
+            return true;
+        }
+        if deadline.is_expired() {
+            return false;
+        }
         uart::tick();
     }
 }
 
+// Set by the TIMER0 callback to end sleep_low_power()
+#[cfg(not(test))]
+static mut LOW_POWER_WAKE: bool = false;
+
+// Longest single TIMER0 alarm, safely below the 32-bit LOAD limit
+#[cfg(not(test))]
+const LOW_POWER_MAX_ALARM_MS: u32 = 10_000;
+
+#[cfg(not(test))]
+fn low_power_alarm() {
+    unsafe { LOW_POWER_WAKE = true };
+}
+
+/// Sleep for specified milliseconds, idling the CPU with `wfi`.
+///
+/// Arms TIMER0 for the interval and waits for interrupts with the `wfi`
+/// instruction instead of busy-looping, which saves power. Every time the
+/// CPU wakes (for the TIMER0 alarm or any other interrupt, such as UART
+/// RX), it calls uart::tick() to service the TX DMA queue.
+///
+/// Because TX DMA is only serviced at wake boundaries, queued UART output
+/// can be delayed by up to the whole sleep interval. Use `sleep()` when TX
+/// latency matters. This takes over TIMER0, cancelling any alarm set with
+/// `timer0::set_alarm_ms()`.
+///
+/// Under the TICKTIMER fallback (see `ticktimer::using_fallback()`), TIMER0
+/// is the 1ms clock tick, so this leaves it alone and instead checks the
+/// time each time `wfi` wakes, which the tick does every millisecond.
+#[cfg(not(test))]
+pub fn sleep_low_power(ms: u32) {
+    if ticktimer::using_fallback() {
+        let deadline = ticktimer::Deadline::after_ms(ms);
+        loop {
+            // Same check-then-wfi order as below, on the deadline instead
+            let cs = interrupt::critical_section();
+            if deadline.is_expired() {
+                break;
+            }
+            unsafe { asm!("wfi") };
+            drop(cs);
+            uart::tick();
+        }
+        uart::tick();
+        return;
+    }
+    let mut remaining = ms;
+    while remaining > 0 {
+        let chunk = remaining.min(LOW_POWER_MAX_ALARM_MS);
+        remaining -= chunk;
+        unsafe { core::ptr::write_volatile(&raw mut LOW_POWER_WAKE, false) };
+        timer0::set_alarm_ms(chunk, low_power_alarm);
+        loop {
+            // Check the flag and wfi with interrupts disabled, so an alarm
+            // that fires between the two still wakes wfi (it wakes on a
+            // pending interrupt even when MIE is clear) and is handled once
+            // interrupts are enabled again.
+            let cs = interrupt::critical_section();
+            if unsafe { core::ptr::read_volatile(&raw const LOW_POWER_WAKE) } {
+                break;
+            }
+            unsafe { asm!("wfi") };
+            drop(cs);
+            uart::tick();
+        }
+    }
+    uart::tick();
+}
+
+// ============================================================================
+// Peripheral Handles
+// ============================================================================
+
+/// Owned handles for peripherals that `init()` set up before main().
+///
+/// Returned once by `take_peripherals()`. Passing these handles around (or
+/// into driver structs) makes it visible which code uses which peripheral.
+/// The free functions in each module keep working alongside the handles,
+/// for the C FFI and for code that prefers them, so the handles document
+/// ownership rather than enforce exclusive access.
+#[cfg(not(test))]
+pub struct Peripherals {
+    /// UART2 console, implementing `core::fmt::Write`
+    pub uart: uart::Uart,
+    /// GPIO port A (not on the dabao headers)
+    pub gpio_a: gpio::Port,
+    /// GPIO port B
+    pub gpio_b: gpio::Port,
+    /// GPIO port C
+    pub gpio_c: gpio::Port,
+    /// TIMER0 countdown timer
+    pub timer0: timer0::Timer0,
+}
+
+// Set once take_peripherals() has handed out the handles
+#[cfg(not(test))]
+static mut PERIPHERALS_TAKEN: bool = false;
+
+/// Take the peripheral handles. Returns None on every call after the first.
+///
+/// ```ignore
+/// let mut p = baochip_sdk::take_peripherals().unwrap();
+/// p.gpio_b.set_mask(1 << 12);
+/// p.timer0.set_alarm_ms(500, callback);
+/// writeln!(p.uart, "ready\r").ok();
+/// ```
+#[cfg(not(test))]
+pub fn take_peripherals() -> Option<Peripherals> {
+    let _cs = interrupt::critical_section();
+    unsafe {
+        if PERIPHERALS_TAKEN {
+            return None;
+        }
+        PERIPHERALS_TAKEN = true;
+    }
+    Some(Peripherals {
+        uart: uart::Uart,
+        gpio_a: gpio::Port::new(gpio::GpioPort::PortA),
+        gpio_b: gpio::Port::new(gpio::GpioPort::PortB),
+        gpio_c: gpio::Port::new(gpio::GpioPort::PortC),
+        timer0: timer0::Timer0::new(),
+    })
+}
+
 /// Initialize system state and peripherals at boot.
 ///
 /// Copies .data section from FLASH to RAM, zeros .bss section, and
 /// initializes peripherals (timer and other drivers).
+#[cfg(not(test))]
 fn init() {
     unsafe {
         // Copy .data section from FLASH to RAM
@@ -85,6 +287,12 @@ fn init() {
         let size = _bss_size as *const u8 as usize;
         core::ptr::write_bytes(start, 0, size);
 
+        // Mark the stack limits so check_stack() can detect overflows
+        init_stack_guard();
+
+        // Latch and clear the reset source flags for reset_cause()
+        latch_reset_cause();
+
         // Configure PB13 and PB14 for UART2
         gpio::set_alternate_function(GpioPin::PortB(gpio::PB13), AF::AF1);
         gpio::set_alternate_function(GpioPin::PortB(gpio::PB14), AF::AF1);
@@ -100,10 +308,232 @@ fn init() {
     }
 }
 
+// ============================================================================
+// Stack Guard
+// ============================================================================
+// The main stack grows down from _stack_base toward the end of .bss, and the
+// trap handler's scratch stack grows down from _scratch_stack toward
+// _stack_base. A sentinel word at the low end of each one gets overwritten if
+// that stack overflows.
+
+// Addresses of the main stack and scratch stack sentinel words
+#[cfg(not(test))]
+fn stack_guard_addrs() -> [*mut u32; 2] {
+    let bss_end =
+        _bss_vma as *const u8 as usize + _bss_size as *const u8 as usize;
+    [bss_end as *mut u32, _stack_base as *mut u32]
+}
+
+// Write the sentinel words (called once at boot, after .bss is zeroed)
+#[cfg(not(test))]
+fn init_stack_guard() {
+    for addr in stack_guard_addrs() {
+        unsafe { core::ptr::write_volatile(addr, STACK_SENTINEL) };
+    }
+}
+
+/// Check the stack guard sentinels, halting if a stack has overflowed.
+///
+/// Reads the sentinel words written at boot just below the main stack
+/// (at the end of .bss) and just below the trap handler's scratch stack
+/// (at _stack_base). If either has been overwritten, logs which stack
+/// overflowed and halts. The trap handler calls this after every trap.
+/// Call it periodically from the main loop too, to catch main stack
+/// overflows that happen between interrupts.
+#[cfg(not(test))]
+pub fn check_stack() {
+    let names = ["main", "scratch"];
+    for (addr, name) in stack_guard_addrs().into_iter().zip(names) {
+        if unsafe { core::ptr::read_volatile(addr) } != STACK_SENTINEL {
+            error!(
+                "\r\nSTACK OVERFLOW: {} stack guard at {:p}\r\n",
+                name, addr
+            );
+            uart::flush();
+            loop {
+                core::hint::spin_loop();
+            }
+        }
+    }
+}
+
+// ============================================================================
+// System Reset
+// ============================================================================
+// SYSCTRL (base 0x40040000) has reset control unit registers SFR_RCURST0
+// (offset 0x80) and SFR_RCURST1 (offset 0x84). Writing the key 0x55aa to
+// SFR_RCURST0 resets the whole SoC, the same mechanism the Xous bootloader
+// uses for its reboot command. After the reset, the boot ROM and bootloader
+// run again just like after power-on, then jump back to this firmware.
+
+#[cfg(not(test))]
+const SYSCTRL_SFR_RCURST0: *mut u32 = pac::SYSCTRL_SFR_RCURST0 as *mut u32;
+#[cfg(not(test))]
+const RCURST_KEY: u32 = 0x55aa;
+
+// SFR_RCUSRCFR (offset 0x88) holds 16 reset source flags. A 1 bit means that
+// reset source fired, and writing 1 back clears it. The register resets to
+// 0 at power-on. The register descriptions this SDK is built from only point
+// at sysctrl.sv in the baochip-1x RTL for the meaning of each bit, without
+// listing them, so the flags are reported raw rather than decoded into
+// watchdog, software, brownout, or pin resets.
+#[cfg(not(test))]
+const SYSCTRL_SFR_RCUSRCFR: *mut u32 = pac::SYSCTRL_SFR_RCUSRCFR as *mut u32;
+#[cfg(not(test))]
+const RCUSRCFR_MASK: u32 = 0xffff;
+
+// Flags latched by init(), before they were cleared
+#[cfg(not(test))]
+static mut RESET_FLAGS: u16 = 0;
+
+/// Reason for the most recent reset, as returned by `reset_cause()`
+#[cfg(not(test))]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ResetCause {
+    /// No reset source flag was set, as after a power-on reset
+    PowerOn,
+    /// Raw SFR_RCUSRCFR reset source flags (bit meanings not yet decoded)
+    Flags(u16),
+}
+
+#[cfg(not(test))]
+impl ResetCause {
+    /// Return the raw reset source flags (0 for `PowerOn`).
+    pub fn raw_flags(&self) -> u16 {
+        match *self {
+            ResetCause::PowerOn => 0,
+            ResetCause::Flags(flags) => flags,
+        }
+    }
+}
+
+// Read SFR_RCUSRCFR, then clear the flags that were set so the next reset
+// reports only its own cause
+#[cfg(not(test))]
+fn latch_reset_cause() {
+    unsafe {
+        let flags = core::ptr::read_volatile(SYSCTRL_SFR_RCUSRCFR);
+        core::ptr::write_volatile(SYSCTRL_SFR_RCUSRCFR, flags & RCUSRCFR_MASK);
+        RESET_FLAGS = (flags & RCUSRCFR_MASK) as u16;
+    }
+}
+
+/// Return why the chip last reset.
+///
+/// init() reads the SYSCTRL SFR_RCUSRCFR reset source flags at boot and
+/// clears them, so this reports the cause of the reset that started the
+/// current run, however often it is called. Returns `PowerOn` if no flag
+/// was set, or the raw flags otherwise (see the notes above the register
+/// constant for why they are not decoded further). Comparing against the
+/// flags seen after a known watchdog or `reset()` lets firmware tell
+/// restarts apart and avoid boot loops.
+#[cfg(not(test))]
+pub fn reset_cause() -> ResetCause {
+    match unsafe { RESET_FLAGS } {
+        0 => ResetCause::PowerOn,
+        flags => ResetCause::Flags(flags),
+    }
+}
+
+/// Reset the SoC, as if the board had been power cycled.
+///
+/// Sends any queued UART output first so log messages are not lost, then
+/// writes the reset key to SYSCTRL SFR_RCURST0. Does not return.
+#[cfg(not(test))]
+pub fn reset() -> ! {
+    uart::flush();
+    interrupt::disable_irqs();
+    unsafe { core::ptr::write_volatile(SYSCTRL_SFR_RCURST0, RCURST_KEY) };
+    // The reset takes effect within a few cycles
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
 /// Panic Handler for no_std.
+///
+/// Disables interrupts, prints the panic message and location with polled
+/// UART writes (bypassing the TX DMA queue, which may be what broke), then
+/// blinks SOS on the PB12 debug LED forever. The blink timing uses raw
+/// `mcycle` delay loops rather than timers or interrupts, so it works even
+/// when nothing is connected to the UART.
+#[cfg(not(test))]
 #[panic_handler]
-pub fn panic(_panic_info: &PanicInfo) -> ! {
-    loop {}
+pub fn panic(panic_info: &PanicInfo) -> ! {
+    interrupt::disable_irqs();
+    let mut out = uart::PolledUart;
+    let _ = write!(out, "\r\nPANIC: {}", panic_info.message());
+    if let Some(location) = panic_info.location() {
+        let _ = write!(out, " at {}:{}", location.file(), location.line());
+    }
+    let _ = out.write_str("\r\n");
+    panic_blink_sos()
+}
+
+// Morse timing for the panic blink, in dot units of PANIC_DOT_MS
+#[cfg(not(test))]
+const PANIC_DOT_MS: u32 = 150;
+#[cfg(not(test))]
+const PANIC_SOS: [u32; 9] = [1, 1, 1, 3, 3, 3, 1, 1, 1];
+
+// PB12 debug LED registers. The panic and fatal trap paths write these
+// directly instead of using gpio::Led, which checks pin claims (the LED pin
+// may be claimed by whatever code crashed) and takes critical sections.
+#[cfg(not(test))]
+const PANIC_LED_AFSEL: usize = pac::IOX_BASE + 0x00c; // AFSEL port B 8-15
+#[cfg(not(test))]
+const PANIC_LED_AF_FIELD: u16 = 0b11 << 8; // PB12's 2-bit AF field
+#[cfg(not(test))]
+const PANIC_LED_OE: usize = pac::IOX_BASE + 0x14c; // GPIOOE port B
+#[cfg(not(test))]
+const PANIC_LED_OUT: usize = pac::IOX_BASE + 0x134; // GPIOOUT port B
+#[cfg(not(test))]
+const PANIC_LED_MASK: u16 = 1 << 12;
+
+// Blink ... --- ... on the debug LED at PB12, forever
+#[cfg(not(test))]
+fn panic_blink_sos() -> ! {
+    fatal_led_on();
+    loop {
+        for (i, &units) in PANIC_SOS.iter().enumerate() {
+            panic_led(true);
+            panic_delay_ms(units * PANIC_DOT_MS);
+            panic_led(false);
+            // 1 unit between symbols, 3 between letters
+            let gap = if i % 3 == 2 { 3 } else { 1 };
+            panic_delay_ms(gap * PANIC_DOT_MS);
+        }
+        // 7 units between words (3 already elapsed after the last letter)
+        panic_delay_ms(4 * PANIC_DOT_MS);
+    }
+}
+
+// Take over the debug LED pin and turn the LED on, for the panic handler
+// and fatal traps. Selects AF0 (GPIO) and enables the output. Call with
+// interrupts already disabled.
+#[cfg(not(test))]
+pub(crate) fn fatal_led_on() {
+    unsafe {
+        mmio::modify16(PANIC_LED_AFSEL, PANIC_LED_AF_FIELD, 0);
+        mmio::modify16(PANIC_LED_OE, 0, PANIC_LED_MASK);
+    }
+    panic_led(true);
+}
+
+// Turn the debug LED on or off, with interrupts already disabled
+#[cfg(not(test))]
+fn panic_led(on: bool) {
+    let (clear, set) =
+        if on { (0, PANIC_LED_MASK) } else { (PANIC_LED_MASK, 0) };
+    unsafe { mmio::modify16(PANIC_LED_OUT, clear, set) };
+}
+
+// Busy-wait without the ticktimer, which may not be safe to use here
+#[cfg(not(test))]
+fn panic_delay_ms(ms: u32) {
+    for _ in 0..ms {
+        cycles::delay_ns(1_000_000);
+    }
 }
 
 // ============================================================================
@@ -117,6 +547,7 @@ pub fn panic(_panic_info: &PanicInfo) -> ! {
 /// This function blocks (with sleep calls) until a character is available.
 /// The sleep() calls ensure that the transmit DMA queue is serviced while
 /// waiting, preventing TX stalls.
+#[cfg(not(test))]
 #[unsafe(no_mangle)]
 pub extern "C" fn dbs_uart_read_char() -> u8 {
     loop {
@@ -127,11 +558,49 @@ pub extern "C" fn dbs_uart_read_char() -> u8 {
     }
 }
 
+/// Read one character from UART2, waiting at most `ms` milliseconds.
+///
+/// Returns the character (0-255), or -1 if none arrived before the
+/// timeout. TX DMA is serviced while waiting. With `ms` of 0 this returns
+/// immediately, which gives a non-blocking single-character read.
+#[cfg(not(test))]
+#[unsafe(no_mangle)]
+pub extern "C" fn dbs_uart_read_char_timeout(ms: u32) -> i32 {
+    match uart::getc_timeout(ms) {
+        Some(byte) => byte as i32,
+        None => -1,
+    }
+}
+
+/// Read available characters from UART2 without blocking.
+///
+/// Copies up to `max_len` bytes from the RX ring buffer into `buf` and
+/// returns the number of bytes copied, which is 0 if nothing is waiting
+/// (or `buf` is null).
+#[cfg(not(test))]
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)] // C callers own the buffer
+pub extern "C" fn dbs_uart_read(buf: *mut u8, max_len: usize) -> usize {
+    if buf.is_null() {
+        return 0;
+    }
+    let slice = unsafe { core::slice::from_raw_parts_mut(buf, max_len) };
+    uart::read(slice)
+}
+
+/// Return the number of received UART2 characters waiting to be read.
+#[cfg(not(test))]
+#[unsafe(no_mangle)]
+pub extern "C" fn dbs_uart_rx_available() -> usize {
+    uart::rx_available()
+}
+
 /// Write data to UART2.
 ///
 /// Queues the data for transmission via DMA. The write is non-blocking;
 /// the function returns immediately. Call dbs_uart_tick() periodically
 /// to service the TX DMA queue.
+#[cfg(not(test))]
 #[unsafe(no_mangle)]
 pub extern "C" fn dbs_uart_write(data: *const u8, len: usize) {
     let slice = unsafe { core::slice::from_raw_parts(data, len) };
@@ -145,15 +614,27 @@ pub extern "C" fn dbs_uart_write(data: *const u8, len: usize) {
 ///
 /// Call periodically from your main event loop. Also called automatically
 /// by sleep() and dbs_uart_read_char().
+#[cfg(not(test))]
 #[unsafe(no_mangle)]
 pub extern "C" fn dbs_uart_tick() {
     uart::tick();
 }
 
+/// Block until all queued UART2 transmit data has been sent.
+///
+/// Services the TX DMA queue until it is empty and the last byte has left
+/// the UART. Call this before a reset or before printing a final prompt.
+#[cfg(not(test))]
+#[unsafe(no_mangle)]
+pub extern "C" fn dbs_uart_flush() {
+    uart::flush();
+}
+
 /// Sleep for specified milliseconds, servicing UART transmit DMA.
 ///
 /// Blocks until the specified time has elapsed. Calls dbs_uart_tick()
 /// periodically to ensure UART transmit queue is serviced.
+#[cfg(not(test))]
 #[unsafe(no_mangle)]
 pub extern "C" fn dbs_timer_sleep_ms(ms: u32) {
     sleep(ms);
@@ -162,7 +643,133 @@ pub extern "C" fn dbs_timer_sleep_ms(ms: u32) {
 /// Get current system time in milliseconds.
 ///
 /// Returns the number of milliseconds elapsed since system boot.
+#[cfg(not(test))]
 #[unsafe(no_mangle)]
 pub extern "C" fn dbs_timer_millis() -> u64 {
     ticktimer::millis()
 }
+
+/// Configure a GPIO pin (AF0) as an output.
+///
+/// Port 0 is port B, port 1 is port C, and port 2 is port A, pins are
+/// 0-15. Returns 0 on success or -1 if the port or pin is out of range or
+/// the pin is claimed by a driver (e.g. the UART2 pins).
+#[cfg(not(test))]
+#[unsafe(no_mangle)]
+pub extern "C" fn dbs_gpio_set_output(port: u8, pin: u8) -> i32 {
+    let Some(pin) = GpioPin::from_numbers(port, pin) else {
+        return -1;
+    };
+    if gpio::is_claimed(pin) {
+        return -1;
+    }
+    gpio::set_alternate_function(pin, AF::AF0);
+    gpio::enable_output(pin);
+    0
+}
+
+/// Set a GPIO output pin high.
+///
+/// Returns 0 on success or -1 if the port or pin is out of range.
+#[cfg(not(test))]
+#[unsafe(no_mangle)]
+pub extern "C" fn dbs_gpio_set(port: u8, pin: u8) -> i32 {
+    let Some(pin) = GpioPin::from_numbers(port, pin) else {
+        return -1;
+    };
+    gpio::set(pin);
+    0
+}
+
+/// Set a GPIO output pin low.
+///
+/// Returns 0 on success or -1 if the port or pin is out of range.
+#[cfg(not(test))]
+#[unsafe(no_mangle)]
+pub extern "C" fn dbs_gpio_clear(port: u8, pin: u8) -> i32 {
+    let Some(pin) = GpioPin::from_numbers(port, pin) else {
+        return -1;
+    };
+    gpio::clear(pin);
+    0
+}
+
+/// Read the input level of a GPIO pin.
+///
+/// Returns 1 (high), 0 (low), or -1 if the port or pin is out of range.
+#[cfg(not(test))]
+#[unsafe(no_mangle)]
+pub extern "C" fn dbs_gpio_read(port: u8, pin: u8) -> i32 {
+    let Some(pin) = GpioPin::from_numbers(port, pin) else {
+        return -1;
+    };
+    gpio::is_high(pin) as i32
+}
+
+/// Select the alternate function (0-3) of a GPIO pin.
+///
+/// AF0 is GPIO. Returns 0 on success or -1 if the port, pin, or alternate
+/// function is out of range, or the pin is claimed by a driver.
+#[cfg(not(test))]
+#[unsafe(no_mangle)]
+pub extern "C" fn dbs_gpio_set_af(port: u8, pin: u8, af: u8) -> i32 {
+    let Some(pin) = GpioPin::from_numbers(port, pin) else {
+        return -1;
+    };
+    if gpio::is_claimed(pin) {
+        return -1;
+    }
+    let af = match af {
+        0 => AF::AF0,
+        1 => AF::AF1,
+        2 => AF::AF2,
+        3 => AF::AF3,
+        _ => return -1,
+    };
+    gpio::set_alternate_function(pin, af);
+    0
+}
+
+/// Reset the SoC (like MicroPython's `machine.reset()`).
+///
+/// Flushes UART output, then triggers a full system reset. Does not return.
+#[cfg(not(test))]
+#[unsafe(no_mangle)]
+pub extern "C" fn dbs_reset() -> ! {
+    reset()
+}
+
+/// Return the reset source flags latched at boot (0 after power-on).
+///
+/// Same as `reset_cause().raw_flags()`, widened for C.
+#[cfg(not(test))]
+#[unsafe(no_mangle)]
+pub extern "C" fn dbs_reset_cause() -> u32 {
+    reset_cause().raw_flags() as u32
+}
+
+/// Return a pseudo-random 32-bit value (not for cryptographic use).
+///
+/// Uses the software PRNG in the prng module, which is not a hardware
+/// entropy source.
+#[cfg(not(test))]
+#[unsafe(no_mangle)]
+pub extern "C" fn dbs_random_u32() -> u32 {
+    prng::next_u32()
+}
+
+/// Start the watchdog, resetting the SoC if not fed within `timeout_ms`.
+///
+/// The watchdog uses TIMER0 while it runs (see the watchdog module docs).
+#[cfg(not(test))]
+#[unsafe(no_mangle)]
+pub extern "C" fn dbs_watchdog_start(timeout_ms: u32) {
+    watchdog::start(timeout_ms);
+}
+
+/// Feed the watchdog, moving its deadline to the timeout from now.
+#[cfg(not(test))]
+#[unsafe(no_mangle)]
+pub extern "C" fn dbs_watchdog_feed() {
+    watchdog::feed();
+}
@@ -1,41 +1,308 @@
 // SPDX-License-Identifier: MIT
 // SPDX-FileCopyrightText: Copyright 2026 Sam Blenny
 
-use crate::uart;
+use crate::{ticktimer, uart};
 use core::fmt::{self, Write};
 
-struct SliceWriter<'a> {
-    buf: &'a mut [u8],
+/// Destination for `log!` output
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Sink {
+    /// UART2 (PB13/PB14) at 1 Mbps, the default
+    Uart,
+    /// USB CDC-ACM serial port. The USB driver has no data path yet, so
+    /// this sink currently falls back to UART2 rather than dropping output.
+    Usb,
+}
+
+static mut LOG_SINK: Sink = Sink::Uart;
+
+/// Log message severity, from most to least severe
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 0,
+    Warn = 1,
+    Info = 2,
+    Debug = 3,
+}
+
+// Messages less severe than this are skipped
+static mut LOG_LEVEL: Level = Level::Debug;
+
+// Prefix each message with "[<millis>] " when true
+static mut LOG_TIMESTAMPS: bool = false;
+
+// Size of the buffer each log message is formatted into
+const LOG_BUF_LEN: usize = 128;
+
+// Replaces the end of a message that doesn't fit in LOG_BUF_LEN bytes
+const TRUNCATED_MARKER: &str = "...\r\n";
+
+/// Select where `log!` output goes. Takes effect on the next message.
+pub fn set_log_sink(sink: Sink) {
+    unsafe { LOG_SINK = sink };
+}
+
+/// Fixed-size stack buffer for building strings with `write!`.
+///
+/// Implements `core::fmt::Write` into an `N`-byte array, so a formatted
+/// string can be measured, inspected, or sent somewhere other than the log
+/// sink without an allocator:
+///
+/// ```ignore
+/// use core::fmt::Write;
+/// use baochip_sdk::log::FmtBuf;
+///
+/// let mut buf = FmtBuf::<32>::new();
+/// let _ = write!(buf, "temp={}C", 21);
+/// uart::write(buf.as_bytes());
+/// ```
+///
+/// Text that doesn't fit is truncated at the last whole character that
+/// fits. The write that overflowed returns `Err(fmt::Error)`, which stops
+/// `write!` early, and is_truncated() reports it afterwards.
+///
+/// `log!` formats each message (timestamp included) into a `FmtBuf<128>`
+/// and relies on this truncation: a message that doesn't fit is still
+/// printed, with its last bytes replaced by `...\r\n` to show it was cut.
+pub struct FmtBuf<const N: usize> {
+    buf: [u8; N],
     len: usize,
+    truncated: bool,
+}
+
+impl<const N: usize> FmtBuf<N> {
+    /// Create an empty buffer.
+    pub const fn new() -> Self {
+        FmtBuf { buf: [0; N], len: 0, truncated: false }
+    }
+
+    /// Return the text written so far.
+    pub fn as_str(&self) -> &str {
+        // Only whole UTF-8 characters are ever copied in, so this can't fail
+        core::str::from_utf8(self.as_bytes()).unwrap_or_default()
+    }
+
+    /// Return the bytes written so far.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Return the number of bytes written so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return true if nothing has been written.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return true if some text was dropped because the buffer was full.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Empty the buffer so it can be reused.
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.truncated = false;
+    }
+
+    // Overwrite the end of the text with `marker` (which must fit in N),
+    // backing up to a character boundary
+    fn end_with(&mut self, marker: &str) {
+        let mut len = self.len.min(N - marker.len());
+        while len < self.len && len > 0 && self.buf[len] & 0xc0 == 0x80 {
+            len -= 1;
+        }
+        self.buf[len..len + marker.len()].copy_from_slice(marker.as_bytes());
+        self.len = len + marker.len();
+    }
+}
+
+impl<const N: usize> Default for FmtBuf<N> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl<'a> Write for SliceWriter<'a> {
+impl<const N: usize> Write for FmtBuf<N> {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        let bytes = s.as_bytes();
-        let end = self.len + bytes.len();
-        if end > self.buf.len() {
+        let room = N - self.len;
+        let mut take = s.len().min(room);
+        while !s.is_char_boundary(take) {
+            take -= 1;
+        }
+        self.buf[self.len..self.len + take]
+            .copy_from_slice(&s.as_bytes()[..take]);
+        self.len += take;
+        if take < s.len() {
+            self.truncated = true;
             return Err(fmt::Error);
         }
-        self.buf[self.len..end].copy_from_slice(bytes);
-        self.len = end;
         Ok(())
     }
 }
 
-// This lets us use variadic string format arguments in no_std, no_alloc
+/// Set the runtime log threshold.
+///
+/// Messages less severe than `level` are skipped before formatting, so
+/// filtered-out `debug!` calls cost only a comparison. The default is
+/// `Level::Debug`, which prints everything.
+pub fn set_log_level(level: Level) {
+    unsafe { LOG_LEVEL = level };
+}
+
+/// Enable or disable timestamps on log messages.
+///
+/// When enabled, each `log!` (or leveled macro) invocation is prefixed with
+/// `[<millis>] `, using `ticktimer::millis()` read once per invocation.
+/// Disabled by default.
+pub fn set_log_timestamps(enabled: bool) {
+    unsafe { LOG_TIMESTAMPS = enabled };
+}
+
+/// Return true if messages at `level` pass the current threshold.
+#[inline]
+pub fn enabled(level: Level) -> bool {
+    level <= unsafe { LOG_LEVEL }
+}
+
+// This lets us use variadic string format arguments in no_std, no_alloc.
+// The whole message (timestamp included) is formatted into one FmtBuf and
+// sent with a single sink write, however many fragments the format string
+// has, so a message never interleaves with other output or spreads over
+// more TX blocks than its length needs. A message too long for the buffer
+// is sent cut short, ending with TRUNCATED_MARKER.
 pub fn log_fmt(args: fmt::Arguments<'_>) {
-    let mut buf = [0_u8; 128];
-    let mut writer = SliceWriter { buf: &mut buf, len: 0 };
-    if writer.write_fmt(args).is_ok() {
-        uart::write(&writer.buf[..writer.len]);
+    log_fmt_with_end(args, "");
+}
+
+// Same as log_fmt(), with "\r\n" appended in the same buffer (for logln!)
+#[doc(hidden)]
+pub fn log_fmt_line(args: fmt::Arguments<'_>) {
+    log_fmt_with_end(args, "\r\n");
+}
+
+fn log_fmt_with_end(args: fmt::Arguments<'_>, end: &str) {
+    let mut writer = FmtBuf::<LOG_BUF_LEN>::new();
+    if unsafe { LOG_TIMESTAMPS } {
+        let _ = write!(writer, "[{}] ", ticktimer::millis());
+    }
+    let _ = writer.write_fmt(args).and_then(|_| writer.write_str(end));
+    if writer.is_truncated() {
+        writer.end_with(TRUNCATED_MARKER);
+    }
+    write_sink(writer.as_bytes());
+}
+
+// Send formatted bytes to the selected log sink
+fn write_sink(bytes: &[u8]) {
+    match unsafe { LOG_SINK } {
+        // Sink::Usb goes to UART2 until the USB driver can send CDC data
+        Sink::Uart | Sink::Usb => {
+            uart::write(bytes);
+        }
+    }
+}
+
+/// Log a canonical hexdump of `data`, preceded by `label`.
+///
+/// Each line has the offset, 16 hex bytes, and an ASCII gutter where
+/// non-printable bytes show as `.`:
+/// ```text
+/// usb descriptor (18 bytes):
+/// 00000000  12 01 00 02 02 00 00 40  09 12 01 00 00 01 01 02  |.......@........|
+/// 00000010  03 01                                             |..|
+/// ```
+/// Logged at `Level::Info`, like `log!`. Each line is formatted into a
+/// stack buffer, so this does not allocate.
+pub fn hexdump(label: &str, data: &[u8]) {
+    if !enabled(Level::Info) {
+        return;
     }
+    log_fmt(format_args!("{} ({} bytes):\r\n", label, data.len()));
+    for (i, chunk) in data.chunks(16).enumerate() {
+        let mut w = FmtBuf::<80>::new();
+        // The buffer is sized for a full line, so these writes can't fail
+        let _ = write!(w, "{:08x}  ", i * 16);
+        for col in 0..16 {
+            let _ = match chunk.get(col) {
+                Some(b) => write!(w, "{:02x} ", b),
+                None => w.write_str("   "),
+            };
+            if col == 7 {
+                let _ = w.write_str(" ");
+            }
+        }
+        let _ = w.write_str(" |");
+        for &b in chunk {
+            let c =
+                if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' };
+            let _ = w.write_char(c);
+        }
+        let _ = w.write_str("|\r\n");
+        write_sink(w.as_bytes());
+    }
+}
+
+// Log at an explicit level, skipping the formatting if it's filtered out
+#[doc(hidden)]
+#[macro_export]
+macro_rules! log_at {
+    ($level:expr, $($arg:tt)*) => {
+        if $crate::log::enabled($level) {
+            $crate::log::log_fmt(core::format_args!($($arg)*))
+        }
+    };
 }
 
-// Macro that takes variadic formatting arguments
+// Leveled macros that take variadic formatting arguments
+// example usage: warn!("retrying, attempt {}\r\n", n);
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)*) => { $crate::log_at!($crate::log::Level::Error, $($arg)*) };
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)*) => { $crate::log_at!($crate::log::Level::Warn, $($arg)*) };
+}
+
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => { $crate::log_at!($crate::log::Level::Info, $($arg)*) };
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => { $crate::log_at!($crate::log::Level::Debug, $($arg)*) };
+}
+
+// Hexdump a byte slice with a label
+// example usage: hexdump!("rx block", &buf[..n]);
+#[macro_export]
+macro_rules! hexdump {
+    ($label:expr, $data:expr) => {
+        $crate::log::hexdump($label, $data)
+    };
+}
+
+// Macro that takes variadic formatting arguments, logged at Level::Info
 // example usage: log!("hello, world {:x}\r\n", 42);
 #[macro_export]
 macro_rules! log {
+    ($($arg:tt)*) => { $crate::info!($($arg)*) };
+}
+
+// Like log!, but ends the line with "\r\n" in the same single write
+// example usage: logln!("rx {} bytes", n);
+#[macro_export]
+macro_rules! logln {
+    () => { $crate::logln!("") };
     ($($arg:tt)*) => {
-        $crate::log::log_fmt(core::format_args!($($arg)*))
+        if $crate::log::enabled($crate::log::Level::Info) {
+            $crate::log::log_fmt_line(core::format_args!($($arg)*))
+        }
     };
 }
@@ -0,0 +1,172 @@
+// SPDX-License-Identifier: MIT
+// SPDX-FileCopyrightText: Copyright 2026 Sam Blenny
+//
+//! Raw memory-mapped register access
+//!
+//! Drivers and bring-up code read and write peripheral registers through
+//! these helpers instead of calling `ptr::read_volatile()` and
+//! `ptr::write_volatile()` directly, so there is one place to audit for
+//! MMIO access. Addresses are plain `usize` values, so a register from a
+//! datasheet or `utralib` can be poked without declaring a pointer
+//! constant first:
+//!
+//! ```ignore
+//! use baochip_sdk::mmio;
+//!
+//! // Read the Corigine USB controller's DEVCAP register
+//! let devcap = unsafe { mmio::read32(0x5020_2400) };
+//!
+//! // Set bit 0 and clear bits 4-5 of some control register
+//! unsafe { mmio::modify32(0x5012_f100, 0b11 << 4, 1 << 0) };
+//! ```
+//!
+//! # API Design
+//!
+//! - read8/16/32(): Volatile read of a byte, halfword, or word
+//! - write8/16/32(): Volatile write of a byte, halfword, or word
+//! - modify8/16/32(): Read, clear `clear_mask` bits, set `set_mask` bits,
+//!   and write back
+//! - Every access has a compiler fence on both sides, so the compiler can't
+//!   move ordinary memory accesses (like filling a DMA buffer) across it
+//! - The functions are unsafe because the address is not checked. Passing
+//!   an unmapped address raises a load or store access fault.
+//! - modify*() is not atomic. Callers that share a register with an
+//!   interrupt handler must hold an `interrupt::critical_section()`.
+
+use core::ptr;
+use core::sync::atomic::{Ordering, compiler_fence};
+
+// ============================================================================
+// Reads
+// ============================================================================
+
+/// Read a byte from the register at `addr`.
+///
+/// # Safety
+///
+/// `addr` must be a readable MMIO (or RAM) address.
+#[inline]
+pub unsafe fn read8(addr: usize) -> u8 {
+    compiler_fence(Ordering::SeqCst);
+    let value = unsafe { ptr::read_volatile(addr as *const u8) };
+    compiler_fence(Ordering::SeqCst);
+    value
+}
+
+/// Read a halfword from the register at `addr`.
+///
+/// # Safety
+///
+/// `addr` must be a readable, 2-byte aligned MMIO (or RAM) address.
+#[inline]
+pub unsafe fn read16(addr: usize) -> u16 {
+    compiler_fence(Ordering::SeqCst);
+    let value = unsafe { ptr::read_volatile(addr as *const u16) };
+    compiler_fence(Ordering::SeqCst);
+    value
+}
+
+/// Read a word from the register at `addr`.
+///
+/// # Safety
+///
+/// `addr` must be a readable, 4-byte aligned MMIO (or RAM) address.
+#[inline]
+pub unsafe fn read32(addr: usize) -> u32 {
+    compiler_fence(Ordering::SeqCst);
+    let value = unsafe { ptr::read_volatile(addr as *const u32) };
+    compiler_fence(Ordering::SeqCst);
+    value
+}
+
+// ============================================================================
+// Writes
+// ============================================================================
+
+/// Write a byte to the register at `addr`.
+///
+/// # Safety
+///
+/// `addr` must be a writable MMIO (or RAM) address, and writing `value`
+/// must not break memory that Rust code relies on.
+#[inline]
+pub unsafe fn write8(addr: usize, value: u8) {
+    compiler_fence(Ordering::SeqCst);
+    unsafe { ptr::write_volatile(addr as *mut u8, value) };
+    compiler_fence(Ordering::SeqCst);
+}
+
+/// Write a halfword to the register at `addr`.
+///
+/// # Safety
+///
+/// `addr` must be a writable, 2-byte aligned MMIO (or RAM) address, and
+/// writing `value` must not break memory that Rust code relies on.
+#[inline]
+pub unsafe fn write16(addr: usize, value: u16) {
+    compiler_fence(Ordering::SeqCst);
+    unsafe { ptr::write_volatile(addr as *mut u16, value) };
+    compiler_fence(Ordering::SeqCst);
+}
+
+/// Write a word to the register at `addr`.
+///
+/// # Safety
+///
+/// `addr` must be a writable, 4-byte aligned MMIO (or RAM) address, and
+/// writing `value` must not break memory that Rust code relies on.
+#[inline]
+pub unsafe fn write32(addr: usize, value: u32) {
+    compiler_fence(Ordering::SeqCst);
+    unsafe { ptr::write_volatile(addr as *mut u32, value) };
+    compiler_fence(Ordering::SeqCst);
+}
+
+// ============================================================================
+// Read-Modify-Write
+// ============================================================================
+
+/// Clear the `clear_mask` bits and set the `set_mask` bits of a byte
+/// register, returning the value written.
+///
+/// # Safety
+///
+/// Same as `read8()` and `write8()`. Not atomic (see the module docs).
+#[inline]
+pub unsafe fn modify8(addr: usize, clear_mask: u8, set_mask: u8) -> u8 {
+    unsafe {
+        let value = (read8(addr) & !clear_mask) | set_mask;
+        write8(addr, value);
+        value
+    }
+}
+
+/// Clear the `clear_mask` bits and set the `set_mask` bits of a halfword
+/// register, returning the value written.
+///
+/// # Safety
+///
+/// Same as `read16()` and `write16()`. Not atomic (see the module docs).
+#[inline]
+pub unsafe fn modify16(addr: usize, clear_mask: u16, set_mask: u16) -> u16 {
+    unsafe {
+        let value = (read16(addr) & !clear_mask) | set_mask;
+        write16(addr, value);
+        value
+    }
+}
+
+/// Clear the `clear_mask` bits and set the `set_mask` bits of a word
+/// register, returning the value written.
+///
+/// # Safety
+///
+/// Same as `read32()` and `write32()`. Not atomic (see the module docs).
+#[inline]
+pub unsafe fn modify32(addr: usize, clear_mask: u32, set_mask: u32) -> u32 {
+    unsafe {
+        let value = (read32(addr) & !clear_mask) | set_mask;
+        write32(addr, value);
+        value
+    }
+}
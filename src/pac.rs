@@ -0,0 +1,224 @@
+// SPDX-License-Identifier: MIT
+// SPDX-FileCopyrightText: Copyright 2026 Sam Blenny
+//
+//! Peripheral access constants for the bao1x
+//!
+//! Collects the peripheral base addresses, IFRAM buffer layout, clock
+//! frequencies, and register bits that more than one module relies on, so
+//! porting to a revised chip is a change to this file rather than a search
+//! through every driver. The addresses match `utralib`'s generated bao1x
+//! register map.
+//!
+//! # What Lives Here
+//!
+//! - Base address of each peripheral block the SDK touches
+//! - The IFRAM DMA buffer map, which several drivers share
+//! - Bits that cross module boundaries: uDMA channel CFG bits (same in
+//!   every uDMA peripheral) and the IRQARRAY event bits that drivers
+//!   enable and the trap handler dispatches
+//!
+//! Register offsets within a block, and bit fields only one driver
+//! interprets, stay in that driver next to the code that uses them. A
+//! driver builds its register addresses from a base here, e.g.
+//! `pac::TIMER0_BASE + 0x08` for TIMER0 EN.
+//!
+//! # API Design
+//!
+//! - Plain `pub const` values, usable in other constants and by
+//!   applications that poke registers through `mmio`
+//! - Addresses are `usize` and bits are `u32`, matching `mmio`
+//! - Instance blocks (UART0-3, SPIM0-3, I2C0-3) give the first base and a
+//!   stride
+
+// ============================================================================
+// Clocks
+// ============================================================================
+
+/// ACLK frequency left by the bootloader (CPU, TICKTIMER, TIMER0, D11CTIME)
+pub const ACLK_HZ: u32 = 350_000_000;
+
+/// PERCLK frequency left by the bootloader (uDMA peripherals, PWM)
+pub const PERCLK_HZ: u32 = 100_000_000;
+
+// ============================================================================
+// System Control
+// ============================================================================
+
+/// SYSCTRL: clock tree, PLL, and reset control
+pub const SYSCTRL_BASE: usize = 0x4004_0000;
+
+/// SYSCTRL SFR_RCURST0: reset control (write to reset the chip)
+pub const SYSCTRL_SFR_RCURST0: usize = SYSCTRL_BASE + 0x80;
+
+/// SYSCTRL SFR_RCUSRCFR: reset source flags (write 1 to clear)
+pub const SYSCTRL_SFR_RCUSRCFR: usize = SYSCTRL_BASE + 0x88;
+
+/// WDG_INTF: watchdog interface
+pub const WDG_INTF_BASE: usize = 0x4004_1000;
+
+// ============================================================================
+// CSR Bus Peripherals
+// ============================================================================
+
+/// D11CTIME: free-running deadline timer
+pub const D11CTIME_BASE: usize = 0xe000_0000;
+
+/// TICKTIMER: 64-bit microsecond counter
+pub const TICKTIMER_BASE: usize = 0xe001_b000;
+
+/// TIMER0: countdown timer with an interrupt
+pub const TIMER0_BASE: usize = 0xe001_c000;
+
+/// Base address of each IRQARRAY bank, indexed by bank number.
+///
+/// The banks are not in numeric order: 10-19 sit between 1 and 2.
+pub const IRQARRAY_BASES: [usize; 20] = [
+    0xe000_4000, // IRQARRAY0
+    0xe000_5000, // IRQARRAY1
+    0xe001_0000, // IRQARRAY2
+    0xe001_1000, // IRQARRAY3
+    0xe001_2000, // IRQARRAY4
+    0xe001_3000, // IRQARRAY5
+    0xe001_4000, // IRQARRAY6
+    0xe001_5000, // IRQARRAY7
+    0xe001_6000, // IRQARRAY8
+    0xe001_7000, // IRQARRAY9
+    0xe000_6000, // IRQARRAY10
+    0xe000_7000, // IRQARRAY11
+    0xe000_8000, // IRQARRAY12
+    0xe000_9000, // IRQARRAY13
+    0xe000_a000, // IRQARRAY14
+    0xe000_b000, // IRQARRAY15
+    0xe000_c000, // IRQARRAY16
+    0xe000_d000, // IRQARRAY17
+    0xe000_e000, // IRQARRAY18
+    0xe000_f000, // IRQARRAY19
+];
+
+// ============================================================================
+// IRQARRAY Event Bits
+// ============================================================================
+
+/// USBC (USB controller) event, in IRQARRAY1
+pub const IRQARRAY1_USBC: u32 = 1 << 0;
+
+/// UARTn RX_CHAR event, in IRQARRAY5. UART0-3 are 4 bits apart, so UARTn
+/// is `IRQARRAY5_UART0_RX_CHAR << (4 * n)`.
+pub const IRQARRAY5_UART0_RX_CHAR: u32 = 1 << 2;
+
+/// UART2 RX_CHAR event, in IRQARRAY5
+pub const IRQARRAY5_UART2_RX_CHAR: u32 = IRQARRAY5_UART0_RX_CHAR << 8;
+
+/// IOXIRQ (GPIO pin-change) event, in IRQARRAY10
+pub const IRQARRAY10_IOXIRQ: u32 = 1 << 0;
+
+// ============================================================================
+// IFSUB Peripherals
+// ============================================================================
+
+/// uDMA control block (REG_CG clock gating at offset 0)
+pub const UDMA_CTRL_BASE: usize = 0x5010_0000;
+
+/// Spacing between uDMA peripheral register blocks
+pub const UDMA_STRIDE: usize = 0x1000;
+
+/// uDMA UART0 (UART1-3 follow at `UDMA_STRIDE` intervals)
+pub const UDMA_UART0_BASE: usize = 0x5010_1000;
+
+/// uDMA UART2, the console UART on the dabao PB13/PB14 pins
+pub const UDMA_UART2_BASE: usize = UDMA_UART0_BASE + 2 * UDMA_STRIDE;
+
+/// uDMA SPIM0 (SPIM1-3 follow at `UDMA_STRIDE` intervals)
+pub const UDMA_SPIM0_BASE: usize = 0x5010_5000;
+
+/// uDMA I2C0 (I2C1-3 follow at `UDMA_STRIDE` intervals)
+pub const UDMA_I2C0_BASE: usize = 0x5010_9000;
+
+/// uDMA channel CFG register: enable (start) the transfer
+pub const UDMA_CFG_EN: u32 = 1 << 4;
+
+/// uDMA channel CFG register: stop the channel and clear its state
+pub const UDMA_CFG_CLR: u32 = 1 << 6;
+
+/// PWM: timer PWM outputs
+pub const PWM_BASE: usize = 0x5012_0000;
+
+/// IOX: GPIO, alternate function, and pad control (see `gpio`)
+///
+/// Each per-port register bank has one 32-bit register per port, in the
+/// order A, B, C, ... (4 bytes apart):
+///
+/// | Register       | Offset | Port A      | Port B      | Port C      |
+/// |----------------|--------|-------------|-------------|-------------|
+/// | AFSEL (low)    | 0x000  | 0x5012f000  | 0x5012f008  | 0x5012f010  |
+/// | AFSEL (high)   | 0x004  | 0x5012f004  | 0x5012f00c  | 0x5012f014  |
+/// | INTCR0-7       | 0x100  | (channels, not per port)                |
+/// | INTFR          | 0x120  | (channels, not per port)                |
+/// | GPIOOUT        | 0x130  | 0x5012f130  | 0x5012f134  | 0x5012f138  |
+/// | GPIOOE         | 0x148  | 0x5012f148  | 0x5012f14c  | 0x5012f150  |
+/// | GPIOPU         | 0x160  | 0x5012f160  | 0x5012f164  | 0x5012f168  |
+/// | GPIOIN         | 0x178  | 0x5012f178  | 0x5012f17c  | 0x5012f180  |
+/// | GPIOCFG_SCHM   | 0x230  | 0x5012f230  | 0x5012f234  | 0x5012f238  |
+/// | GPIOCFG_RATCLR | 0x248  | 0x5012f248  | 0x5012f24c  | 0x5012f250  |
+/// | GPIOCFG_DRVSEL | 0x260  | 0x5012f260  | 0x5012f264  | 0x5012f268  |
+///
+/// AFSEL is the exception, with two registers (pins 0-7 and 8-15) per
+/// port, 8 bytes apart.
+pub const IOX_BASE: usize = 0x5012_f000;
+
+/// Corigine USB controller DEVCAP register
+pub const USB_CORIGINE_DEVCAP: usize = 0x5020_2400;
+
+// ============================================================================
+// IFRAM DMA Buffers
+// ============================================================================
+//
+// The uDMA address registers only reach the first 4KB of IFRAM0, so every
+// DMA buffer lives in this map:
+//
+// | Range                   | Owner                                     |
+// |-------------------------|-------------------------------------------|
+// | 0x50000000 - 0x500007FF | uart: TX blocks (2KB)                     |
+// | 0x50000600 - 0x500007FF | serial: TX blocks, 128 bytes per port,    |
+// |                         | lent by uart once a serial port is set up |
+// | 0x50000800 - 0x500008FF | i2c: command words (64 bytes used)        |
+// | 0x500008FC              | uart: write_byte_blocking() byte          |
+// | 0x50000900 - 0x500009FF | i2c: TX data                              |
+// | 0x50000A00 - 0x50000AFF | i2c: RX data                              |
+// | 0x50000B00 - 0x50000BFF | spi: command words                        |
+// | 0x50000C00 - 0x50000DFF | spi: TX data                              |
+// | 0x50000E00 - 0x50000FFF | spi: RX data                              |
+
+/// IFRAM0: 128KB of RAM the uDMA engine can reach
+pub const IFRAM0_BASE: usize = 0x5000_0000;
+
+/// uart TX block area (see `uart::init_with_tx_buffer()`)
+pub const IFRAM_UART_TX: usize = IFRAM0_BASE;
+
+/// Size of the uart TX block area
+pub const IFRAM_UART_TX_SIZE: usize = 0x800;
+
+/// serial TX blocks for UART0, UART1, and UART3, in the top 512 bytes of
+/// the uart TX area (see `uart::init_with_tx_buffer()`)
+pub const IFRAM_SERIAL_TX: usize = IFRAM0_BASE + 0x600;
+
+/// i2c command buffer
+pub const IFRAM_I2C_CMD: usize = IFRAM0_BASE + 0x800;
+
+/// uart polled TX byte, in the unused tail of the i2c command buffer
+pub const IFRAM_UART_POLLED_TX: usize = IFRAM0_BASE + 0x8fc;
+
+/// i2c TX data buffer
+pub const IFRAM_I2C_TX: usize = IFRAM0_BASE + 0x900;
+
+/// i2c RX data buffer
+pub const IFRAM_I2C_RX: usize = IFRAM0_BASE + 0xa00;
+
+/// spi command buffer
+pub const IFRAM_SPI_CMD: usize = IFRAM0_BASE + 0xb00;
+
+/// spi TX data buffer
+pub const IFRAM_SPI_TX: usize = IFRAM0_BASE + 0xc00;
+
+/// spi RX data buffer
+pub const IFRAM_SPI_RX: usize = IFRAM0_BASE + 0xe00;
@@ -0,0 +1,127 @@
+// SPDX-License-Identifier: MIT
+// SPDX-FileCopyrightText: Copyright 2026 Sam Blenny
+//
+//! Non-cryptographic pseudo-random numbers for bao1x dabao evaluation board
+//!
+//! NOT FOR SECURITY USE. This is a software PRNG (SplitMix64) seeded from
+//! the tick timer. Its output is predictable from a few samples, and the
+//! seed is nearly the same on every boot. Do not use it for keys, nonces,
+//! tokens, or anything else that must be unpredictable. It is meant for
+//! test data, backoff jitter, and similar jobs.
+//!
+//! # Usage
+//!
+//! ```ignore
+//! use baochip_sdk::prng;
+//!
+//! prng::init();
+//! let n = prng::next_u32();
+//! let mut buf = [0u8; 16];
+//! prng::fill(&mut buf);
+//! ```
+//!
+//! # Hardware TRNG
+//!
+//! The bao1x has a TRNG (base 0x4002e000, random data in SFR_BUF at
+//! 0x30), but the register descriptions this SDK is built from do not give
+//! its enable values, the SFR_AR_GEN start key, or which SFR_SR bit means
+//! SFR_BUF holds a fresh word. Reading SFR_BUF without the right setup
+//! would give stale data that looks random, so the SDK has no TRNG driver
+//! yet and no source of real entropy.
+//!
+//! # Algorithm
+//!
+//! SplitMix64: a 64-bit counter stepped by a fixed odd constant and passed
+//! through a mixing function. It is fast, has a period of 2^64, and passes
+//! the usual statistical tests, which is plenty for test data.
+//!
+//! init() seeds the state from `ticktimer::micros()`. Early in boot the
+//! microsecond count is nearly the same on every power-up, so call init()
+//! after something with variable timing (such as waiting for UART input),
+//! or mix in more entropy with add_entropy(). Before init() is called, the
+//! sequence starts from a fixed seed of 0.
+//!
+//! # API Design
+//!
+//! - init(): Seed from the ticktimer
+//! - add_entropy(): Mix a value into the state
+//! - next_u32(): Return the next 32-bit value
+//! - fill(): Fill a buffer with random bytes
+
+use crate::interrupt;
+use crate::ticktimer;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+// SplitMix64 constants
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+const MIX_MUL1: u64 = 0xbf58_476d_1ce4_e5b9;
+const MIX_MUL2: u64 = 0x94d0_49bb_1331_11eb;
+
+// ============================================================================
+// Internal State
+// ============================================================================
+
+static mut STATE: u64 = 0;
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Seed the generator from the current `ticktimer::micros()` value.
+///
+/// The seed is guessable, so the sequence is not secure (see module docs).
+///
+/// Mixes into the existing state, so calling init() more than once only
+/// adds entropy.
+pub fn init() {
+    add_entropy(ticktimer::micros());
+}
+
+/// Mix `value` into the generator state.
+///
+/// Use this to add timing of external events (button presses, UART input,
+/// ADC noise) so the sequence differs between boots.
+pub fn add_entropy(value: u64) {
+    let _cs = interrupt::critical_section();
+    unsafe { STATE = mix(STATE ^ value) };
+}
+
+/// Return the next pseudo-random 32-bit value.
+///
+/// Not cryptographically secure: do not use it for keys or nonces.
+pub fn next_u32() -> u32 {
+    (next_u64() >> 32) as u32
+}
+
+/// Fill `buf` with pseudo-random bytes.
+///
+/// Not cryptographically secure: do not use it for keys or nonces.
+pub fn fill(buf: &mut [u8]) {
+    for chunk in buf.chunks_mut(8) {
+        let bytes = next_u64().to_le_bytes();
+        chunk.copy_from_slice(&bytes[..chunk.len()]);
+    }
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+// Step the state and return its mixed value
+fn next_u64() -> u64 {
+    let _cs = interrupt::critical_section();
+    unsafe {
+        STATE = STATE.wrapping_add(GOLDEN_GAMMA);
+        mix(STATE)
+    }
+}
+
+// SplitMix64 output function
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(MIX_MUL1);
+    z = (z ^ (z >> 27)).wrapping_mul(MIX_MUL2);
+    z ^ (z >> 31)
+}
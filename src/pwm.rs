@@ -0,0 +1,327 @@
+// SPDX-License-Identifier: MIT
+// SPDX-FileCopyrightText: Copyright 2026 Sam Blenny
+//
+//! PWM output driver for bao1x dabao evaluation board
+//!
+//! Provides fixed-frequency PWM outputs for LED dimming, servos, and
+//! similar jobs, using the PWM timer block and alternate function AF3.
+//!
+//! # Usage
+//!
+//! Dim an LED to 25% brightness, on a pin whose AF3 function is known to
+//! be PWM3 channel 0 (see Pin Mapping below):
+//!
+//! ```ignore
+//! use baochip_sdk::pwm::PwmChannel;
+//!
+//! let mut led = PwmChannel::with_channel(pin, 3, 0, 1000).unwrap();
+//! led.set_duty(0.25);
+//! led.enable();
+//! ```
+//!
+//! Drive a hobby servo (50 Hz, 1-2 ms pulses):
+//!
+//! ```ignore
+//! let mut servo = PwmChannel::with_channel(pin, 0, 1, 50).unwrap();
+//! servo.set_duty(0.075); // 1.5 ms pulse = center position
+//! servo.enable();
+//! ```
+//!
+//! # Hardware Details
+//!
+//! The PWM block (base 0x50120000) has 4 timers (PWM0-PWM3), each with a
+//! 16-bit counter and 4 compare channels. The channels of a timer share its
+//! counter, so they all run at the same frequency. Creating a PwmChannel
+//! sets the frequency of its timer, which changes the frequency of any
+//! other channels already running on that timer.
+//!
+//! # Pin Mapping
+//!
+//! A pin carries a PWM channel when it is set to alternate function AF3.
+//! The AF3 pad map is not part of the register descriptions this SDK is
+//! built from, so there is no constructor that looks the channel up from
+//! the pin. `PwmChannel::with_channel()` takes the timer and channel
+//! explicitly, and the caller is responsible for naming the right ones.
+//!
+//! UNVERIFIED: a plausible guess is that channels are assigned in pin
+//! order, four per timer, the same way on port B and port C. This has not
+//! been checked against documentation or hardware:
+//!
+//! | Timer | Channels 0-3     | Same channels on |
+//! |-------|------------------|------------------|
+//! | PWM0  | PB0-PB3          | PC0-PC3          |
+//! | PWM1  | PB4-PB7          | PC4-PC7          |
+//! | PWM2  | PB8-PB11         | PC8-PC11         |
+//! | PWM3  | PB12-PB15        | PC12-PC15        |
+//!
+//! # Register Map
+//!
+//! Each timer has a block of registers at `0x50120000 + timer * 0x40`:
+//! - 0x00 CMD: Bit 0 start, bit 1 stop, bit 2 update, bit 3 reset
+//! - 0x04 CFG: Bit 12 sawtooth (count up only), bits [23:16] prescaler
+//! - 0x08 TH: Counter start value [15:0] and end value [31:16]
+//!   (UNVERIFIED, see below)
+//! - 0x0C-0x18 CH0_TH-CH3_TH: Compare value [15:0] and mode [18:16]
+//!
+//! CH_EN (0x50120104) bits 0-3 enable the clocks of PWM0-PWM3. The TH
+//! register is not listed in the register descriptions, so its offset and
+//! layout are taken from the PULP adv_timer that the PWM block is believed
+//! to be based on, and have not been checked on hardware.
+//!
+//! # Clock and Duty Cycle
+//!
+//! The timers count PERCLK (100 MHz) cycles, divided by `prescaler + 1`.
+//! with_channel() picks the smallest prescaler that fits one period in the
+//! 16-bit counter, giving the finest duty cycle resolution available at
+//! that frequency. The usable range is about 6 Hz to 1 MHz (at 1 MHz there are
+//! only 100 steps of duty cycle).
+//!
+//! Each channel uses compare mode SETRST: the output goes high when the
+//! counter reaches the channel's compare value and low when the period
+//! ends, so the compare value is `period - high_time`.
+//!
+//! Duty cycles of exactly 0.0 and 1.0 do not use the timer at all. The
+//! comparator cannot produce a clean constant level (a compare value at
+//! either end of the period gives a one-cycle glitch), so for those cases
+//! the pin is switched to GPIO (AF0) and driven low or high.
+//!
+//! # API Design
+//!
+//! - PwmChannel::with_channel(): Claim a pin and set up a timer/channel
+//!   at a frequency
+//! - set_duty(): Set the fraction of each period the output is high
+//! - enable(): Start driving the pin
+//! - disable(): Stop driving the pin (it is left driven low)
+//! - release(): Disable the channel and release the pin claim
+
+use crate::clock;
+use crate::gpio::{self, AF, GpioPin, PinToken};
+use crate::interrupt;
+use crate::pac;
+use core::ptr;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+// PWM block base address and per-timer register stride
+const PWM_BASE: usize = pac::PWM_BASE;
+const TIMER_STRIDE: usize = 0x40;
+
+// Per-timer register offsets
+const REG_CMD: usize = 0x00;
+const REG_CFG: usize = 0x04;
+const REG_TH: usize = 0x08;
+const REG_CH0_TH: usize = 0x0c;
+
+// Shared registers
+const REG_CH_EN: *mut u32 = (PWM_BASE + 0x104) as *mut u32;
+
+// CMD register bits
+const CMD_START: u32 = 1 << 0;
+const CMD_STOP: u32 = 1 << 1;
+const CMD_UPDATE: u32 = 1 << 2;
+
+// CFG register fields
+const CFG_SAW: u32 = 1 << 12;
+const CFG_PRESC_SHIFT: u32 = 16;
+
+// Channel compare mode: set on match, reset at the end of the period
+const CH_MODE_SETRST: u32 = 2 << 16;
+
+const TIMERS: usize = 4;
+const CHANNELS: usize = 4;
+
+// Counter range
+const COUNTER_MAX: u32 = 1 << 16;
+
+// ============================================================================
+// Internal State
+// ============================================================================
+
+// Bitmask of enabled channels for each timer
+static mut ACTIVE_CHANNELS: [u8; TIMERS] = [0; TIMERS];
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// A PWM output on one pin.
+///
+/// The output starts disabled with a duty cycle of 0. The pin is claimed
+/// (see "Pin Claims" in the `gpio` docs) until release(). Dropping the
+/// channel neither stops it nor releases the claim.
+pub struct PwmChannel {
+    pin: GpioPin,
+    token: Option<PinToken>,
+    timer: usize,
+    channel: usize,
+    period: u32, // Counter ticks per period
+    duty: f32,
+    enabled: bool,
+}
+
+impl PwmChannel {
+    /// Set up PWM `timer` (0-3) `channel` (0-3) to drive `pin` at
+    /// `freq_hz`.
+    ///
+    /// The pin to channel mapping is not documented (see Pin Mapping in the
+    /// module docs), so `timer` and `channel` must be the ones the pin's
+    /// AF3 function is wired to.
+    ///
+    /// Returns None if the timer or channel number is out of range, if the
+    /// frequency is outside the range the timer can produce, or if the pin
+    /// is already claimed.
+    pub fn with_channel(
+        pin: GpioPin,
+        timer: usize,
+        channel: usize,
+        freq_hz: u32,
+    ) -> Option<Self> {
+        if timer >= TIMERS || channel >= CHANNELS || freq_hz == 0 {
+            return None;
+        }
+        // Smallest prescaler that fits one period in the 16-bit counter
+        let cycles = clock::perclk_hz() / freq_hz;
+        let presc = cycles.saturating_sub(1) / COUNTER_MAX;
+        if presc > 0xff {
+            return None;
+        }
+        let period = cycles / (presc + 1);
+        if period < 2 {
+            return None;
+        }
+        let token = Some(gpio::try_claim(pin)?);
+
+        // Count up from 0 to period - 1, then start over
+        write_reg(timer, REG_CMD, CMD_STOP);
+        write_reg(timer, REG_CFG, CFG_SAW | (presc << CFG_PRESC_SHIFT));
+        write_reg(timer, REG_TH, (period - 1) << 16);
+        write_reg(timer, REG_CMD, CMD_UPDATE);
+        if unsafe { ACTIVE_CHANNELS[timer] } != 0 {
+            // Other channels on this timer are running, so keep it going
+            write_reg(timer, REG_CMD, CMD_START);
+        }
+
+        Some(PwmChannel {
+            pin,
+            token,
+            timer,
+            channel,
+            period,
+            duty: 0.0,
+            enabled: false,
+        })
+    }
+
+    /// Set the fraction of each period (0.0 to 1.0) the output is high.
+    ///
+    /// Values outside that range are clamped. 0.0 and 1.0 give a constant
+    /// low or high level. Takes effect immediately if the channel is
+    /// enabled.
+    pub fn set_duty(&mut self, fraction: f32) {
+        self.duty = fraction.clamp(0.0, 1.0);
+        if self.enabled {
+            self.apply();
+        }
+    }
+
+    /// The current duty cycle, as set by set_duty().
+    pub fn duty(&self) -> f32 {
+        self.duty
+    }
+
+    /// Start driving the pin at the current duty cycle.
+    pub fn enable(&mut self) {
+        self.enabled = true;
+        self.apply();
+    }
+
+    /// Stop driving the PWM signal, leaving the pin as a GPIO output
+    /// driven low. The timer stops once none of its channels are enabled.
+    pub fn disable(&mut self) {
+        self.enabled = false;
+        self.set_static(false);
+    }
+
+    /// Disable the channel and release the pin claim.
+    ///
+    /// The pin is left as a GPIO output driven low.
+    pub fn release(mut self) {
+        self.disable();
+        if let Some(token) = self.token.take() {
+            gpio::release(token);
+        }
+    }
+
+    // Update the hardware to match the duty cycle
+    fn apply(&mut self) {
+        let high = (self.duty * self.period as f32 + 0.5) as u32;
+        if high == 0 {
+            self.set_static(false);
+        } else if high >= self.period {
+            self.set_static(true);
+        } else {
+            let th = (self.period - high) | CH_MODE_SETRST;
+            write_reg(self.timer, REG_CH0_TH + self.channel * 4, th);
+            self.reconfigure_pin(|pin| {
+                gpio::set_alternate_function(pin, AF::AF3);
+            });
+            self.set_active(true);
+        }
+    }
+
+    // Drive the pin as a constant GPIO level instead of PWM
+    fn set_static(&mut self, high: bool) {
+        if high {
+            gpio::set(self.pin);
+        } else {
+            gpio::clear(self.pin);
+        }
+        self.reconfigure_pin(|pin| {
+            gpio::set_alternate_function(pin, AF::AF0);
+            gpio::enable_output(pin);
+        });
+        self.set_active(false);
+    }
+
+    // Run pin configuration calls with the claim released, as the gpio
+    // configuration functions refuse claimed pins, then claim it again
+    fn reconfigure_pin(&mut self, f: impl FnOnce(GpioPin)) {
+        if let Some(token) = self.token.take() {
+            gpio::release(token);
+        }
+        f(self.pin);
+        self.token = gpio::try_claim(self.pin);
+    }
+
+    // Track which channels use the timer, and run it only while any do
+    fn set_active(&self, active: bool) {
+        let bit = 1 << self.channel;
+        let clk_bit = 1 << self.timer;
+        let _cs = interrupt::critical_section();
+        unsafe {
+            let was = ACTIVE_CHANNELS[self.timer];
+            let now = if active { was | bit } else { was & !bit };
+            ACTIVE_CHANNELS[self.timer] = now;
+            if was == 0 && now != 0 {
+                let en = ptr::read_volatile(REG_CH_EN);
+                ptr::write_volatile(REG_CH_EN, en | clk_bit);
+                write_reg(self.timer, REG_CMD, CMD_START);
+            } else if was != 0 && now == 0 {
+                write_reg(self.timer, REG_CMD, CMD_STOP);
+                let en = ptr::read_volatile(REG_CH_EN);
+                ptr::write_volatile(REG_CH_EN, en & !clk_bit);
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+fn write_reg(timer: usize, offset: usize, value: u32) {
+    let addr = PWM_BASE + timer * TIMER_STRIDE + offset;
+    unsafe { ptr::write_volatile(addr as *mut u32, value) }
+}
@@ -0,0 +1,261 @@
+// SPDX-License-Identifier: MIT
+// SPDX-FileCopyrightText: Copyright 2026 Sam Blenny
+//
+//! Fixed-size queue for passing values from interrupt handlers to main code
+//!
+//! `Queue<T, N>` is a single-producer, single-consumer ring buffer that
+//! holds up to `N` values of a `Copy` type. It is meant to live in a
+//! `static`, so an interrupt callback (the producer) can push events and
+//! the main loop (the consumer) can pop them without a critical section:
+//!
+//! ```ignore
+//! use baochip_sdk::{log, queue::Queue, ticktimer, uart};
+//!
+//! static PRESSES: Queue<u64, 8> = Queue::new();
+//!
+//! fn on_button() {
+//!     // Interrupt context: drop the event if the main loop is behind
+//!     let _ = PRESSES.push(ticktimer::millis());
+//! }
+//!
+//! loop {
+//!     while let Some(ms) = PRESSES.pop() {
+//!         log!("pressed at {}\r\n", ms);
+//!     }
+//!     uart::tick();
+//! }
+//! ```
+//!
+//! # Why No Lock Is Needed
+//!
+//! The CPU has a single hart, so the producer and consumer never run at
+//! the same time. The worst case is an interrupt handler running between
+//! two instructions of the main loop. The queue handles that by giving
+//! each side its own index:
+//! - push() writes the slot, then publishes it by storing `head`
+//! - pop() reads the slot, then frees it by storing `tail`
+//!
+//! Each side only stores its own index, and each index is a single word
+//! store, so the other side sees either the old or the new value, never a
+//! partial one. Volatile index accesses with compiler fences keep the
+//! compiler from moving slot accesses across the index stores.
+//!
+//! This only holds with exactly one producer and one consumer. If both the
+//! main loop and an interrupt handler push to the same queue, values can
+//! be lost or corrupted. Wrap the push in `interrupt::critical_section()`
+//! to share a side between contexts.
+//!
+//! # API Design
+//!
+//! - Queue::new(): Empty queue, usable in a `static` initializer
+//! - push(): Append a value, or return it as `Err` if the queue is full
+//! - pop(): Remove the oldest value, or return None if empty
+//! - len(), is_empty(), is_full(), capacity(): Occupancy checks. These are
+//!   snapshots, since the other side may change the queue right after.
+//! - Full capacity: all `N` slots are usable. The indices count from 0 to
+//!   2N-1, so a full queue (head - tail == N) is distinct from an empty one
+//!   (head == tail) for any `N`, not just powers of two.
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::ptr;
+use core::sync::atomic::{Ordering, compiler_fence};
+
+// ============================================================================
+// Queue
+// ============================================================================
+
+/// Single-producer, single-consumer queue of up to `N` values
+///
+/// See the module docs for the rules on which contexts may push and pop.
+pub struct Queue<T: Copy, const N: usize> {
+    slots: UnsafeCell<[MaybeUninit<T>; N]>,
+    head: UnsafeCell<usize>, // Next index to push, in 0..2N (producer)
+    tail: UnsafeCell<usize>, // Next index to pop, in 0..2N (consumer)
+}
+
+// The producer and consumer each only write their own index, and slots are
+// handed over by the index stores (see "Why No Lock Is Needed").
+unsafe impl<T: Copy + Send, const N: usize> Sync for Queue<T, N> {}
+
+impl<T: Copy, const N: usize> Queue<T, N> {
+    /// Create an empty queue.
+    ///
+    /// Fails to compile if `N` is 0.
+    pub const fn new() -> Self {
+        const { assert!(N > 0, "queue: capacity must be at least 1") };
+        Queue {
+            slots: UnsafeCell::new([MaybeUninit::uninit(); N]),
+            head: UnsafeCell::new(0),
+            tail: UnsafeCell::new(0),
+        }
+    }
+
+    /// Append `value`, or return it as `Err` if the queue is full.
+    ///
+    /// Only the producer may call this.
+    pub fn push(&self, value: T) -> Result<(), T> {
+        let head = self.head();
+        if Self::count(head, self.tail()) == N {
+            return Err(value);
+        }
+        unsafe {
+            // The consumer won't read this slot until head moves past it
+            let slots = self.slots.get();
+            (*slots)[head % N].write(value);
+            compiler_fence(Ordering::SeqCst);
+            ptr::write_volatile(self.head.get(), Self::advance(head));
+        }
+        Ok(())
+    }
+
+    /// Remove and return the oldest value, or None if the queue is empty.
+    ///
+    /// Only the consumer may call this.
+    pub fn pop(&self) -> Option<T> {
+        let tail = self.tail();
+        if Self::count(self.head(), tail) == 0 {
+            return None;
+        }
+        unsafe {
+            // The producer won't reuse this slot until tail moves past it
+            let slots = self.slots.get();
+            let value = (*slots)[tail % N].assume_init();
+            compiler_fence(Ordering::SeqCst);
+            ptr::write_volatile(self.tail.get(), Self::advance(tail));
+            Some(value)
+        }
+    }
+
+    /// Return the number of values waiting to be popped.
+    pub fn len(&self) -> usize {
+        Self::count(self.head(), self.tail())
+    }
+
+    /// Return true if there is nothing to pop.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return true if push() would fail.
+    pub fn is_full(&self) -> bool {
+        self.len() == N
+    }
+
+    /// Return the maximum number of values the queue holds (`N`).
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    // Read the producer's index
+    #[inline]
+    fn head(&self) -> usize {
+        let head = unsafe { ptr::read_volatile(self.head.get()) };
+        compiler_fence(Ordering::SeqCst);
+        head
+    }
+
+    // Read the consumer's index
+    #[inline]
+    fn tail(&self) -> usize {
+        let tail = unsafe { ptr::read_volatile(self.tail.get()) };
+        compiler_fence(Ordering::SeqCst);
+        tail
+    }
+
+    // Number of occupied slots between two indices in 0..2N
+    #[inline]
+    fn count(head: usize, tail: usize) -> usize {
+        if head >= tail { head - tail } else { head + 2 * N - tail }
+    }
+
+    // Step an index, wrapping from 2N-1 back to 0
+    #[inline]
+    fn advance(index: usize) -> usize {
+        if index + 1 == 2 * N { 0 } else { index + 1 }
+    }
+}
+
+impl<T: Copy, const N: usize> Default for Queue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::Queue;
+
+    #[test]
+    fn push_until_full() {
+        let q: Queue<u32, 3> = Queue::new();
+        for i in 0..3 {
+            assert_eq!(q.push(i), Ok(()));
+        }
+        assert!(q.is_full());
+        assert_eq!(q.len(), 3);
+        assert_eq!(q.push(99), Err(99));
+        assert_eq!(q.len(), 3);
+    }
+
+    #[test]
+    fn pop_until_empty() {
+        let q: Queue<u32, 3> = Queue::new();
+        assert_eq!(q.pop(), None);
+        for i in 0..3 {
+            q.push(i).unwrap();
+        }
+        for i in 0..3 {
+            assert_eq!(q.pop(), Some(i));
+        }
+        assert!(q.is_empty());
+        assert_eq!(q.pop(), None);
+    }
+
+    #[test]
+    fn indices_wrap_past_2n() {
+        // N = 3 is not a power of two, so the indices wrap at 6, not at a
+        // bit boundary. Fill and drain repeatedly to cross that point with
+        // the queue empty, partly full, and full.
+        let q: Queue<u32, 3> = Queue::new();
+        let mut next_in = 0;
+        let mut next_out = 0;
+        for round in 0..20 {
+            let fill = round % 3 + 1;
+            for _ in 0..fill {
+                q.push(next_in).unwrap();
+                next_in += 1;
+            }
+            assert_eq!(q.len(), fill as usize);
+            assert_eq!(q.is_full(), fill == 3);
+            if q.is_full() {
+                assert_eq!(q.push(99), Err(99));
+            }
+            for _ in 0..fill {
+                assert_eq!(q.pop(), Some(next_out));
+                next_out += 1;
+            }
+            assert!(q.is_empty());
+        }
+        // 39 values went through, wrapping the indices at 6 several times
+        assert_eq!(next_out, 39);
+    }
+
+    #[test]
+    fn interleaved_push_pop_across_wrap() {
+        // Keep two values queued while the indices go around several times
+        let q: Queue<u32, 3> = Queue::new();
+        q.push(0).unwrap();
+        q.push(1).unwrap();
+        for i in 2..50 {
+            q.push(i).unwrap();
+            assert_eq!(q.len(), 3);
+            assert_eq!(q.pop(), Some(i - 2));
+            assert_eq!(q.len(), 2);
+        }
+    }
+}
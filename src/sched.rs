@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: MIT
+// SPDX-FileCopyrightText: Copyright 2026 Sam Blenny
+//
+//! Cooperative run-to-completion task loop for bao1x dabao evaluation board
+//!
+//! A structured replacement for hand-written main loops that interleave
+//! `uart::tick()`, button polling, and timing checks. Tasks are plain
+//! `fn()` callbacks registered with a period. `run()` loops forever,
+//! servicing the UART TX queue and calling each task when its period has
+//! elapsed according to `ticktimer::millis()`.
+//!
+//! # Overview
+//!
+//! Tasks are `timers::Timer::periodic()` timers, and each pass of the loop
+//! is `uart::tick()` followed by `timers::poll_timers()`. So tasks share
+//! the 8 software timer slots with any `timers::Timer` the application
+//! starts, and those timers fire from the same loop.
+//!
+//! Tasks always run to completion in main-loop context, so they can share
+//! state without critical sections (as long as no interrupt handler
+//! touches it too). A task that blocks delays every other task, so long
+//! jobs should be split into steps that each return quickly. Short periods
+//! suit polling jobs like `gpio::DebouncedInput::update()`.
+//!
+//! # Usage
+//!
+//! ```ignore
+//! use baochip_sdk::sched::{self, Task};
+//!
+//! fn blink() {
+//!     // Toggle an LED
+//! }
+//!
+//! fn poll_button() {
+//!     // Call DebouncedInput::update() and act on edges
+//! }
+//!
+//! let _ = Task::every(500, blink);
+//! let _ = Task::every(1, poll_button);
+//! sched::run();
+//! ```
+//!
+//! # Timing
+//!
+//! A task first runs one period after it is registered. After that, the
+//! timing is that of `timers::Timer::periodic()`: each deadline is the
+//! previous one plus the period, so a task that is a little late does not
+//! drift, and one that falls a whole period behind runs once rather than
+//! in a burst to catch up. Timing resolution is 1ms, plus the time the
+//! other tasks in the pass take, so a period of 0 is treated as 1ms.
+//!
+//! # API Design
+//!
+//! - `Task::every()`: Register a task to run every `period_ms`
+//! - `Task::cancel()`: Remove a task
+//! - `run()`: Loop forever, calling `run_once()`
+//! - `run_once()`: One pass: uart::tick(), then timers::poll_timers()
+//!
+//! Tasks may register or cancel tasks, including themselves, while they
+//! run.
+
+use crate::timers::{self, Timer};
+use crate::uart;
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Handle for a registered task.
+///
+/// Wraps the task's `timers::Timer`, so once a task is cancelled and its
+/// slot reused, an old handle no longer affects it.
+#[derive(Clone, Copy)]
+pub struct Task {
+    timer: Timer,
+}
+
+impl Task {
+    /// Run `task` every `period_ms` milliseconds, starting `period_ms`
+    /// from now.
+    ///
+    /// A period of 0 is treated as 1ms. Returns None if all software timer
+    /// slots are in use.
+    pub fn every(period_ms: u32, task: fn()) -> Option<Task> {
+        Timer::periodic(period_ms, task).map(|timer| Task { timer })
+    }
+
+    /// Remove the task. Does nothing if it was already cancelled.
+    pub fn cancel(self) {
+        self.timer.cancel();
+    }
+}
+
+/// Run the task loop forever.
+///
+/// Calls `run_once()` in a loop. Use this as the last statement of
+/// `main()`, after registering tasks.
+pub fn run() -> ! {
+    loop {
+        run_once();
+    }
+}
+
+/// Make one pass of the task loop.
+///
+/// Services the UART TX queue with uart::tick(), then runs every task and
+/// software timer that is due with timers::poll_timers(). For custom loops
+/// that need to do something between passes.
+pub fn run_once() {
+    uart::tick();
+    timers::poll_timers();
+}
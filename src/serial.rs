@@ -0,0 +1,494 @@
+// SPDX-License-Identifier: MIT
+// SPDX-FileCopyrightText: Copyright 2026 Sam Blenny
+//
+//! Additional UARTs (UART0, UART1, UART3) for bao1x dabao evaluation board
+//!
+//! Provides DMA-based TX and interrupt-fed RX for the uDMA UARTs other than
+//! UART2, for jobs like bridging between two devices. UART2 (the debug
+//! console on PB13/PB14) stays in the `uart` module, and its `uart::init()`
+//! and `uart::write()` work as before.
+//!
+//! # Usage
+//!
+//! ```ignore
+//! use baochip_sdk::{gpio, serial, uart};
+//! use gpio::{AF, GpioPin};
+//! use serial::{Port, SerialPort};
+//!
+//! // Route the UART1 pads first (see Pin Mapping below)
+//! gpio::set_alternate_function(rx_pin, AF::AF1);
+//! gpio::set_alternate_function(tx_pin, AF::AF1);
+//!
+//! let port = SerialPort::init(Port::Uart1, 115_200);
+//! loop {
+//!     // Bridge UART2 and UART1 in both directions
+//!     if let Some(byte) = uart::getc() {
+//!         port.write(&[byte]);
+//!     }
+//!     if let Some(byte) = port.getc() {
+//!         uart::write(&[byte]);
+//!     }
+//!     port.tick();
+//!     uart::tick();
+//! }
+//! ```
+//!
+//! # Hardware Details
+//!
+//! All four UARTs are the same uDMA UART block, clocked by PERCLK (100 MHz),
+//! with the register layout described in the `uart` module:
+//!
+//! | UART  | Base       | uDMA REG_CG bit | IRQARRAY5 RX_CHAR bit |
+//! |-------|------------|-----------------|-----------------------|
+//! | UART0 | 0x50101000 | 0               | 2                     |
+//! | UART1 | 0x50102000 | 1               | 6                     |
+//! | UART2 | 0x50103000 | 2               | 10 (`uart` module)    |
+//! | UART3 | 0x50104000 | 3               | 14                    |
+//!
+//! The baud divisor is `PERCLK / baud`, so rates that don't divide 100 MHz
+//! evenly are slightly off (115200 baud uses divisor 868, 0.01% fast).
+//!
+//! # Pin Mapping
+//!
+//! UART2 is fixed to PB13/PB14 on AF1. The pads for UART0, UART1 and UART3
+//! are not part of the register descriptions this SDK is built from (the
+//! AF1 list in the gpio module only names UART2), so init() does not touch
+//! the GPIO configuration. Set the alternate function of the RX and TX pads
+//! with `gpio::set_alternate_function()` before calling init().
+//!
+//! # Memory Layout
+//!
+//! The uDMA address registers only have 12 bits, so TX buffers must live in
+//! the first 4KB of IFRAM0 alongside the UART2, I2C and SPI buffers. The
+//! serial blocks borrow the top 512 bytes of the UART2 TX area, which the
+//! `uart` module gives up the first time a port is set up here (its
+//! default layout drops from 16x128 to 12x128 blocks). Programs that never
+//! call init() keep the full 2KB for UART2. Each port gets two 64-byte
+//! blocks, so one block can fill while DMA sends the other:
+//! - 0x50000600: UART0 TX blocks (128 bytes)
+//! - 0x50000680: UART1 TX blocks (128 bytes)
+//! - 0x50000700: UART3 TX blocks (128 bytes)
+//!
+//! TX queueing works like the UART2 block FIFO: write() appends to the
+//! current block and tick() starts DMA for full blocks, or hands off a
+//! partial block when DMA is idle. The buffer is small, so call tick()
+//! often, or use write_all() for bursts. RX bytes are moved into a
+//! 128-byte ring per port by the IRQARRAY5 interrupt handler.
+//!
+//! # API Design
+//!
+//! - SerialPort::init(): Set up a UART for 8N1 at a baud rate
+//! - SerialPort::init_with_config(): Same, with a custom frame format
+//! - write(): Queue TX data (non-blocking, returns bytes queued)
+//! - write_all(): Queue all TX data, blocking until there is room
+//! - tick(): Start DMA for ready TX blocks
+//! - flush(): Block until all queued TX data has been sent
+//! - getc(): Read one byte from the RX ring if available
+//! - read(): Read all available bytes from the RX ring, up to a limit
+//! - rx_available(): Number of bytes waiting in the RX ring
+//! - rx_overrun(): Check and clear the RX overrun flag
+//! - SerialPort implements `core::fmt::Write`
+
+use crate::clock;
+use crate::interrupt;
+use crate::irqarray::IRQARRAY5;
+use crate::mmio;
+use crate::pac;
+use crate::uart::{self, UartConfig};
+use core::fmt;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+// uDMA UART register offsets (same block for UART0-UART3)
+const UART0_BASE: usize = pac::UDMA_UART0_BASE;
+const UART_BASE_STRIDE: usize = pac::UDMA_STRIDE;
+const REG_RX_CFG: usize = 0x08;
+const REG_TX_SADDR: usize = 0x10;
+const REG_TX_SIZE: usize = 0x14;
+const REG_TX_CFG: usize = 0x18;
+const REG_STATUS: usize = 0x20;
+const REG_UART_SETUP: usize = 0x24;
+const REG_ERROR: usize = 0x28;
+const REG_IRQ_EN: usize = 0x2c;
+const REG_VALID: usize = 0x30;
+const REG_DATA: usize = 0x34;
+
+// Register bits
+const CFG_EN: u32 = pac::UDMA_CFG_EN;
+const CFG_CLR: u32 = pac::UDMA_CFG_CLR;
+const UART_EN_TX: u32 = 1 << 8;
+const UART_EN_RX: u32 = 1 << 9;
+const UART_RX_CLEAN_FIFO: u32 = 1 << 5;
+const STATUS_TX_BUSY: u32 = 1 << 0;
+const IRQ_EN_RX: u32 = 1 << 0;
+const VALID_DATA_AVAILABLE: u32 = 1 << 0;
+
+// TX buffer configuration
+const IFRAM_TX_ADDR: usize = pac::IFRAM_SERIAL_TX;
+const TX_BLOCK_SIZE: usize = 64;
+const TX_BLOCK_COUNT: usize = 2;
+
+// RX ring buffer configuration
+const RX_RING_SIZE: usize = 128;
+
+const PORTS: usize = 3;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// UART selection (UART2 is handled by the `uart` module).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Port {
+    Uart0 = 0,
+    Uart1 = 1,
+    Uart3 = 3,
+}
+
+impl Port {
+    // Index into the per-port state table
+    fn index(self) -> usize {
+        match self {
+            Port::Uart0 => 0,
+            Port::Uart1 => 1,
+            Port::Uart3 => 2,
+        }
+    }
+
+    fn reg(self, offset: usize) -> usize {
+        UART0_BASE + self as usize * UART_BASE_STRIDE + offset
+    }
+
+    fn clk_bit(self) -> u32 {
+        clock::UDMA_CG_UART0 << self as u32
+    }
+
+    fn rx_char_bit(self) -> u32 {
+        pac::IRQARRAY5_UART0_RX_CHAR << (4 * self as u32)
+    }
+
+    fn tx_block_addr(self, block: usize) -> usize {
+        let port_offset = self.index() * TX_BLOCK_SIZE * TX_BLOCK_COUNT;
+        IFRAM_TX_ADDR + port_offset + block * TX_BLOCK_SIZE
+    }
+}
+
+const ALL_PORTS: [Port; PORTS] = [Port::Uart0, Port::Uart1, Port::Uart3];
+
+// ============================================================================
+// Internal State
+// ============================================================================
+
+// Per-port TX block FIFO and RX ring, with the same meaning as the UART2
+// state in the `uart` module.
+#[derive(Clone, Copy)]
+struct PortState {
+    enabled: bool,
+    tx_next_block: usize,
+    tx_fill_len: usize,
+    tx_block_len: [u8; TX_BLOCK_COUNT],
+    tx_queue_head: usize,
+    tx_in_flight: bool,
+    rx_ring: [u8; RX_RING_SIZE],
+    rx_head: usize,
+    rx_tail: usize,
+    rx_count: usize,
+    rx_overrun: bool,
+}
+
+const IDLE_PORT: PortState = PortState {
+    enabled: false,
+    tx_next_block: 0,
+    tx_fill_len: 0,
+    tx_block_len: [0; TX_BLOCK_COUNT],
+    tx_queue_head: 0,
+    tx_in_flight: false,
+    rx_ring: [0; RX_RING_SIZE],
+    rx_head: 0,
+    rx_tail: 0,
+    rx_count: 0,
+    rx_overrun: false,
+};
+
+static mut STATE: [PortState; PORTS] = [IDLE_PORT; PORTS];
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Handle for one of the additional UARTs.
+///
+/// Handles are cheap to copy. All handles for the same port share its
+/// buffers.
+#[derive(Clone, Copy)]
+pub struct SerialPort {
+    port: Port,
+}
+
+impl SerialPort {
+    /// Set up `port` for 8N1 at `baud` bits per second.
+    ///
+    /// Enables the UART clock, configures UART_SETUP, resets the TX and RX
+    /// buffers, and enables the RX interrupt. Configure the pads first (see
+    /// the module docs). Calling init() again reconfigures the port, first
+    /// stopping its DMA and dropping any queued TX and received RX data,
+    /// with the same reset sequence as `uart::init()`.
+    ///
+    /// The first call takes 512 bytes from the UART2 TX buffer (see Memory
+    /// Layout in the module docs), flushing any UART2 output queued so far.
+    pub fn init(port: Port, baud: u32) -> SerialPort {
+        Self::init_with_config(port, baud, UartConfig::default())
+    }
+
+    /// Set up `port` at `baud` with a custom frame format.
+    ///
+    /// Takes the same `UartConfig` as `uart::init_with_config()`.
+    pub fn init_with_config(
+        port: Port,
+        baud: u32,
+        config: UartConfig,
+    ) -> SerialPort {
+        uart::lend_tx_area_to_serial();
+        let _cs = interrupt::critical_section();
+        unsafe {
+            // Enable the UART clock via uDMA control
+            clock::udma_clock_enable(port.clk_bit());
+
+            // Stop anything a previous init() left running before the
+            // buffer state below forgets about it
+            reset(port);
+
+            mmio::write32(
+                port.reg(REG_UART_SETUP),
+                config.setup_value(baud.max(1)) | UART_EN_TX | UART_EN_RX,
+            );
+
+            let state = &raw mut STATE;
+            (*state)[port.index()] = IDLE_PORT;
+            (*state)[port.index()].enabled = true;
+
+            // Enable the RX interrupt and its IRQARRAY5 event
+            mmio::write32(port.reg(REG_IRQ_EN), IRQ_EN_RX);
+            IRQARRAY5.enable(port.rx_char_bit());
+        }
+        SerialPort { port }
+    }
+
+    /// Queue data for transmission via DMA.
+    ///
+    /// Returns the number of bytes queued, which is less than `data.len()`
+    /// if the TX blocks fill up. The rest is not queued. Non-blocking.
+    pub fn write(&self, data: &[u8]) -> usize {
+        let _cs = interrupt::critical_section();
+        let mut written = 0;
+        unsafe {
+            let state = &raw mut STATE;
+            let s = &mut (*state)[self.port.index()];
+            for &byte in data {
+                // Stop if the block to fill is still pending or in flight
+                if s.tx_block_len[s.tx_next_block] > 0 {
+                    break;
+                }
+                let addr =
+                    self.port.tx_block_addr(s.tx_next_block) + s.tx_fill_len;
+                mmio::write8(addr, byte);
+                s.tx_fill_len += 1;
+                written += 1;
+
+                // If the block is full, mark it ready and move to the next
+                if s.tx_fill_len == TX_BLOCK_SIZE {
+                    s.tx_block_len[s.tx_next_block] = TX_BLOCK_SIZE as u8;
+                    s.tx_next_block = (s.tx_next_block + 1) % TX_BLOCK_COUNT;
+                    s.tx_fill_len = 0;
+                }
+            }
+        }
+        self.tick();
+        written
+    }
+
+    /// Queue all of `data` for transmission, blocking until it fits.
+    pub fn write_all(&self, data: &[u8]) {
+        let mut offset = 0;
+        while offset < data.len() {
+            offset += self.write(&data[offset..]);
+            self.tick();
+        }
+    }
+
+    /// Service the TX DMA queue.
+    ///
+    /// Retires a finished transfer and starts DMA for the next ready (or
+    /// partially filled) block. Call periodically from the main loop.
+    pub fn tick(&self) {
+        let port = self.port;
+        let _cs = interrupt::critical_section();
+        unsafe {
+            let state = &raw mut STATE;
+            let s = &mut (*state)[port.index()];
+            let tx_saddr = mmio::read32(port.reg(REG_TX_SADDR));
+            if tx_saddr == 0 && s.tx_in_flight {
+                // Transfer complete, mark this block as done
+                s.tx_block_len[s.tx_queue_head] = 0;
+                s.tx_queue_head = (s.tx_queue_head + 1) % TX_BLOCK_COUNT;
+                s.tx_in_flight = false;
+            }
+
+            // If idle with no ready blocks, hand off the partial block
+            if !s.tx_in_flight
+                && s.tx_block_len[s.tx_queue_head] == 0
+                && s.tx_fill_len > 0
+            {
+                s.tx_block_len[s.tx_next_block] = s.tx_fill_len as u8;
+                s.tx_next_block = (s.tx_next_block + 1) % TX_BLOCK_COUNT;
+                s.tx_fill_len = 0;
+            }
+
+            // If idle, start DMA for the next ready block
+            let len = s.tx_block_len[s.tx_queue_head];
+            if !s.tx_in_flight && len > 0 {
+                let addr = port.tx_block_addr(s.tx_queue_head) as u32;
+                mmio::write32(port.reg(REG_TX_SADDR), addr);
+                mmio::write32(port.reg(REG_TX_SIZE), len as u32);
+                mmio::write32(port.reg(REG_TX_CFG), CFG_EN);
+                s.tx_in_flight = true;
+            }
+        }
+    }
+
+    /// Block until all queued TX data has been sent.
+    pub fn flush(&self) {
+        loop {
+            self.tick();
+            let _cs = interrupt::critical_section();
+            let empty = unsafe {
+                let state = &raw const STATE;
+                let s = &(*state)[self.port.index()];
+                !s.tx_in_flight
+                    && s.tx_block_len[s.tx_queue_head] == 0
+                    && s.tx_fill_len == 0
+            };
+            if empty {
+                break;
+            }
+        }
+        let status = self.port.reg(REG_STATUS);
+        unsafe { while mmio::read32(status) & STATUS_TX_BUSY != 0 {} }
+    }
+
+    /// Read one byte from the RX ring, if one is waiting.
+    pub fn getc(&self) -> Option<u8> {
+        let mut byte = [0u8];
+        (self.read(&mut byte) == 1).then_some(byte[0])
+    }
+
+    /// Read as many bytes as are waiting into `buf`, without blocking.
+    ///
+    /// Returns the number of bytes read (0 if none are waiting).
+    pub fn read(&self, buf: &mut [u8]) -> usize {
+        let _cs = interrupt::critical_section();
+        let mut n = 0;
+        unsafe {
+            let state = &raw mut STATE;
+            let s = &mut (*state)[self.port.index()];
+            while n < buf.len() && s.rx_count > 0 {
+                buf[n] = s.rx_ring[s.rx_tail];
+                s.rx_tail = (s.rx_tail + 1) % RX_RING_SIZE;
+                s.rx_count -= 1;
+                n += 1;
+            }
+        }
+        n
+    }
+
+    /// Return the number of received bytes waiting to be read.
+    pub fn rx_available(&self) -> usize {
+        let _cs = interrupt::critical_section();
+        unsafe {
+            let state = &raw const STATE;
+            (*state)[self.port.index()].rx_count
+        }
+    }
+
+    /// Return true if received bytes were dropped because the RX ring was
+    /// full, clearing the flag.
+    pub fn rx_overrun(&self) -> bool {
+        let _cs = interrupt::critical_section();
+        unsafe {
+            let state = &raw mut STATE;
+            let s = &mut (*state)[self.port.index()];
+            let overrun = s.rx_overrun;
+            s.rx_overrun = false;
+            overrun
+        }
+    }
+}
+
+impl fmt::Write for SerialPort {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_all(s.as_bytes());
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Interrupt Handling
+// ============================================================================
+
+/// Handle RX interrupts for UART0, UART1 and UART3.
+///
+/// Called by the IRQARRAY5 trap handler, next to
+/// `uart::handle_rx_interrupt()`. Drains each enabled port's VALID/DATA
+/// registers into its RX ring, then clears its pending bit.
+pub(crate) fn handle_rx_interrupt() {
+    unsafe {
+        let state = &raw mut STATE;
+        for port in ALL_PORTS {
+            let s = &mut (*state)[port.index()];
+            if !s.enabled {
+                continue;
+            }
+            // Reading ERROR clears the hardware error flags
+            mmio::read32(port.reg(REG_ERROR));
+            while mmio::read32(port.reg(REG_VALID)) & VALID_DATA_AVAILABLE != 0
+            {
+                let byte = mmio::read32(port.reg(REG_DATA)) as u8;
+                if s.rx_count < RX_RING_SIZE {
+                    s.rx_ring[s.rx_head] = byte;
+                    s.rx_head = (s.rx_head + 1) % RX_RING_SIZE;
+                    s.rx_count += 1;
+                } else {
+                    s.rx_overrun = true;
+                }
+            }
+            IRQARRAY5.clear_pending(port.rx_char_bit());
+        }
+    }
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+// Put a port into a known idle state before init applies its setup value,
+// using the same steps as the uart module's reset sequence. Call with
+// interrupts disabled.
+fn reset(port: Port) {
+    unsafe {
+        // 1. Stop the RX interrupt, so the handler can't run mid-reset
+        mmio::write32(port.reg(REG_IRQ_EN), 0);
+        IRQARRAY5.disable(port.rx_char_bit());
+        // 2. Disable TX and RX, and flush the RX FIFO
+        mmio::write32(port.reg(REG_UART_SETUP), UART_RX_CLEAN_FIFO);
+        // 3. Stop both DMA channels and zero the TX transfer registers
+        mmio::write32(port.reg(REG_TX_CFG), CFG_CLR);
+        mmio::write32(port.reg(REG_RX_CFG), CFG_CLR);
+        mmio::write32(port.reg(REG_TX_SADDR), 0);
+        mmio::write32(port.reg(REG_TX_SIZE), 0);
+        // 4. Drop any stale error flags and pending RX event
+        mmio::read32(port.reg(REG_ERROR));
+        IRQARRAY5.clear_pending(port.rx_char_bit());
+    }
+}
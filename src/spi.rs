@@ -0,0 +1,453 @@
+// SPDX-License-Identifier: MIT
+// SPDX-FileCopyrightText: Copyright 2026 Sam Blenny
+//
+//! SPI master driver for bao1x dabao evaluation board
+//!
+//! Provides blocking, DMA-based SPI master transfers for devices like SPI
+//! flash chips and displays. Chip select is a GPIO pin driven by software,
+//! so any free pin can be used.
+//!
+//! # Usage
+//!
+//! Configure the SPIM pins for the bus, initialize it with a chip select
+//! pin, then read a SPI flash JEDEC ID:
+//!
+//! ```ignore
+//! use baochip_sdk::{gpio, spi};
+//! use gpio::{AF, GpioPin};
+//! use spi::{SpiBus, SpiConfig};
+//!
+//! // SCK, MOSI, and MISO pads for the bus go here (see below)
+//! // gpio::set_alternate_function(..., AF::AF2);
+//!
+//! let cs = GpioPin::PortB(gpio::PB11);
+//! spi::init(SpiConfig::new(SpiBus::Spim1, cs));
+//!
+//! let mut id = [0x9f, 0, 0, 0]; // JEDEC Read ID command + 3 bytes
+//! if spi::transfer(&mut id).is_err() {
+//!     // Timed out: check the SCK/MOSI/MISO pin setup
+//! }
+//! ```
+//!
+//! # Hardware Details
+//!
+//! The bao1x has four uDMA SPI master controllers (SPIM0-SPIM3). Their
+//! SCK, MOSI, and MISO signals reach the GPIO pads through the IOX
+//! alternate function mux:
+//! - SPIM1: alternate function AF2
+//! - SPIM2: alternate function AF1
+//! - SPIM0, SPIM3: not listed for any alternate function in the AF table
+//!
+//! As with I2C, the pad assignment for each bus is not recorded in the
+//! register descriptions this SDK is built from, so this module does not
+//! configure the SCK/MOSI/MISO pins. Check the bao1x pinout for the pads of
+//! the bus you want and set them to its AF before calling init().
+//!
+//! The controller's own chip select outputs are not used. init() sets the
+//! `cs` pin from `SpiConfig` to a GPIO output (AF0) driven high, and each
+//! transfer drives it low for the duration of the transfer.
+//!
+//! # Register Map
+//!
+//! Each controller has the same register layout at its own base address:
+//! - SPIM0: 0x50105000
+//! - SPIM1: 0x50106000
+//! - SPIM2: 0x50107000
+//! - SPIM3: 0x50108000
+//!
+//! | Offset | Register  | Use                                      |
+//! |--------|-----------|------------------------------------------|
+//! | 0x00   | RX_SADDR  | RX DMA buffer address (0 when done)      |
+//! | 0x04   | RX_SIZE   | RX DMA length in bytes                   |
+//! | 0x08   | RX_CFG    | Bit 4: start RX DMA, bit 6: stop/clear   |
+//! | 0x10   | TX_SADDR  | TX DMA buffer address (0 when done)      |
+//! | 0x14   | TX_SIZE   | TX DMA length in bytes                   |
+//! | 0x18   | TX_CFG    | Bit 4: start TX DMA, bit 6: stop/clear   |
+//! | 0x20   | CMD_SADDR | Command buffer address (0 when done)     |
+//! | 0x24   | CMD_SIZE  | Command buffer length in bytes           |
+//! | 0x28   | CMD_CFG   | Bit 4: start command DMA, bit 6: clear   |
+//! | 0x30   | STATUS    | Bit 0: busy                              |
+//!
+//! Each bus is clocked by a uDMA clock gate bit (REG_CG bits 4-7 for
+//! SPIM0-SPIM3, base 0x50100000), which init() turns on.
+//!
+//! # Command Stream
+//!
+//! Like the I2C controller, the SPIM controller is driven by a list of
+//! 32-bit commands fetched by the command DMA channel, with the opcode in
+//! bits [31:28]. This driver uses:
+//! - 0x0 CFG: Bits [7:0] clock divisor, bit 8 CPHA, bit 9 CPOL
+//! - 0x6 TX_DATA: Send words from the TX channel
+//! - 0x7 RX_DATA: Receive words into the RX channel
+//! - 0xC FULL_DUPL: Send and receive words at the same time
+//!
+//! The data commands take the word count minus one in bits [15:0], the
+//! bits per word minus one in bits [20:16] (7 for bytes), and an LSB-first
+//! flag in bit 26. The opcodes follow the PULP udma_spim controller that
+//! the bao1x uDMA is based on, since they are not part of the register
+//! descriptions.
+//!
+//! # Clock Configuration
+//!
+//! The controllers are clocked by PERCLK (100 MHz), and SCK is:
+//! `SCK = PERCLK / (2 * divisor)`
+//! For example, divisor 50 gives 1 MHz and divisor 2 gives 25 MHz.
+//!
+//! # DMA vs. Polled I/O
+//!
+//! The uDMA SPI controller has no data register to poll, so every byte goes
+//! through DMA. This driver uses DMA synchronously: it copies the caller's
+//! data into IFRAM bounce buffers, starts the transfer, and busy-waits for
+//! it to finish before returning. That keeps the API as simple as a polled
+//! driver and works with buffers anywhere in RAM, at the cost of the CPU
+//! waiting and copying. The copy is fast compared to the SPI clock, so this
+//! mainly matters for long transfers at low SCK rates. An interrupt-driven
+//! driver could overlap transfers with other work, but would need buffers
+//! that stay in IFRAM until the transfer completes.
+//!
+//! # DMA and Memory Layout
+//!
+//! DMA buffers live in IFRAM0, above the UART and I2C buffers
+//! (0x50000000 - 0x50000AFF):
+//! - 0x50000B00: Command buffer (64 bytes)
+//! - 0x50000C00: TX buffer (512 bytes)
+//! - 0x50000E00: RX buffer (512 bytes)
+//!
+//! The uDMA buffer address fields are 12 bits wide, so all uDMA buffers are
+//! kept in the first 4KB of IFRAM0. Transfers longer than 512 bytes are
+//! split into 512-byte chunks with CS held low throughout, so the device
+//! sees one continuous transfer.
+//!
+//! # Errors
+//!
+//! Each chunk has 100 ms to finish. If it doesn't (for example, because
+//! the bus clock is off or the controller is wedged), the command, TX, and
+//! RX channels are stopped with their CFG_CLR bits, CS is released, and the
+//! transfer returns `SpiError::Timeout`. What the device received before
+//! that is not known, so treat its state as undefined.
+//!
+//! # API Design
+//!
+//! - init(): Enable a bus and set its mode, bit order, clock, and CS pin
+//! - transfer(): Full-duplex transfer, replacing sent bytes with received
+//! - write(): Send bytes, ignoring MISO
+//! - read(): Receive bytes, with MOSI idle
+
+use crate::clock;
+use crate::gpio::{self, AF, GpioPin};
+use crate::pac;
+use crate::ticktimer::Deadline;
+use core::ptr;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+// SPIM controller base addresses
+const SPIM0_BASE: usize = pac::UDMA_SPIM0_BASE;
+const SPIM_BASE_STRIDE: usize = pac::UDMA_STRIDE;
+
+// SPIM register offsets
+const REG_RX_SADDR: usize = 0x00;
+const REG_RX_SIZE: usize = 0x04;
+const REG_RX_CFG: usize = 0x08;
+const REG_TX_SADDR: usize = 0x10;
+const REG_TX_SIZE: usize = 0x14;
+const REG_TX_CFG: usize = 0x18;
+const REG_CMD_SADDR: usize = 0x20;
+const REG_CMD_SIZE: usize = 0x24;
+const REG_CMD_CFG: usize = 0x28;
+const REG_STATUS: usize = 0x30;
+
+// Channel configuration bits (data size bits [2:1] = 0 for bytes)
+const CFG_EN: u32 = pac::UDMA_CFG_EN;
+const CFG_CLR: u32 = pac::UDMA_CFG_CLR;
+
+// STATUS register bits
+const STATUS_BUSY: u32 = 1 << 0;
+
+// Command opcodes (bits [31:28])
+const CMD_CFG: u32 = 0x0 << 28;
+const CMD_TX_DATA: u32 = 0x6 << 28;
+const CMD_RX_DATA: u32 = 0x7 << 28;
+const CMD_FULL_DUPL: u32 = 0xC << 28;
+
+// CFG command fields
+const CFG_CPHA: u32 = 1 << 8;
+const CFG_CPOL: u32 = 1 << 9;
+
+// Data command fields
+const DATA_BITS_8: u32 = 7 << 16;
+const DATA_LSB_FIRST: u32 = 1 << 26;
+
+// IFRAM DMA buffers (above the UART and I2C buffers)
+const IFRAM_CMD_ADDR: usize = pac::IFRAM_SPI_CMD;
+const IFRAM_TX_ADDR: usize = pac::IFRAM_SPI_TX;
+const IFRAM_RX_ADDR: usize = pac::IFRAM_SPI_RX;
+const CHUNK_SIZE: usize = 512;
+
+// Default clock divisor (1 MHz SCK from 100 MHz PERCLK)
+const DEFAULT_DIVISOR: u8 = 50;
+
+// Time allowed for one chunk before giving up
+const TIMEOUT_MS: u32 = 100;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// SPI controller selection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpiBus {
+    Spim0 = 0,
+    Spim1 = 1,
+    Spim2 = 2,
+    Spim3 = 3,
+}
+
+/// Errors returned by SPI transfers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpiError {
+    /// A chunk did not finish in time
+    Timeout,
+}
+
+/// SPI clock mode (clock polarity and phase).
+///
+/// | Mode  | CPOL (idle SCK) | CPHA (sample edge) |
+/// |-------|-----------------|--------------------|
+/// | Mode0 | low             | leading            |
+/// | Mode1 | low             | trailing           |
+/// | Mode2 | high            | leading            |
+/// | Mode3 | high            | trailing           |
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Mode0,
+    Mode1,
+    Mode2,
+    Mode3,
+}
+
+/// Order in which the bits of each byte are shifted out and in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitOrder {
+    MsbFirst,
+    LsbFirst,
+}
+
+/// SPI bus configuration for init().
+///
+/// `SpiConfig::new()` selects mode 0, MSB first, and a 1 MHz clock. Change
+/// the fields for other devices:
+///
+/// ```ignore
+/// use spi::{BitOrder, Mode, SpiBus, SpiConfig};
+///
+/// let mut config = SpiConfig::new(SpiBus::Spim2, cs);
+/// config.mode = Mode::Mode3;
+/// config.divisor = 2; // 25 MHz
+/// spi::init(config);
+/// ```
+#[derive(Clone, Copy)]
+pub struct SpiConfig {
+    pub bus: SpiBus,
+    pub mode: Mode,
+    pub bit_order: BitOrder,
+    /// SCK = PERCLK / (2 * divisor), must be at least 1
+    pub divisor: u8,
+    /// GPIO pin used as active-low chip select
+    pub cs: GpioPin,
+}
+
+impl SpiConfig {
+    /// Mode 0, MSB first, 1 MHz clock on `bus`, with `cs` as chip select.
+    pub fn new(bus: SpiBus, cs: GpioPin) -> Self {
+        SpiConfig {
+            bus,
+            mode: Mode::Mode0,
+            bit_order: BitOrder::MsbFirst,
+            divisor: DEFAULT_DIVISOR,
+            cs,
+        }
+    }
+
+    // Build the CFG command for this configuration
+    fn cfg_command(&self) -> u32 {
+        let mut cmd = CMD_CFG | self.divisor.max(1) as u32;
+        if let Mode::Mode1 | Mode::Mode3 = self.mode {
+            cmd |= CFG_CPHA;
+        }
+        if let Mode::Mode2 | Mode::Mode3 = self.mode {
+            cmd |= CFG_CPOL;
+        }
+        cmd
+    }
+}
+
+// ============================================================================
+// Internal State
+// ============================================================================
+
+static mut SPI_BASE: usize = SPIM0_BASE;
+static mut SPI_CFG_CMD: u32 = CMD_CFG | DEFAULT_DIVISOR as u32;
+static mut SPI_DATA_FLAGS: u32 = DATA_BITS_8;
+static mut SPI_CS: Option<GpioPin> = None;
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Enable an SPI bus and select it for later transfers.
+///
+/// Turns on the bus clock, stores the mode, bit order, and clock divisor
+/// from `config`, and sets up `config.cs` as a GPIO output driven high
+/// (deselected). Configure the SCK/MOSI/MISO pins first (see the module
+/// docs). Calling init() again switches to a different bus or device.
+pub fn init(config: SpiConfig) {
+    unsafe {
+        // Enable the bus clock via uDMA control
+        clock::udma_clock_enable(clock::UDMA_CG_SPIM0 << config.bus as u32);
+
+        SPI_BASE = SPIM0_BASE + config.bus as usize * SPIM_BASE_STRIDE;
+        SPI_CFG_CMD = config.cfg_command();
+        SPI_DATA_FLAGS = match config.bit_order {
+            BitOrder::MsbFirst => DATA_BITS_8,
+            BitOrder::LsbFirst => DATA_BITS_8 | DATA_LSB_FIRST,
+        };
+        SPI_CS = Some(config.cs);
+    }
+
+    // Set the CS level before enabling the output so it never glitches low
+    gpio::set_alternate_function(config.cs, AF::AF0);
+    gpio::set(config.cs);
+    gpio::enable_output(config.cs);
+}
+
+/// Full-duplex transfer: send `buf`, replacing its contents with the bytes
+/// received at the same time.
+///
+/// Holds CS low for the whole transfer. Blocks until it is done, or
+/// returns `SpiError::Timeout` if a chunk takes longer than 100 ms.
+pub fn transfer(buf: &mut [u8]) -> Result<(), SpiError> {
+    select();
+    let result = buf.chunks_mut(CHUNK_SIZE).try_for_each(|chunk| {
+        copy_to_tx(chunk);
+        run(CMD_FULL_DUPL, chunk.len(), true, true)?;
+        copy_from_rx(chunk);
+        Ok(())
+    });
+    deselect();
+    result
+}
+
+/// Send `data`, ignoring whatever the device sends back.
+///
+/// Holds CS low for the whole transfer. Blocks until it is done, or
+/// returns `SpiError::Timeout` if a chunk takes longer than 100 ms.
+pub fn write(data: &[u8]) -> Result<(), SpiError> {
+    select();
+    let result = data.chunks(CHUNK_SIZE).try_for_each(|chunk| {
+        copy_to_tx(chunk);
+        run(CMD_TX_DATA, chunk.len(), true, false)
+    });
+    deselect();
+    result
+}
+
+/// Receive `buf.len()` bytes while MOSI idles.
+///
+/// Holds CS low for the whole transfer. Blocks until it is done, or
+/// returns `SpiError::Timeout` if a chunk takes longer than 100 ms.
+pub fn read(buf: &mut [u8]) -> Result<(), SpiError> {
+    select();
+    let result = buf.chunks_mut(CHUNK_SIZE).try_for_each(|chunk| {
+        run(CMD_RX_DATA, chunk.len(), false, true)?;
+        copy_from_rx(chunk);
+        Ok(())
+    });
+    deselect();
+    result
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+fn select() {
+    if let Some(cs) = unsafe { SPI_CS } {
+        gpio::clear(cs);
+    }
+}
+
+fn deselect() {
+    if let Some(cs) = unsafe { SPI_CS } {
+        gpio::set(cs);
+    }
+}
+
+fn copy_to_tx(data: &[u8]) {
+    for (i, &b) in data.iter().enumerate() {
+        unsafe { ptr::write_volatile((IFRAM_TX_ADDR + i) as *mut u8, b) };
+    }
+}
+
+fn copy_from_rx(buf: &mut [u8]) {
+    for (i, b) in buf.iter_mut().enumerate() {
+        *b = unsafe { ptr::read_volatile((IFRAM_RX_ADDR + i) as *const u8) };
+    }
+}
+
+// Run one data command of `len` bytes (1 to CHUNK_SIZE) and wait for the
+// command, TX, and RX channels to finish. On timeout, stop all three.
+fn run(opcode: u32, len: usize, tx: bool, rx: bool) -> Result<(), SpiError> {
+    unsafe {
+        let cmds = [SPI_CFG_CMD, opcode | SPI_DATA_FLAGS | (len as u32 - 1)];
+        for (i, &cmd) in cmds.iter().enumerate() {
+            ptr::write_volatile((IFRAM_CMD_ADDR + i * 4) as *mut u32, cmd);
+        }
+        core::sync::atomic::compiler_fence(
+            core::sync::atomic::Ordering::SeqCst,
+        );
+
+        // Arm the data channels before starting the command stream
+        if rx {
+            write_reg(REG_RX_SADDR, IFRAM_RX_ADDR as u32);
+            write_reg(REG_RX_SIZE, len as u32);
+            write_reg(REG_RX_CFG, CFG_EN);
+        }
+        if tx {
+            write_reg(REG_TX_SADDR, IFRAM_TX_ADDR as u32);
+            write_reg(REG_TX_SIZE, len as u32);
+            write_reg(REG_TX_CFG, CFG_EN);
+        }
+        write_reg(REG_CMD_SADDR, IFRAM_CMD_ADDR as u32);
+        write_reg(REG_CMD_SIZE, (cmds.len() * 4) as u32);
+        write_reg(REG_CMD_CFG, CFG_EN);
+    }
+
+    // Each SADDR register reads 0 once its channel has finished
+    let deadline = Deadline::after_ms(TIMEOUT_MS);
+    while read_reg(REG_CMD_SADDR) != 0
+        || (tx && read_reg(REG_TX_SADDR) != 0)
+        || (rx && read_reg(REG_RX_SADDR) != 0)
+        || read_reg(REG_STATUS) & STATUS_BUSY != 0
+    {
+        if deadline.is_expired() {
+            write_reg(REG_CMD_CFG, CFG_CLR);
+            write_reg(REG_TX_CFG, CFG_CLR);
+            write_reg(REG_RX_CFG, CFG_CLR);
+            return Err(SpiError::Timeout);
+        }
+        core::hint::spin_loop();
+    }
+    core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::Acquire);
+    Ok(())
+}
+
+fn read_reg(offset: usize) -> u32 {
+    unsafe { ptr::read_volatile((SPI_BASE + offset) as *const u32) }
+}
+
+fn write_reg(offset: usize, value: u32) {
+    unsafe { ptr::write_volatile((SPI_BASE + offset) as *mut u32, value) }
+}
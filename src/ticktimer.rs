@@ -3,13 +3,15 @@
 //
 //! TICKTIMER driver for bao1x dabao evaluation board
 //!
-//! Provides a system millisecond timer via the TICKTIMER peripheral.
+//! Provides system millisecond and microsecond timers via the TICKTIMER
+//! peripheral.
 //!
 //! # Overview
 //!
 //! TICKTIMER is a 64-bit counter that increments at a configurable rate.
-//! This driver configures it to increment once per millisecond, providing
-//! a system-wide millisecond clock accessible via `millis()`.
+//! This driver configures it to increment once per microsecond, providing
+//! a system-wide microsecond clock accessible via `micros()`. `millis()`
+//! divides that count by 1000.
 //!
 //! # Usage
 //!
@@ -22,27 +24,70 @@
 //! fn main() {
 //!     let start = ticktimer::millis();
 //!     // ... do some work ...
-//!     let elapsed = ticktimer::millis() - start;
+//!     let elapsed = ticktimer::elapsed_since(start);
+//!
+//!     // Or, for short intervals like pulse widths:
+//!     let start_us = ticktimer::micros();
+//!     // ... wait for pin change ...
+//!     let width_us = ticktimer::micros() - start_us;
 //! }
 //! ```
 //!
 //! # Clock Configuration
 //!
 //! TICKTIMER increments based on CLOCKS_PER_TICK:
-//! - At 350 MHz ACLK, CLOCKS_PER_TICK = 350,000 gives 1ms ticks
-//! - Formula: CLOCKS_PER_TICK = ACLK_HZ / 1000
+//! - At 350 MHz ACLK, CLOCKS_PER_TICK = 350 gives 1us ticks
+//! - Formula: CLOCKS_PER_TICK = clock::aclk_hz() / 1_000_000
+//!
+//! The current default (800,000) was designed for 1ms ticks on 800 MHz
+//! systems. We override it to 350 for 1us ticks on the Bao1x at 350 MHz.
+//!
+//! The resolution of `micros()` is 1us (350 ACLK cycles). `millis()` is
+//! `micros() / 1000`, so it still counts whole milliseconds since boot and
+//! stays monotonic. The finer tick does not change what `millis()` returns,
+//! apart from the division truncating partial milliseconds.
+//!
+//! # Delays
+//!
+//! `Delay` busy-waits for a given number of nanoseconds, microseconds, or
+//! milliseconds, servicing UART TX while it waits. With the `embedded-hal`
+//! feature enabled, it implements `embedded_hal::delay::DelayNs`, so it can
+//! be passed to HAL-generic sensor driver crates. Delays shorter than 1us
+//! are below the resolution of `micros()`, so those spin for a computed
+//! number of ACLK cycles instead.
+//!
+//! # TIMER0 Fallback
 //!
-//! The current default (800,000) was designed for 800 MHz systems.
-//! We override it to 350,000 for the Bao1x at 350 MHz.
+//! Some bootloaders may leave TICKTIMER stopped, which would make
+//! `millis()` read a constant and every timeout in the SDK hang. So
+//! `init()` reads TIME0, spins for about 100us of ACLK cycles, and reads it
+//! again. It only does this once, at boot; later calls just update the
+//! tick rate. If the count did not move, it switches to a software counter
+//! instead: TIMER0 runs in periodic mode with a 1ms interrupt, and the
+//! callback increments a millisecond count that `millis()` and `micros()`
+//! return. `using_fallback()` reports whether that happened, so firmware
+//! can log it.
+//!
+//! The fallback has costs to be aware of:
+//! - `micros()` only has 1ms resolution (it returns `millis() * 1000`), so
+//!   `Delay` and other sub-millisecond waits round up to the next tick
+//! - Time only advances while interrupts are enabled
+//! - TIMER0 is taken. The `timer0` functions log a warning and leave it
+//!   alone (`try_set_alarm_ms()` returns `TimerError::InUse`),
+//!   `watchdog::start()` refuses to start, and `sleep_low_power()` wakes
+//!   on the fallback tick instead of its own alarm
+//! - After `clock::set_aclk_hz()`, the TIMER0 reload is recomputed for the
+//!   new ACLK, without resetting the count
 //!
 //! # Registers
 //!
 //! TICKTIMER provides:
-//! - TIME1/TIME0: 64-bit elapsed time in millisecond ticks
+//! - TIME1/TIME0: 64-bit elapsed time in microsecond ticks
 //! - CONTROL: Reset control
 //! - CLOCKS_PER_TICK: Divisor for tick rate
 //! - Event control registers (not used in this driver)
 
+use crate::{clock, cycles, interrupt, pac, timer0};
 use core::ptr;
 
 // ============================================================================
@@ -50,22 +95,35 @@ use core::ptr;
 // ============================================================================
 
 // TICKTIMER register addresses
-const TICKTIMER_TIME0: *const u32 = 0xe001b008 as *const u32;
-const TICKTIMER_TIME1: *const u32 = 0xe001b004 as *const u32;
-const TICKTIMER_CLOCKS_PER_TICK: *mut u32 = 0xe001b020 as *mut u32;
+const TICKTIMER_TIME0: *const u32 = (pac::TICKTIMER_BASE + 0x08) as *const u32;
+const TICKTIMER_TIME1: *const u32 = (pac::TICKTIMER_BASE + 0x04) as *const u32;
+const TICKTIMER_CLOCKS_PER_TICK: *mut u32 =
+    (pac::TICKTIMER_BASE + 0x20) as *mut u32;
+
+// How long init() watches TIME0 for movement before giving up on it
+const PROBE_US: u32 = 100;
+
+// Set by the first init(), so later calls don't probe again
+static mut PROBED: bool = false;
 
-// Calculate clocks per millisecond from system clock frequency
-const CLOCKS_PER_MS: u32 = crate::ACLK_HZ / 1000;
+// TIMER0 software clock, used when TICKTIMER is not counting
+static mut FALLBACK: bool = false;
+static mut FALLBACK_MS: u64 = 0; // Incremented by fallback_tick()
 
 // ============================================================================
 // Public API
 // ============================================================================
 
-/// Initialize the timer for 1 millisecond tick rate.
+/// Initialize the timer for 1 microsecond tick rate.
 ///
-/// Sets CLOCKS_PER_TICK to (ACLK_HZ / 1000) so that the timer
-/// increments once per millisecond. Must be called once at boot time
-/// before any code calls `millis()`.
+/// Sets CLOCKS_PER_TICK to (clock::aclk_hz() / 1_000_000) so that the timer
+/// increments once per microsecond. Must be called once at boot time
+/// before any code calls `millis()` or `micros()`. If the counter doesn't
+/// advance, switches to the TIMER0 fallback (see the module docs).
+///
+/// The check for a stopped counter only runs on the first call. Calling
+/// init() again (e.g. after an ACLK change) just updates the tick rate,
+/// so the time keeps counting from where it was.
 ///
 /// # Safety
 ///
@@ -73,21 +131,66 @@ const CLOCKS_PER_MS: u32 = crate::ACLK_HZ / 1000;
 /// Concurrent timer access from multiple threads would cause data races, but
 /// that is not possible in this environment.
 pub fn init() {
+    set_tick_rate();
+    unsafe {
+        if PROBED {
+            return;
+        }
+        PROBED = true;
+
+        // Check the counter is running, using mcycle to measure the wait
+        let clocks_per_us = clock::aclk_hz() / 1_000_000;
+        let before = ptr::read_volatile(TICKTIMER_TIME0);
+        cycles::delay_cycles(clocks_per_us.saturating_mul(PROBE_US));
+        if ptr::read_volatile(TICKTIMER_TIME0) == before {
+            FALLBACK_MS = 0;
+            // Fires once interrupt::irq_setup() enables interrupts
+            timer0::start_fallback_clock(fallback_tick);
+            FALLBACK = true;
+        }
+    }
+}
+
+/// Set the tick rate for the current `clock::aclk_hz()`.
+///
+/// Writes CLOCKS_PER_TICK, and if the TIMER0 fallback is running,
+/// recomputes its 1ms period. Neither resets the time.
+pub(crate) fn set_tick_rate() {
     unsafe {
-        // Configure timer for 1ms ticks: 350,000 clocks per tick
-        // At 350 MHz: 350,000 / 350,000,000 = 0.001 seconds = 1 millisecond
-        ptr::write_volatile(TICKTIMER_CLOCKS_PER_TICK, CLOCKS_PER_MS);
+        // Configure timer for 1us ticks: 350 clocks per tick
+        // At 350 MHz: 350 / 350,000,000 = 0.000001 seconds = 1 microsecond
+        let clocks_per_us = clock::aclk_hz() / 1_000_000;
+        ptr::write_volatile(TICKTIMER_CLOCKS_PER_TICK, clocks_per_us);
         // Ensure timer configuration is complete before any millis() calls
         core::sync::atomic::compiler_fence(
             core::sync::atomic::Ordering::SeqCst,
         );
     }
+    if using_fallback() {
+        timer0::update_fallback_clock();
+    }
+}
+
+/// Return true if `millis()` is driven by the TIMER0 fallback.
+///
+/// That means `init()` found TICKTIMER was not counting (see "TIMER0
+/// Fallback" in the module docs).
+pub fn using_fallback() -> bool {
+    unsafe { FALLBACK }
+}
+
+// TIMER0 callback for the fallback clock (interrupt context)
+fn fallback_tick() {
+    unsafe {
+        let ms = &raw mut FALLBACK_MS;
+        *ms = (*ms).wrapping_add(1);
+    }
 }
 
 /// Read the current elapsed time in milliseconds since boot.
 ///
 /// Returns a u64 millisecond counter that increments at 1ms intervals.
-/// The counter will not overflow for approximately 584 million years,
+/// The counter will not overflow for approximately 584 thousand years,
 /// so wraparound is not a practical concern for embedded applications.
 ///
 /// # Safety
@@ -96,12 +199,186 @@ pub fn init() {
 /// Concurrent timer access from multiple threads would cause data races, but
 /// that is not possible in this environment.
 pub fn millis() -> u64 {
+    micros() / 1000
+}
+
+/// Read the current elapsed time in microseconds since boot.
+///
+/// Returns a u64 microsecond counter that increments at 1us intervals.
+/// The counter will not overflow for approximately 584 thousand years.
+///
+/// # Safety
+///
+/// This function is safe to call because the firmware runs single-threaded.
+/// Concurrent timer access from multiple threads would cause data races, but
+/// that is not possible in this environment.
+pub fn micros() -> u64 {
+    if using_fallback() {
+        // The count is two words, so keep the tick from landing mid-read
+        let _cs = interrupt::critical_section();
+        return unsafe { FALLBACK_MS } * 1000;
+    }
     unsafe {
-        // Read TIME0 (bits 0-31) first, then TIME1 (bits 32-63)
-        // This is the safe pattern for reading split 64-bit values.
-        // If TIME0 wraps while we're reading, we catch it on the next call.
-        let lo = ptr::read_volatile(TICKTIMER_TIME0) as u64;
-        let hi = ptr::read_volatile(TICKTIMER_TIME1) as u64;
-        (hi << 32) | lo
+        // Read TIME1 (bits 32-63), then TIME0 (bits 0-31), then TIME1 again.
+        // If TIME1 changed, TIME0 wrapped between the reads and the low word
+        // may belong to either high word, so retry. At 1us ticks TIME0 wraps
+        // about every 71 minutes, so this matters for long-running devices.
+        loop {
+            let hi = ptr::read_volatile(TICKTIMER_TIME1);
+            let lo = ptr::read_volatile(TICKTIMER_TIME0);
+            if ptr::read_volatile(TICKTIMER_TIME1) == hi {
+                return ((hi as u64) << 32) | lo as u64;
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Deadlines
+// ============================================================================
+
+/// Return the `millis()` time `ms` milliseconds from now.
+///
+/// Saturates at u64::MAX instead of wrapping, so a far-off deadline never
+/// turns into one in the past.
+pub fn deadline_ms(ms: u32) -> u64 {
+    millis().saturating_add(ms as u64)
+}
+
+/// Return the milliseconds since the `millis()` time `earlier`.
+///
+/// Saturates at 0 instead of wrapping, so a timestamp read after the
+/// current time (for example, by an interrupt handler) counts as no time
+/// elapsed rather than as a huge interval.
+pub fn elapsed_since(earlier: u64) -> u64 {
+    millis().saturating_sub(earlier)
+}
+
+/// A point in time, in `millis()` units, for timeouts in polling loops.
+///
+/// ```ignore
+/// use baochip_sdk::{ticktimer, uart};
+/// use ticktimer::Deadline;
+///
+/// // Wait up to 500ms for a byte
+/// let deadline = Deadline::after_ms(500);
+/// let mut byte = None;
+/// while byte.is_none() && !deadline.is_expired() {
+///     byte = uart::getc();
+///     uart::tick();
+/// }
+/// ```
+#[derive(Clone, Copy)]
+pub struct Deadline {
+    end_ms: u64,
+}
+
+impl Deadline {
+    /// Create a deadline `ms` milliseconds from now.
+    pub fn after_ms(ms: u32) -> Self {
+        Deadline { end_ms: deadline_ms(ms) }
+    }
+
+    /// Return true once the deadline has passed.
+    pub fn is_expired(&self) -> bool {
+        millis() >= self.end_ms
+    }
+
+    /// Return the number of milliseconds left, or 0 if expired.
+    pub fn remaining_ms(&self) -> u64 {
+        self.end_ms.saturating_sub(millis())
+    }
+}
+
+/// Wait until `deadline` has passed, servicing UART DMA.
+///
+/// Blocks, calling uart::tick() periodically to service the TX DMA queue,
+/// in the same way as `crate::sleep()`.
+pub fn delay_until(deadline: Deadline) {
+    while !deadline.is_expired() {
+        crate::uart::tick();
+    }
+}
+
+// ============================================================================
+// Delay
+// ============================================================================
+
+/// Busy-wait delay provider.
+///
+/// Every delay lasts at least the requested time, and may run over by up
+/// to 1us plus the time of a `uart::tick()` call. Delays of 1us or more
+/// wait on `micros()` and service UART TX DMA while waiting. Shorter delays
+/// spin on the CPU cycle counter with `cycles::delay_ns()`, without
+/// servicing UART.
+///
+/// ```ignore
+/// use baochip_sdk::ticktimer::Delay;
+///
+/// let mut delay = Delay::new();
+/// delay.delay_us(50);
+/// delay.delay_ms(10);
+/// ```
+#[derive(Clone, Copy, Default)]
+pub struct Delay;
+
+impl Delay {
+    /// Create a delay provider. It holds no state, so any number can exist.
+    pub const fn new() -> Self {
+        Delay
+    }
+
+    /// Wait at least `ns` nanoseconds.
+    pub fn delay_ns(&mut self, ns: u32) {
+        if ns < 1000 {
+            cycles::delay_ns(ns);
+        } else {
+            wait_us(ns.div_ceil(1000) as u64);
+        }
+    }
+
+    /// Wait at least `us` microseconds.
+    pub fn delay_us(&mut self, us: u32) {
+        wait_us(us as u64);
+    }
+
+    /// Wait at least `ms` milliseconds.
+    pub fn delay_ms(&mut self, ms: u32) {
+        wait_us(ms as u64 * 1000);
+    }
+}
+
+/// Wait until more than `us` microsecond ticks have passed.
+///
+/// The first tick may come almost immediately after `start` is read, so
+/// waiting for `us + 1` ticks is what guarantees at least `us` microseconds.
+fn wait_us(us: u64) {
+    let start = micros();
+    while micros() - start <= us {
+        crate::uart::tick();
+    }
+}
+
+// ============================================================================
+// embedded-hal Traits (feature = "embedded-hal")
+// ============================================================================
+
+#[cfg(feature = "embedded-hal")]
+mod hal {
+    use super::Delay;
+    use embedded_hal::delay::DelayNs;
+
+    impl DelayNs for Delay {
+        fn delay_ns(&mut self, ns: u32) {
+            Delay::delay_ns(self, ns);
+        }
+
+        fn delay_us(&mut self, us: u32) {
+            Delay::delay_us(self, us);
+        }
+
+        fn delay_ms(&mut self, ms: u32) {
+            Delay::delay_ms(self, ms);
+        }
     }
 }
@@ -33,33 +33,182 @@
 //!
 //! timer0::set_alarm_ms(1000, alarm_callback);
 //! ```
+//!
+//! For a fixed-rate interrupt, use periodic mode, which reloads the
+//! countdown from RELOAD each time it reaches zero:
+//!
+//! ```ignore
+//! timer0::set_periodic_ms(10, control_loop_step);
+//! // ...
+//! timer0::cancel();
+//! ```
+//!
+//! To let the callback decide when to stop, use `set_periodic_ms_until()`
+//! with a callback that returns false on its last period. The interrupt
+//! handler then stops the timer, as if `cancel()` had been called:
+//!
+//! ```ignore
+//! fn blink_ten_times() -> bool {
+//!     // ... toggle an LED, count calls in a static ...
+//!     count < 10 // keep running?
+//! }
+//! timer0::set_periodic_ms_until(100, blink_ten_times);
+//! ```
+//!
+//! `set_alarm_ms()` accepts any `ms`: 0 loads a zero countdown, whose
+//! timing is undefined, and values past about 12.27 seconds saturate at
+//! the LOAD limit, so the alarm fires early. `try_set_alarm_ms()` checks
+//! for both and returns a `TimerError` instead of arming the timer:
+//!
+//! ```ignore
+//! if timer0::try_set_alarm_ms(ms, alarm_callback).is_err() {
+//!     // ms was 0 or longer than TIMER0 can count
+//! }
+//! ```
+//!
+//! C callers that need to pass state to the callback can use
+//! `set_alarm_ms_ctx()`, which takes an `extern "C" fn(*mut c_void)` and a
+//! context pointer that is passed back when the alarm fires.
+//!
+//! A pending alarm can be disarmed before it fires with `cancel_alarm()`,
+//! which returns whether an alarm was actually armed.
+//!
+//! # Free-Running Counter
+//!
+//! `start_free_running()` runs TIMER0 in periodic mode with the maximum
+//! reload value and its interrupt disabled, as a cheap ACLK-rate timestamp
+//! source (about 2.86ns per count at 350 MHz). `read_counter()` latches the
+//! countdown with UPDATE_VALUE, reads VALUE, and inverts it so the result
+//! counts up:
+//!
+//! ```ignore
+//! timer0::start_free_running();
+//! let t0 = timer0::read_counter();
+//! // ... code to measure ...
+//! let cycles = timer0::read_counter().wrapping_sub(t0);
+//! ```
+//!
+//! The counter wraps from 0xffffffff to 0 about every 12.27 seconds at
+//! 350 MHz. A `wrapping_sub()` of two readings gives the right difference
+//! across one wrap, so intervals up to 12.27 seconds measure correctly;
+//! longer ones come out short by a multiple of the wrap period. While the
+//! counter runs, TIMER0 can't also be used for alarms, so anything that
+//! arms an alarm (including `watchdog`, `sleep_low_power()`, and
+//! `set_alarm_ms()`) stops it, and `cancel()` stops it too.
+//!
+//! # TICKTIMER Fallback
+//!
+//! If `ticktimer::using_fallback()` is true, TIMER0 is the 1ms tick behind
+//! `millis()` (see "TIMER0 Fallback" in the ticktimer docs). Stopping or
+//! rearming it would stop the clock, so while the fallback runs, every
+//! function here that would do that logs a warning and leaves TIMER0
+//! alone instead. `try_set_alarm_ms()` returns `TimerError::InUse`.
+
+use crate::clock;
+use crate::interrupt::Callback;
+use crate::mmio;
+use crate::pac;
+use core::ffi::c_void;
 
 // ====================================================================
 // Callback Storage
 // ====================================================================
 
-static mut TIMER0_CALLBACK: Option<fn()> = None;
+static mut TIMER0_CALLBACK: Option<Callback> = None;
+static mut TIMER0_PERIODIC: bool = false; // RELOAD is set, keep running
 
 // ====================================================================
 // Register Addresses
 // ====================================================================
 
-const TIMER0_LOAD: *mut u32 = 0xe001c000 as *mut u32;
-const TIMER0_RELOAD: *mut u32 = 0xe001c004 as *mut u32;
-const TIMER0_EN: *mut u32 = 0xe001c008 as *mut u32;
-// const TIMER0_UPDATE_VALUE: *mut u32 = 0xe001c00c as *mut u32;
-// const TIMER0_VALUE: *const u32 = 0xe001c010 as *const u32;
-const TIMER0_EV_PENDING: *mut u32 = 0xe001c018 as *mut u32;
-const TIMER0_EV_ENABLE: *mut u32 = 0xe001c01c as *mut u32;
+const TIMER0_LOAD: usize = pac::TIMER0_BASE;
+const TIMER0_RELOAD: usize = pac::TIMER0_BASE + 0x04;
+const TIMER0_EN: usize = pac::TIMER0_BASE + 0x08;
+const TIMER0_UPDATE_VALUE: usize = pac::TIMER0_BASE + 0x0c;
+const TIMER0_VALUE: usize = pac::TIMER0_BASE + 0x10;
+const TIMER0_EV_PENDING: usize = pac::TIMER0_BASE + 0x18;
+const TIMER0_EV_ENABLE: usize = pac::TIMER0_BASE + 0x1c;
 
 // ====================================================================
 // Public API
 // ====================================================================
 
+/// Errors returned by try_set_alarm_ms().
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimerError {
+    /// The delay was 0
+    Zero,
+    /// The delay needs more ACLK cycles than the 32-bit LOAD register holds
+    TooLong,
+    /// TIMER0 is driving the TICKTIMER fallback clock
+    InUse,
+}
+
+/// Handle for TIMER0, as returned in `crate::Peripherals`.
+///
+/// Wraps the free functions of this module. Only
+/// `crate::take_peripherals()` creates one, so code that owns it knows no
+/// other handle is arming the timer (the free functions and drivers built
+/// on TIMER0, like `watchdog` and `sleep_low_power()`, still can).
+pub struct Timer0 {
+    _private: (),
+}
+
+impl Timer0 {
+    pub(crate) const fn new() -> Self {
+        Timer0 { _private: () }
+    }
+
+    /// Set a one-shot alarm (see `set_alarm_ms()`).
+    pub fn set_alarm_ms(&mut self, ms: u32, callback: fn()) {
+        set_alarm_ms(ms, callback);
+    }
+
+    /// Set a one-shot alarm, checking `ms` (see `try_set_alarm_ms()`).
+    pub fn try_set_alarm_ms(
+        &mut self,
+        ms: u32,
+        callback: fn(),
+    ) -> Result<(), TimerError> {
+        try_set_alarm_ms(ms, callback)
+    }
+
+    /// Set a one-shot alarm in microseconds (see `set_alarm_us()`).
+    pub fn set_alarm_us(&mut self, us: u32, callback: fn()) {
+        set_alarm_us(us, callback);
+    }
+
+    /// Start a periodic alarm (see `set_periodic_ms()`).
+    pub fn set_periodic_ms(&mut self, ms: u32, callback: fn()) {
+        set_periodic_ms(ms, callback);
+    }
+
+    /// Start a self-stopping periodic alarm (see `set_periodic_ms_until()`).
+    pub fn set_periodic_ms_until(&mut self, ms: u32, callback: fn() -> bool) {
+        set_periodic_ms_until(ms, callback);
+    }
+
+    /// Stop the timer and forget its callback (see `cancel_alarm()`).
+    pub fn cancel(&mut self) -> bool {
+        cancel_alarm()
+    }
+
+    /// Run as a free-running counter (see `start_free_running()`).
+    pub fn start_free_running(&mut self) {
+        start_free_running();
+    }
+
+    /// Read the free-running counter (see `read_counter()`).
+    pub fn read_counter(&self) -> u32 {
+        read_counter()
+    }
+}
+
 /// Set one-shot alarm after specified milliseconds
 ///
 /// # Arguments
-/// * `ms` - Milliseconds until alarm fires (1-4294967295)
+/// * `ms` - Milliseconds until alarm fires (1-12271, longer values saturate
+///   at the 32-bit LOAD register limit of about 12.27 seconds)
 /// * `callback` - Function to call when alarm fires (runs in interrupt context)
 ///
 /// # Notes
@@ -75,55 +224,263 @@ const TIMER0_EV_ENABLE: *mut u32 = 0xe001c01c as *mut u32;
 /// timer0::set_alarm_ms(1000, alarm_fired);
 /// ```
 pub fn set_alarm_ms(ms: u32, callback: fn()) {
-    // Calculate countdown value in ACLK cycles
-    // cycles = (ACLK_HZ / 1000) * ms
-    let cycles = (crate::ACLK_HZ / 1000).saturating_mul(ms);
+    start(ms_to_cycles(ms), 0, Callback::Plain(callback));
+}
+
+/// Set one-shot alarm after specified milliseconds, rejecting bad delays
+///
+/// # Arguments
+/// * `ms` - Milliseconds until alarm fires (1-12271 at 350 MHz)
+/// * `callback` - Function to call when alarm fires (runs in interrupt context)
+///
+/// # Notes
+/// Works like `set_alarm_ms()`, but returns `TimerError::Zero` for `ms ==
+/// 0` and `TimerError::TooLong` if the countdown would saturate, without
+/// arming the timer or touching an alarm that is already armed. Use
+/// `set_alarm_ms()` when `ms` is known to be in range. Returns
+/// `TimerError::InUse` while TIMER0 drives the TICKTIMER fallback.
+pub fn try_set_alarm_ms(ms: u32, callback: fn()) -> Result<(), TimerError> {
+    if crate::ticktimer::using_fallback() {
+        return Err(TimerError::InUse);
+    }
+    if ms == 0 {
+        return Err(TimerError::Zero);
+    }
+    let cycles =
+        (clock::aclk_hz() / 1000).checked_mul(ms).ok_or(TimerError::TooLong)?;
+    start(cycles, 0, Callback::Plain(callback));
+    Ok(())
+}
+
+/// Set one-shot alarm with a context pointer for the callback
+///
+/// # Arguments
+/// * `ms` - Milliseconds until alarm fires (same range as `set_alarm_ms()`)
+/// * `callback` - C-ABI function to call when alarm fires (runs in interrupt
+///   context)
+/// * `ctx` - Opaque pointer passed to `callback`. It must stay valid until
+///   the alarm fires or is cancelled.
+///
+/// # Notes
+/// Works like `set_alarm_ms()`, but lets C code (e.g. MicroPython's
+/// callback dispatcher) pass state to the callback without globals.
+pub fn set_alarm_ms_ctx(
+    ms: u32,
+    callback: extern "C" fn(*mut c_void),
+    ctx: *mut c_void,
+) {
+    start(ms_to_cycles(ms), 0, Callback::Context(callback, ctx));
+}
+
+/// Set one-shot alarm after specified microseconds
+///
+/// # Arguments
+/// * `us` - Microseconds until alarm fires (longer than about 12.27 seconds
+///   saturates at the 32-bit LOAD register limit)
+/// * `callback` - Function to call when alarm fires (runs in interrupt context)
+///
+/// # Notes
+/// Works like `set_alarm_ms()` with 350 ACLK cycles per microsecond. The
+/// countdown itself is cycle-accurate, but the callback runs after trap
+/// entry, register save, and dispatch in the interrupt handler, which add
+/// a small fixed latency (roughly 1-2us; not precisely characterized). So
+/// delays shorter than a few microseconds are not reliable. For those,
+/// busy-wait on `ticktimer::micros()` instead.
+pub fn set_alarm_us(us: u32, callback: fn()) {
+    start(us_to_cycles(us), 0, Callback::Plain(callback));
+}
+
+/// Set periodic alarm every specified milliseconds
+///
+/// # Arguments
+/// * `ms` - Milliseconds between alarms
+/// * `callback` - Function to call each time the alarm fires (runs in
+///   interrupt context)
+///
+/// # Notes
+/// Timer counts down from LOAD value to 0, fires event, reloads from
+/// RELOAD, and keeps running until `cancel()` is called. The interrupt
+/// handler only clears the pending event in this mode, so the timer is
+/// not stopped between periods.
+pub fn set_periodic_ms(ms: u32, callback: fn()) {
+    let cycles = ms_to_cycles(ms);
+    start(cycles, cycles, Callback::Plain(callback));
+}
+
+/// Set periodic alarm that runs until its callback returns false
+///
+/// # Arguments
+/// * `ms` - Milliseconds between alarms
+/// * `callback` - Function to call each time the alarm fires (runs in
+///   interrupt context). Return true to keep running or false to stop.
+///
+/// # Notes
+/// Works like `set_periodic_ms()`, but when the callback returns false the
+/// interrupt handler stops the timer and forgets the callback, the same as
+/// `cancel()`. This saves setting a flag in the callback and cancelling
+/// from the main loop.
+pub fn set_periodic_ms_until(ms: u32, callback: fn() -> bool) {
+    let cycles = ms_to_cycles(ms);
+    start(cycles, cycles, Callback::Rearm(callback));
+}
+
+/// Stop a periodic (or one-shot) alarm and forget its callback
+///
+/// Same as `cancel_alarm()`, for callers that don't need to know whether
+/// the timer was running.
+pub fn cancel() {
+    cancel_alarm();
+}
+
+/// Disarm a pending alarm so its callback can't run
+///
+/// Stops the timer, clears any pending event, and sets the stored callback
+/// to None. Returns true if an alarm was armed (the timer was running), or
+/// false if there was nothing to cancel, e.g. because a one-shot alarm
+/// already fired.
+pub fn cancel_alarm() -> bool {
+    if in_use_by_fallback() {
+        return false;
+    }
+    // Disable interrupts so the alarm can't fire between the check and stop
+    let _cs = crate::interrupt::critical_section();
+    let was_armed = unsafe { mmio::read32(TIMER0_EN) } & 1 != 0;
+    stop_and_clear();
+    unsafe {
+        TIMER0_PERIODIC = false;
+        TIMER0_CALLBACK = None;
+    }
+    was_armed
+}
+
+/// Start TIMER0 as a free-running counter with no interrupt
+///
+/// Replaces any alarm. See "Free-Running Counter" in the module docs.
+pub fn start_free_running() {
+    if in_use_by_fallback() {
+        return;
+    }
+    let _cs = crate::interrupt::critical_section();
+    stop_and_clear();
+    unsafe {
+        TIMER0_CALLBACK = None;
+        // Periodic, so the interrupt handler would only clear the event if
+        // one were somehow raised
+        TIMER0_PERIODIC = true;
+        mmio::write32(TIMER0_LOAD, u32::MAX);
+        mmio::write32(TIMER0_RELOAD, u32::MAX);
+        mmio::write32(TIMER0_EN, 1);
+    }
+}
+
+/// Read the free-running counter, counting up in ACLK cycles
+///
+/// Latches the current countdown value and returns it inverted, so later
+/// readings are larger (modulo wraparound). Only meaningful after
+/// `start_free_running()`.
+pub fn read_counter() -> u32 {
+    let _cs = crate::interrupt::critical_section();
+    unsafe {
+        mmio::write32(TIMER0_UPDATE_VALUE, 1);
+        !mmio::read32(TIMER0_VALUE)
+    }
+}
+
+// Calculate countdown value in ACLK cycles
+// cycles = (aclk_hz / 1000) * ms
+fn ms_to_cycles(ms: u32) -> u32 {
+    (clock::aclk_hz() / 1000).saturating_mul(ms)
+}
+
+// Calculate countdown value in ACLK cycles
+// cycles = (aclk_hz / 1_000_000) * us
+fn us_to_cycles(us: u32) -> u32 {
+    (clock::aclk_hz() / 1_000_000).saturating_mul(us)
+}
+
+// Warn and return true if TIMER0 is the TICKTIMER fallback clock
+fn in_use_by_fallback() -> bool {
+    let in_use = crate::ticktimer::using_fallback();
+    if in_use {
+        crate::warn!("timer0: in use by the ticktimer fallback clock\r\n");
+    }
+    in_use
+}
+
+/// Start the 1ms periodic tick for the TICKTIMER fallback clock
+///
+/// Only for `ticktimer::init()`, which calls it once, before it sets the
+/// fallback flag that makes the public functions leave TIMER0 alone.
+pub(crate) fn start_fallback_clock(callback: fn()) {
+    let cycles = ms_to_cycles(1);
+    start_unchecked(cycles, cycles, Callback::Plain(callback));
+}
+
+/// Recompute the fallback clock's 1ms period after an ACLK change
+///
+/// Only writes RELOAD, so the running countdown and the millisecond count
+/// are kept. The new period starts at the next reload.
+pub(crate) fn update_fallback_clock() {
+    unsafe { mmio::write32(TIMER0_RELOAD, ms_to_cycles(1)) };
+}
+
+// Configure and start the timer, unless the fallback clock is using it
+fn start(load: u32, reload: u32, callback: Callback) {
+    if !in_use_by_fallback() {
+        start_unchecked(load, reload, callback);
+    }
+}
 
+// Configure and start the timer. A reload value of 0 gives one-shot mode.
+fn start_unchecked(load: u32, reload: u32, callback: Callback) {
     unsafe {
-        // Store callback before starting timer
+        // Store callback and mode before starting timer
         TIMER0_CALLBACK = Some(callback);
+        TIMER0_PERIODIC = reload != 0;
 
         // Disable timer and zero event interrupt before reconfiguring
-        core::ptr::write_volatile(TIMER0_EN, 0);
-        core::ptr::write_volatile(TIMER0_EV_ENABLE, 0);
+        mmio::write32(TIMER0_EN, 0);
+        mmio::write32(TIMER0_EV_ENABLE, 0);
 
         // Clear any pending interrupt
-        core::ptr::write_volatile(TIMER0_EV_PENDING, 1);
+        mmio::write32(TIMER0_EV_PENDING, 1);
 
-        // Ensure timer and interrupts are off before configuring
-        core::sync::atomic::compiler_fence(
-            core::sync::atomic::Ordering::SeqCst,
-        );
+        // Set countdown value
+        mmio::write32(TIMER0_LOAD, load);
 
-        // Set countdown value (one-shot mode)
-        core::ptr::write_volatile(TIMER0_LOAD, cycles);
-
-        // Zero the reload value
-        core::ptr::write_volatile(TIMER0_RELOAD, 0);
+        // Set the reload value (0 for one-shot mode)
+        mmio::write32(TIMER0_RELOAD, reload);
 
         // Enable event interrupt generation
-        core::ptr::write_volatile(TIMER0_EV_ENABLE, 1);
-
-        // Ensure timer is configured before starting
-        core::sync::atomic::compiler_fence(
-            core::sync::atomic::Ordering::SeqCst,
-        );
+        mmio::write32(TIMER0_EV_ENABLE, 1);
 
         // Start timer
-        core::ptr::write_volatile(TIMER0_EN, 1);
+        mmio::write32(TIMER0_EN, 1);
     }
 }
 
 /// Stop timer, clear pending interrupt event, disable interrupt signalling
 pub fn stop_and_clear() {
     unsafe {
-        core::ptr::write_volatile(TIMER0_EN, 0);
-        core::ptr::write_volatile(TIMER0_EV_ENABLE, 0);
-        core::ptr::write_volatile(TIMER0_EV_PENDING, 1); // write 1 to clear!
+        mmio::write32(TIMER0_EN, 0);
+        mmio::write32(TIMER0_EV_ENABLE, 0);
+        mmio::write32(TIMER0_EV_PENDING, 1); // write 1 to clear!
+    }
+}
+
+/// Clear pending interrupt event without stopping the timer
+pub(crate) fn clear_pending() {
+    unsafe {
+        mmio::write32(TIMER0_EV_PENDING, 1); // write 1 to clear!
     }
 }
 
+/// Check whether the timer is in periodic mode (for interrupt handler use)
+pub(crate) fn is_periodic() -> bool {
+    unsafe { TIMER0_PERIODIC }
+}
+
 /// Retrieve the current callback (for interrupt handler use)
-pub(crate) fn get_callback() -> Option<fn()> {
+pub(crate) fn get_callback() -> Option<Callback> {
     unsafe { TIMER0_CALLBACK }
 }
@@ -0,0 +1,180 @@
+// SPDX-License-Identifier: MIT
+// SPDX-FileCopyrightText: Copyright 2026 Sam Blenny
+//
+//! Software timers for bao1x dabao evaluation board
+//!
+//! Provides several independent one-shot and periodic timers on top of
+//! `ticktimer::millis()`, for coarse timing jobs like debouncing, LED
+//! blinking, and protocol timeouts that would otherwise compete for the
+//! single TIMER0 callback.
+//!
+//! # Overview
+//!
+//! Timers live in a fixed table of 8 slots. Nothing fires on its own:
+//! `poll_timers()` checks each slot against `millis()` and calls the
+//! callbacks of any timers that have expired. Call it from the main loop,
+//! or from a periodic TIMER0 callback if the timers need to fire while the
+//! main loop is blocked. Timing resolution is 1ms, plus however long it
+//! takes between calls to `poll_timers()`.
+//!
+//! # Usage
+//!
+//! ```ignore
+//! use baochip_sdk::timers::{self, Timer};
+//!
+//! fn blink() {
+//!     // Toggle an LED
+//! }
+//!
+//! fn timeout() {
+//!     // Give up on a protocol exchange
+//! }
+//!
+//! let _blink = Timer::periodic(500, blink);
+//! let protocol = Timer::once(2000, timeout);
+//! loop {
+//!     timers::poll_timers();
+//!     // ... when the exchange finishes early:
+//!     // if let Some(t) = protocol { t.cancel(); }
+//! }
+//! ```
+//!
+//! # API Design
+//!
+//! - `Timer::once()`: Call a function once after a delay
+//! - `Timer::periodic()`: Call a function repeatedly at a fixed interval
+//! - `Timer::cancel()`: Stop a timer before it fires (again)
+//! - `poll_timers()`: Fire the callbacks of expired timers
+
+use crate::interrupt;
+use crate::ticktimer;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+const TIMER_SLOTS: usize = 8;
+
+// ============================================================================
+// Internal State
+// ============================================================================
+
+#[derive(Clone, Copy)]
+struct Slot {
+    callback: Option<fn()>, // None = free slot
+    deadline_ms: u64,       // millis() value when the timer fires next
+    period_ms: u32,         // Reload interval, 0 = one-shot
+    generation: u32,        // Bumped when the slot is freed
+}
+
+const FREE_SLOT: Slot =
+    Slot { callback: None, deadline_ms: 0, period_ms: 0, generation: 0 };
+
+static mut SLOTS: [Slot; TIMER_SLOTS] = [FREE_SLOT; TIMER_SLOTS];
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Handle for a running software timer.
+///
+/// The handle remembers which slot the timer uses. Once a one-shot timer
+/// fires or a timer is cancelled, the slot can be reused, and an old handle
+/// no longer affects it.
+#[derive(Clone, Copy)]
+pub struct Timer {
+    slot: usize,
+    generation: u32,
+}
+
+impl Timer {
+    /// Call `callback` once, `ms` milliseconds from now.
+    ///
+    /// Returns None if all timer slots are in use.
+    pub fn once(ms: u32, callback: fn()) -> Option<Timer> {
+        start(ms, 0, callback)
+    }
+
+    /// Call `callback` every `ms` milliseconds, starting `ms` from now.
+    ///
+    /// Returns None if all timer slots are in use. Each period is measured
+    /// from the previous deadline rather than from when `poll_timers()`
+    /// noticed it, so the rate does not drift when polling is late.
+    pub fn periodic(ms: u32, callback: fn()) -> Option<Timer> {
+        start(ms, ms.max(1), callback)
+    }
+
+    /// Stop the timer. Does nothing if it already fired or was cancelled.
+    pub fn cancel(self) {
+        let _cs = interrupt::critical_section();
+        unsafe {
+            let slots = &raw mut SLOTS;
+            let slot = &mut (*slots)[self.slot];
+            if slot.callback.is_some() && slot.generation == self.generation {
+                free(slot);
+            }
+        }
+    }
+}
+
+/// Fire the callbacks of all expired timers.
+///
+/// One-shot timers are freed before their callback runs. Periodic timers
+/// are rescheduled for their next deadline. Callbacks run in the context
+/// of the caller (interrupt context if called from a TIMER0 callback).
+pub fn poll_timers() {
+    let now = ticktimer::millis();
+    for i in 0..TIMER_SLOTS {
+        let due = unsafe {
+            let _cs = interrupt::critical_section();
+            let slots = &raw mut SLOTS;
+            let slot = &mut (*slots)[i];
+            match slot.callback {
+                Some(callback) if now >= slot.deadline_ms => {
+                    if slot.period_ms == 0 {
+                        free(slot);
+                    } else {
+                        slot.deadline_ms += slot.period_ms as u64;
+                        // Skip missed periods rather than firing in a burst
+                        if slot.deadline_ms <= now {
+                            slot.deadline_ms = now + slot.period_ms as u64;
+                        }
+                    }
+                    Some(callback)
+                }
+                _ => None,
+            }
+        };
+        // Call outside the critical section so callbacks can start timers
+        if let Some(callback) = due {
+            callback();
+        }
+    }
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+// Claim a free slot for a new timer
+fn start(ms: u32, period_ms: u32, callback: fn()) -> Option<Timer> {
+    let deadline_ms = ticktimer::deadline_ms(ms);
+    let _cs = interrupt::critical_section();
+    unsafe {
+        let slots = &raw mut SLOTS;
+        (*slots).iter_mut().enumerate().find(|(_, s)| s.callback.is_none()).map(
+            |(i, slot)| {
+                slot.callback = Some(callback);
+                slot.deadline_ms = deadline_ms;
+                slot.period_ms = period_ms;
+                Timer { slot: i, generation: slot.generation }
+            },
+        )
+    }
+}
+
+// Release a slot, invalidating handles that refer to it
+fn free(slot: &mut Slot) {
+    slot.callback = None;
+    slot.generation = slot.generation.wrapping_add(1);
+}
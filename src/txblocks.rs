@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: MIT
+// SPDX-FileCopyrightText: Copyright 2026 Sam Blenny
+//
+//! Block FIFO fill step for the uart TX buffer
+//!
+//! `uart::try_write()` queues TX data in a ring of fixed-size blocks (see
+//! "Internal State" in `uart`). Deciding which block and offset each byte
+//! goes to needs no hardware, so that step lives here, where the host unit
+//! tests can reach it. The uart module owns the state and passes it in.
+//!
+//! # Why fill() Doesn't Skip Busy Blocks
+//!
+//! fill() stops at the first block that is still queued or in flight, even
+//! if a block further round the ring is free. Blocks only become ready at
+//! the fill position and only empty at the DMA head, so the busy blocks are
+//! always one contiguous run, and a busy block at the fill position means
+//! the ring is full, with no free block to skip to. Even if there were one,
+//! filling it would send the new data ahead of the queued blocks, out of
+//! order. The tests pin this down for the wraparound cases.
+
+/// Copy bytes of `data` into the block ring until it runs out of room.
+///
+/// `block_len` has one entry per block in use, with 0 for a free block (or
+/// the one being filled) and the byte count for a block that is ready or
+/// in flight. `next_block` and `fill_len` are the block being filled and
+/// how many bytes it already holds. `store(offset, byte)` writes a byte at
+/// `offset` from the start of the ring. A block that fills is marked ready
+/// with `block_bytes` and the fill position moves to the next block.
+///
+/// Returns the number of bytes copied, which is less than `data.len()` if
+/// the fill position reaches a busy block.
+pub(crate) fn fill(
+    block_len: &mut [u16],
+    block_bytes: usize,
+    next_block: &mut usize,
+    fill_len: &mut usize,
+    data: &[u8],
+    mut store: impl FnMut(usize, u8),
+) -> usize {
+    let mut written = 0;
+    for &byte in data {
+        if block_len[*next_block] > 0 {
+            break;
+        }
+        store(*next_block * block_bytes + *fill_len, byte);
+        *fill_len += 1;
+        written += 1;
+        if *fill_len == block_bytes {
+            block_len[*next_block] = block_bytes as u16;
+            *next_block = (*next_block + 1) % block_len.len();
+            *fill_len = 0;
+        }
+    }
+    written
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::fill;
+
+    const BLOCK_BYTES: usize = 4;
+
+    // Run fill() over a 3-block ring, recording where each byte went
+    fn run(
+        lens: &mut [u16; 3],
+        next: &mut usize,
+        fill_len: &mut usize,
+        data: &[u8],
+    ) -> (usize, [u8; 12]) {
+        let mut ring = [0u8; 12];
+        let n = fill(lens, BLOCK_BYTES, next, fill_len, data, |at, b| {
+            ring[at] = b;
+        });
+        (n, ring)
+    }
+
+    #[test]
+    fn fills_across_blocks() {
+        let (mut lens, mut next, mut fill_len) = ([0; 3], 0, 0);
+        let (n, ring) = run(&mut lens, &mut next, &mut fill_len, b"abcdef");
+        assert_eq!(n, 6);
+        assert_eq!(&ring[..6], b"abcdef");
+        assert_eq!(lens, [4, 0, 0]);
+        assert_eq!((next, fill_len), (1, 2));
+    }
+
+    #[test]
+    fn stops_when_every_block_is_full() {
+        let (mut lens, mut next, mut fill_len) = ([0; 3], 0, 0);
+        let data = b"0123456789abc";
+        let (n, ring) = run(&mut lens, &mut next, &mut fill_len, data);
+        assert_eq!(n, 12);
+        assert_eq!(&ring, b"0123456789ab");
+        assert_eq!(lens, [4, 4, 4]);
+        assert_eq!((next, fill_len), (0, 0));
+
+        // Full ring: nothing more fits
+        let (n, _) = run(&mut lens, &mut next, &mut fill_len, b"x");
+        assert_eq!(n, 0);
+        assert_eq!((next, fill_len), (0, 0));
+    }
+
+    #[test]
+    fn wraps_to_block_zero() {
+        // Blocks 0 and 1 already sent, filling resumes at block 2
+        let (mut lens, mut next, mut fill_len) = ([0; 3], 2, 0);
+        let (n, ring) = run(&mut lens, &mut next, &mut fill_len, b"abcdef");
+        assert_eq!(n, 6);
+        assert_eq!(&ring[8..], b"abcd");
+        assert_eq!(&ring[..2], b"ef");
+        assert_eq!(lens, [0, 0, 4]);
+        assert_eq!((next, fill_len), (0, 2));
+    }
+
+    #[test]
+    fn wrap_stops_at_busy_block_despite_later_free_block() {
+        // Block 0 is queued, block 1 is free, filling is at block 2. After
+        // block 2 fills, the wrap reaches busy block 0. Block 1 must not be
+        // used, since DMA would send it before block 2, out of order.
+        let (mut lens, mut next, mut fill_len) = ([4, 0, 0], 2, 0);
+        let (n, ring) = run(&mut lens, &mut next, &mut fill_len, b"abcdefgh");
+        assert_eq!(n, 4);
+        assert_eq!(&ring[8..], b"abcd");
+        assert_eq!(&ring[4..8], [0; 4]);
+        assert_eq!(lens, [4, 0, 4]);
+        assert_eq!((next, fill_len), (0, 0));
+    }
+
+    #[test]
+    fn busy_fill_block_writes_nothing() {
+        // Scattered free blocks 0 and 2 around busy block 1 at the fill
+        // position: the write is refused rather than reordered.
+        let (mut lens, mut next, mut fill_len) = ([0, 4, 0], 1, 0);
+        let (n, ring) = run(&mut lens, &mut next, &mut fill_len, b"ab");
+        assert_eq!(n, 0);
+        assert_eq!(ring, [0; 12]);
+        assert_eq!(lens, [0, 4, 0]);
+        assert_eq!((next, fill_len), (1, 0));
+    }
+}
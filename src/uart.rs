@@ -4,8 +4,8 @@
 //! UART2 driver for bao1x dabao evaluation board
 //!
 //! Provides non-blocking, DMA-based serial I/O for debug logging and REPL
-//! shells. TX uses buffered DMA transfers, RX uses direct polling via the
-//! VALID register.
+//! shells. TX uses buffered DMA transfers, RX uses an interrupt-fed ring
+//! buffer.
 //!
 //! # Usage
 //!
@@ -45,6 +45,13 @@
 //! The GPIO module must configure these pins as alternate function AF1
 //! before UART2 can communicate.
 //!
+//! # Break Condition
+//!
+//! UART_SETUP has no break-enable bit, so send_break() holds the line low
+//! by temporarily switching PB14 from AF1 to a GPIO output driven low, then
+//! switching it back to AF1. It calls flush() first so no queued data is
+//! cut off by the break.
+//!
 //! # Clock Configuration
 //!
 //! UART2 is clocked by PERCLK (100 MHz). Baud rate divisor is computed:
@@ -61,26 +68,220 @@
 //! - Bit 0: Parity enable (0 = no parity)
 //! For 8N1: 0x0316
 //!
+//! # Reset Sequence
+//!
+//! init() doesn't assume the bootloader left UART2 idle. After flashing
+//! over a running system, a TX transfer may still be in flight or the RX
+//! interrupt may be enabled, which would garble the first write. So init()
+//! resets the UART before applying its setup value:
+//! 1. Disable the UART RX interrupt and the UART2_RX_CHAR event in
+//!    IRQARRAY5
+//! 2. Write UART_SETUP with TX and RX disabled and the RX FIFO clean bit
+//!    (bit 5) set
+//! 3. Stop the TX and RX DMA channels with their CFG clear bits, and zero
+//!    TX_SADDR and TX_SIZE
+//! 4. Read ERROR to clear stale error flags, and clear the pending
+//!    UART2_RX_CHAR event
+//!
+//! Writing the new UART_SETUP value then re-enables TX and RX and releases
+//! the FIFO clean bit. The RX interrupt is enabled last, once the RX ring
+//! has been reset.
+//!
+//! # Loopback
+//!
+//! There is no internal loopback mode. UART_SETUP only has the parity,
+//! data bits, stop bits, RX polling, RX FIFO clean, TX/RX enable and
+//! divisor fields listed above, and no other UART register has a loopback
+//! bit, so this driver has no loopback self-test. To check the UART path
+//! without a host, wire PB14 (TX) to PB13 (RX), write a pattern, and read
+//! it back with getc_timeout().
+//!
+//! # Frame Format
+//!
+//! `init()` uses 8N1. Use `init_with_config()` with a `UartConfig` for
+//! other frame formats. The hardware supports:
+//! - 5, 6, 7, or 8 data bits
+//! - No parity or even parity (there is no odd/even select bit, and the
+//!   parity generator always produces even parity)
+//! - 1 or 2 stop bits
+//!
+//! Any combination of those works, so 7E1 and 8N2 are both available, but
+//! odd parity formats such as 7O1 or 8O1 are not.
+//!
 //! # TX DMA and Memory Layout
 //!
 //! TX data is buffered in IFRAM0 (0x50000000 - 0x5001FFFF). The 2KB TX
-//! buffer is divided into 16x128-byte blocks. Each write() call fills one
-//! or more blocks sequentially. Blocks that fill become ready for DMA.
-//! tick() starts DMA transfers for ready blocks.
+//! buffer (0x50000000 - 0x500007FF) is divided into 16x128-byte blocks by
+//! default. init_with_tx_buffer() can split it differently, into 2 to 128
+//! blocks of 16 to 256 bytes, as long as they fit in the same 2KB.
+//! Setting up a `serial` port takes the top 512 bytes (0x50000600 -
+//! 0x500007FF) for the serial TX blocks, which drops the default layout
+//! to 12x128 and limits init_with_tx_buffer() to 1.5KB from then on.
+//! Programs that only use UART2 keep the whole 2KB.
+//! Smaller blocks suit byte-at-a-time output, since a block is tied up
+//! until its transfer finishes, and larger blocks mean fewer transfers for
+//! bulk output.
+//! The uDMA address registers only have 12 bits, so every DMA buffer must
+//! sit in the first 4KB of IFRAM0, which the UART, `serial`, I2C and SPI
+//! drivers share. write() appends to the
+//! current block, continuing where the previous write() left off, and moves
+//! on to the next block when the current one fills. Blocks that fill become
+//! ready for DMA. tick() starts DMA transfers for ready blocks, and when DMA
+//! is idle with no ready blocks, it hands off the partially filled current
+//! block. So, byte-at-a-time output (e.g. REPL echo) accumulates in one
+//! block while a previous transfer is running, rather than using a whole
+//! block per byte.
+//!
+//! # TX Stall Recovery
+//!
+//! A DMA transfer is complete once TX_SADDR reads 0. If that never
+//! happens, the queue would stay stuck behind the in-flight block and all
+//! output would stop without any sign of why. So tick() notes the
+//! `millis()` time when it starts each transfer, and if the transfer is
+//! still running after the stall timeout (2s by default, set with
+//! set_tx_stall_timeout_ms()), it stops the channel with the TX_CFG clear
+//! bit, drops the rest of the head block, counts the stall, and moves on
+//! to the next block. It then logs a warning, which goes out through the
+//! recovered queue. tx_stall_count() reports how many transfers were
+//! abandoned, so callers can detect a recurring problem.
+//!
+//! The timeout has to be longer than one block takes to send at the
+//! configured baud rate, or healthy transfers would be cut off. The 2s
+//! default covers rates down to 1200 baud with the default 128-byte
+//! blocks (256-byte blocks need 2400 baud or faster).
+//!
+//! # Polled TX
+//!
+//! The uDMA UART has no TX data register, so every byte goes out by DMA.
+//! write_byte_blocking() sends a single byte without the TX block queue:
+//! it waits for any queued transfer to finish, copies the byte into its own
+//! one-byte IFRAM buffer (0x500008FC), runs a 1-byte DMA transfer, and
+//! polls until the UART has sent it. It only touches the UART registers,
+//! so it still works when the queue state is corrupt or interrupts are
+//! off, which makes it suitable for the panic handler and fault paths.
+//! write_polled() does the same for a byte slice, and PolledUart wraps it
+//! in `core::fmt::Write`. They are slow (one transfer per byte) and block
+//! until every byte is out, so use write() for normal output.
+//!
+//! Since the polled path never touches the TX block queue, it is safe to
+//! use from the trap handler even when the interrupted code was in the
+//! middle of write() or tick(). The trap handler's diagnostic messages use
+//! it for that reason. REG_DATA can't be used for this instead: it is the
+//! RX data register, and the UART has no TX data register to poll.
 //!
 //! # RX Design
 //!
-//! RX does not use DMA or internal buffering. getc() directly polls the
-//! VALID register and reads bytes one at a time.
+//! RX does not use DMA. The UART runs in RX polling mode with its RX
+//! interrupt enabled, which raises the UART2_RX_CHAR event (bit 10 of
+//! IRQARRAY5, base 0xe0013000) when a byte arrives. IRQARRAY5 is enabled
+//! by MIM bit 5, which is set at boot by `interrupt::irq_setup()`. The trap
+//! handler calls `handle_rx_interrupt()`, which drains the VALID/DATA
+//! registers into a 256-byte ring buffer. getc() reads from the ring, so
+//! bytes that arrive while the main loop is busy (e.g. in `sleep()`) are
+//! kept until the ring fills.
+//!
+//! When the ring is full, newly received bytes are dropped and an overrun
+//! flag is set. Check and clear it with rx_overrun().
+//!
+//! # Line Callback
+//!
+//! For command interfaces (AT commands, a debug shell), set_line_callback()
+//! switches RX to an event-driven mode: the RX interrupt collects bytes in
+//! a 128-byte line buffer instead of the ring, and when the delimiter byte
+//! arrives it calls the callback with the line (without the delimiter):
+//!
+//! ```ignore
+//! fn on_line(line: &[u8]) {
+//!     // Interrupt context: parse the command, or copy it out for main
+//! }
+//!
+//! uart::set_line_callback(b'\r', on_line);
+//! ```
+//!
+//! The callback runs in interrupt context with interrupts disabled, so it
+//! should be short. It may call write(), but not anything that waits for
+//! TX or RX (write_all(), flush(), getc_timeout(), read_line()). The line
+//! slice is only valid during the call. Terminals send CR, LF, or CR LF,
+//! so with a CR delimiter an LF right after it is skipped, and with an LF
+//! delimiter a CR right before it is trimmed. Bytes past the 128th in a
+//! line are dropped and set the rx_overrun() flag, and the truncated line
+//! is still delivered when the delimiter arrives.
+//!
+//! While a line callback is set, getc(), read(), and read_line() see no
+//! new data. clear_line_callback() returns to the ring buffer and discards
+//! any partial line.
+//!
+//! # Flow Control
+//!
+//! The UART has no RTS/CTS hardware (UART_SETUP has no flow control bits),
+//! so enable_flow_control() implements both in software on two GPIO pins,
+//! using the usual active-low levels:
+//! - RTS (output): Held low while the RX ring has room. The RX interrupt
+//!   drives it high once 192 of the 256 bytes are in use, and getc() or
+//!   read() drive it low again once the ring drains to 64 bytes. The 64
+//!   bytes of headroom cover bytes the other side sends before it notices.
+//! - CTS (input, pulled up): tick() only starts a TX DMA transfer while
+//!   CTS reads low. With flow control on, blocks go out in 16-byte
+//!   transfers, so at most 16 more bytes are sent after CTS goes high.
+//!
+//! CTS is sampled by tick(), so TX resumes on the next tick() after CTS
+//! goes low again. While CTS stays high, flush() and write_all() wait.
+//! write_byte_blocking() ignores CTS, since it is meant for panic output.
+//!
+//! # RX Errors
+//!
+//! The ERROR register reports receive errors, and reading it clears them:
+//! - Bit 0: RX overflow (a byte arrived before the previous one was read)
+//! - Bit 1: Parity error (only possible with `Parity::Even` configured)
+//!
+//! There is no framing error (bad stop bit) flag. The RX interrupt handler
+//! and getc() read ERROR and accumulate its bits, so errors are not lost
+//! between calls. rx_errors() returns the accumulated flags and clears
+//! them.
 //!
 //! # API Design
 //!
 //! - init(): Set up UART2 and initial state
+//! - init_with_config(): Set up UART2 with a custom frame format
+//! - init_with_tx_buffer(): Re-split the TX buffer into different blocks
 //! - write(): Buffer TX data (non-blocking, silent drop if full)
-//! - getc(): Read one byte from RX if available
+//! - try_write(): Buffer TX data, returning an error if the buffer fills
+//! - write_all(): Buffer all TX data, blocking until there is room
+//! - write_all_timeout(): Like write_all(), but gives up after a timeout
+//! - tx_free(): Number of bytes write() can queue without dropping any
+//! - tx_pending(): Number of queued bytes not yet sent
+//! - getc(): Read one byte from the RX ring if available
+//! - getc_timeout(): Wait a bounded time for one byte from the RX ring
+//! - read(): Read all available bytes from the RX ring, up to a limit
+//! - read_line(): Read a line with backspace handling and optional echo
+//! - rx_available(): Number of bytes waiting in the RX ring
+//! - rx_overrun(): Check and clear the RX overrun flag
+//! - rx_errors(): Check and clear the hardware RX error flags
+//! - set_line_callback(): Deliver each received line to a callback
+//! - clear_line_callback(): Go back to buffering RX in the ring
 //! - tick(): Start DMA for ready TX blocks
+//! - flush(): Block until all queued TX data has been sent
+//! - set_tx_stall_timeout_ms(): Set how long a TX DMA transfer may take
+//! - tx_stall_count(): Number of stalled TX transfers tick() recovered from
+//! - set_baud(): Change the baud rate after flushing TX
+//! - send_break(): Hold TX low for a number of milliseconds
+//! - enable_flow_control(): Use GPIO pins for software RTS/CTS
+//! - disable_flow_control(): Turn software RTS/CTS off again
+//! - write_byte_blocking(): Send one byte by polling, bypassing the queue
+//! - write_polled(): Send bytes by polling, for panic and interrupt context
+//! - Uart: Zero-sized handle implementing `core::fmt::Write`
+//! - PolledUart: Like Uart, but using write_polled()
 
+use crate::clock;
+use crate::cycles;
+use crate::gpio::{self, AF, GpioPin, PinToken};
 use crate::interrupt;
+use crate::irqarray::IRQARRAY5;
+use crate::mmio;
+use crate::pac;
+use crate::ticktimer;
+use crate::txblocks;
+use core::fmt;
 use core::ptr;
 use core::slice;
 
@@ -89,54 +290,225 @@ use core::slice;
 // ============================================================================
 
 // UART2 register addresses
-const REG_TX_SADDR: *mut u32 = 0x50103010 as *mut u32;
-const REG_TX_SIZE: *mut u32 = 0x50103014 as *mut u32;
-const REG_TX_CFG: *mut u32 = 0x50103018 as *mut u32;
-const REG_UART_SETUP: *mut u32 = 0x50103024 as *mut u32;
-const REG_VALID: *mut u32 = 0x50103030 as *mut u32;
-const REG_DATA: *mut u32 = 0x50103034 as *mut u32;
-
-// uDMA Control register addresses
-const UDMA_REG_CG: *mut u32 = 0x50100000 as *mut u32;
+const REG_RX_CFG: usize = pac::UDMA_UART2_BASE + 0x08;
+const REG_TX_SADDR: usize = pac::UDMA_UART2_BASE + 0x10;
+const REG_TX_SIZE: usize = pac::UDMA_UART2_BASE + 0x14;
+const REG_TX_CFG: usize = pac::UDMA_UART2_BASE + 0x18;
+const REG_STATUS: usize = pac::UDMA_UART2_BASE + 0x20;
+const REG_UART_SETUP: usize = pac::UDMA_UART2_BASE + 0x24;
+const REG_ERROR: usize = pac::UDMA_UART2_BASE + 0x28;
+const REG_IRQ_EN: usize = pac::UDMA_UART2_BASE + 0x2c;
+const REG_VALID: usize = pac::UDMA_UART2_BASE + 0x30;
+const REG_DATA: usize = pac::UDMA_UART2_BASE + 0x34;
 
-// Peripheral IDs (bit masks)
-const UART2_CLK_BIT: u32 = 1 << 2;
+// UART2 pins (AF1)
+const RX_PIN: GpioPin = GpioPin::PortB(gpio::PB13);
+const TX_PIN: GpioPin = GpioPin::PortB(gpio::PB14);
 
 // TX/RX configuration bits
-const CFG_EN: u32 = 1 << 4;
+const CFG_EN: u32 = pac::UDMA_CFG_EN;
+const CFG_CLR: u32 = pac::UDMA_CFG_CLR;
 
 // UART_SETUP register bits
+const UART_PARITY_EN: u32 = 1 << 0;
+const UART_BITS_SHIFT: u32 = 1;
+const UART_STOP_BITS_2: u32 = 1 << 3;
+const UART_RX_POLLING: u32 = 1 << 4;
+const UART_RX_CLEAN_FIFO: u32 = 1 << 5;
 const UART_EN_TX: u32 = 1 << 8;
 const UART_EN_RX: u32 = 1 << 9;
+const UART_DIVISOR_SHIFT: u32 = 16;
+const UART_DIVISOR_MASK: u32 = 0xffff << UART_DIVISOR_SHIFT;
+
+// STATUS register bits
+const STATUS_TX_BUSY: u32 = 1 << 0;
+
+// ERROR register bits
+const ERROR_RX_OVERFLOW: u32 = 1 << 0;
+const ERROR_RX_PARITY: u32 = 1 << 1;
+
+// IRQ_EN register bits
+const IRQ_EN_RX: u32 = 1 << 0;
 
 // VALID register bits
 const VALID_DATA_AVAILABLE: u32 = 1 << 0;
 
+// UART2_RX_CHAR event in IRQARRAY5
+const UART2_RX_CHAR_BIT: u32 = pac::IRQARRAY5_UART2_RX_CHAR;
+
 // TX buffer configuration
-const IFRAM_TX_ADDR: usize = 0x50000000;
-const TX_BLOCK_SIZE: usize = 128;
-const TX_BLOCK_COUNT: usize = 16;
+const IFRAM_TX_ADDR: usize = pac::IFRAM_UART_TX;
+const TX_BLOCK_SIZE: usize = 128; // Default, see init_with_tx_buffer()
+const TX_BLOCK_COUNT: usize = 16; // Default, see init_with_tx_buffer()
+const TX_AREA_SIZE: usize = pac::IFRAM_UART_TX_SIZE;
+const TX_BLOCK_MIN: usize = 16;
+const TX_BLOCK_MAX: usize = 256;
+const TX_BLOCKS_MAX: usize = TX_AREA_SIZE / TX_BLOCK_MIN;
+
+// One-byte buffer for write_byte_blocking(), in the unused tail of the I2C
+// command buffer area (0x50000800 - 0x500008FF, of which I2C uses 64 bytes)
+const IFRAM_POLLED_TX_ADDR: usize = pac::IFRAM_UART_POLLED_TX;
+
+// How long write_byte_blocking() waits for TX before giving up. Counted in
+// CPU cycles, because in the ticktimer fallback millis() only advances in
+// the TIMER0 interrupt, which can't run inside the critical section.
+const POLLED_TX_TIMEOUT_MS: u32 = 10;
+
+// Default time a TX DMA transfer may take before tick() treats it as
+// stalled. A full 128-byte block takes about 1.3ms at 1 Mbps and about
+// 1.1s at 1200 baud.
+const TX_STALL_TIMEOUT_DEFAULT_MS: u32 = 2000;
 
-// UART configuration: 8N1, 1 Mbps
-const PERCLK_HZ: u32 = 100_000_000;
+// RX ring buffer configuration
+const RX_RING_SIZE: usize = 256;
+
+// Software flow control: deassert RTS when the RX ring reaches the high
+// water mark, assert it again once it drains to the low water mark, and
+// send at most FLOW_TX_CHUNK bytes per DMA transfer so TX stops soon
+// after CTS deasserts.
+const RTS_HIGH_WATER: usize = RX_RING_SIZE - 64;
+const RTS_LOW_WATER: usize = RX_RING_SIZE / 4;
+const FLOW_TX_CHUNK: usize = 16;
+
+// Line buffer for set_line_callback()
+const LINE_BUF_SIZE: usize = 128;
+
+// UART configuration: 1 Mbps
 const UART_BAUD: u32 = 1_000_000;
-const UART_DIVISOR: u32 = PERCLK_HZ / UART_BAUD;
-const UART_SETUP_VALUE: u32 = 0x0316 | (UART_DIVISOR << 16);
+
+// ============================================================================
+// Frame Format
+// ============================================================================
+
+/// Number of data bits per frame (UART_SETUP bits [2:1]).
+#[derive(Clone, Copy)]
+pub enum DataBits {
+    Five = 0,
+    Six = 1,
+    Seven = 2,
+    Eight = 3,
+}
+
+/// Parity mode (UART_SETUP bit 0).
+///
+/// The hardware has no odd/even select, so only even parity is available.
+#[derive(Clone, Copy)]
+pub enum Parity {
+    None,
+    Even,
+}
+
+/// Number of stop bits per frame (UART_SETUP bit 3).
+#[derive(Clone, Copy)]
+pub enum StopBits {
+    One,
+    Two,
+}
+
+/// UART frame format.
+///
+/// The default is 8N1. For example, 7E1 is:
+/// ```ignore
+/// use uart::{DataBits, Parity, StopBits, UartConfig};
+///
+/// let config = UartConfig {
+///     data_bits: DataBits::Seven,
+///     parity: Parity::Even,
+///     stop_bits: StopBits::One,
+/// };
+/// uart::init_with_config(config);
+/// ```
+#[derive(Clone, Copy)]
+pub struct UartConfig {
+    pub data_bits: DataBits,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+}
+
+impl Default for UartConfig {
+    fn default() -> Self {
+        UartConfig {
+            data_bits: DataBits::Eight,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+        }
+    }
+}
+
+impl UartConfig {
+    // Build the UART_SETUP value (without TX/RX enable bits)
+    pub(crate) fn setup_value(&self, baud: u32) -> u32 {
+        let mut value = (self.data_bits as u32) << UART_BITS_SHIFT;
+        if let Parity::Even = self.parity {
+            value |= UART_PARITY_EN;
+        }
+        if let StopBits::Two = self.stop_bits {
+            value |= UART_STOP_BITS_2;
+        }
+        let divisor = clock::perclk_hz() / baud;
+        value | UART_RX_POLLING | (divisor << UART_DIVISOR_SHIFT)
+    }
+}
 
 // ============================================================================
 // Internal State
 // ============================================================================
 
-// TX buffer implemented as a circular FIFO of 128-byte blocks.
+// TX buffer implemented as a circular FIFO of TX_BLOCKS blocks of
+// TX_BLOCK_BYTES each (16x128 unless init_with_tx_buffer() or serial
+// changed it).
 // TX_NEXT_BLOCK points to the block being filled by write().
+// TX_FILL_LEN is the number of bytes write() has put in TX_NEXT_BLOCK so
+// far. Those bytes are not ready for DMA until the block fills or tick()
+// hands it off.
 // TX_QUEUE_HEAD points to the oldest block ready for DMA.
-// TX_BLOCK_LEN[i] stores the actual byte count for block i (0 = empty/done).
+// TX_BLOCK_LEN[i] stores the byte count for a ready or in-flight block i
+// (0 = empty/done, or still being filled).
 // When TX_NEXT_BLOCK == TX_QUEUE_HEAD and both have pending data, the
 // buffer is full.
+// Blocks only become ready at TX_NEXT_BLOCK and only empty at
+// TX_QUEUE_HEAD, so the ready blocks are always the contiguous run from
+// TX_QUEUE_HEAD up to TX_NEXT_BLOCK. Free blocks are never scattered
+// between ready ones, and if TX_NEXT_BLOCK itself is not empty, every
+// block is in use.
 static mut TX_NEXT_BLOCK: usize = 0; // Block index for next write()
-static mut TX_BLOCK_LEN: [u8; TX_BLOCK_COUNT] = [0; 16];
+static mut TX_FILL_LEN: usize = 0; // Bytes in TX_NEXT_BLOCK not yet ready
+static mut TX_BLOCKS: usize = TX_BLOCK_COUNT; // Blocks in use
+static mut TX_BLOCK_BYTES: usize = TX_BLOCK_SIZE; // Bytes per block
+static mut TX_AREA_LIMIT: usize = TX_AREA_SIZE; // Shrinks for serial
+static mut TX_BLOCK_LEN: [u16; TX_BLOCKS_MAX] = [0; TX_BLOCKS_MAX];
 static mut TX_QUEUE_HEAD: usize = 0; // Block index for next DMA
 static mut TX_IN_FLIGHT: bool = false; // DMA transfer active
+static mut TX_HEAD_SENT: usize = 0; // Bytes of TX_QUEUE_HEAD already sent
+static mut TX_CHUNK_LEN: usize = 0; // Length of the active DMA transfer
+static mut TX_START_MS: u64 = 0; // millis() when the active DMA started
+static mut TX_STALL_TIMEOUT_MS: u32 = TX_STALL_TIMEOUT_DEFAULT_MS;
+static mut TX_STALL_COUNT: u32 = 0; // Transfers abandoned by tick()
+
+// Claims on RX_PIN and TX_PIN, held from init() on
+static mut PIN_TOKENS: Option<[PinToken; 2]> = None;
+
+// GPIO pins used for software RTS/CTS, if enabled
+static mut FLOW_RTS: Option<GpioPin> = None;
+static mut FLOW_CTS: Option<GpioPin> = None;
+
+// RX ring buffer filled by handle_rx_interrupt() and drained by getc().
+// RX_HEAD is the next slot to fill, RX_TAIL is the next slot to read, and
+// RX_COUNT is the number of bytes waiting (so full and empty are distinct).
+static mut RX_RING: [u8; RX_RING_SIZE] = [0; RX_RING_SIZE];
+static mut RX_HEAD: usize = 0;
+static mut RX_TAIL: usize = 0;
+static mut RX_COUNT: usize = 0;
+static mut RX_OVERRUN: bool = false; // Bytes dropped because ring was full
+static mut RX_ERROR_BITS: u32 = 0; // ERROR register bits seen since last read
+static mut RX_LAST_CR: bool = false; // read_line() saw CR, so skip a next LF
+
+// Line mode state, used instead of the RX ring while LINE_CALLBACK is set
+static mut LINE_CALLBACK: Option<fn(&[u8])> = None;
+static mut LINE_DELIM: u8 = b'\r';
+static mut LINE_BUF: [u8; LINE_BUF_SIZE] = [0; LINE_BUF_SIZE];
+static mut LINE_LEN: usize = 0;
+static mut LINE_LAST_CR: bool = false; // Previous byte was CR
 
 // ============================================================================
 // C API Convenience Functions
@@ -175,117 +547,534 @@ pub extern "C" fn uart_write(data: *const u8) {
 
 /// Initialize UART2 for 8N1 at 1 Mbps.
 ///
-/// Enables the UART2 clock and configures the UART_SETUP register.
-/// This assumes the bootloader has taken care of resetting the UART.
+/// Enables the UART2 clock, resets the UART (see "Reset Sequence" in the
+/// module docs), and configures the UART_SETUP register.
 ///
 /// GPIO pins PB13 and PB14 must be configured separately via the GPIO
-/// module as alternate function AF1 before UART2 can communicate.
+/// module as alternate function AF1 before UART2 can communicate. This
+/// claims both pins (see `gpio::try_claim()`), so configuring them as
+/// GPIO afterwards panics in debug builds.
 ///
 /// Must be called before any other UART functions.
 pub fn init() {
+    init_with_config(UartConfig::default());
+}
+
+/// Initialize UART2 at 1 Mbps with a custom frame format.
+///
+/// Works like `init()`, but builds UART_SETUP from `config` instead of
+/// using 8N1. See the module docs for the supported frame formats.
+pub fn init_with_config(config: UartConfig) {
+    claim_pins();
     unsafe {
         // Enable UART2 clock via uDMA control
-        let cg = ptr::read_volatile(UDMA_REG_CG);
-        ptr::write_volatile(UDMA_REG_CG, cg | UART2_CLK_BIT);
-        // Ensure clock enable completes before configuring UART
-        core::sync::atomic::compiler_fence(
-            core::sync::atomic::Ordering::SeqCst,
-        );
+        clock::udma_clock_enable(clock::UDMA_CG_UART0 << 2);
+
+        // Clear whatever state the bootloader or a previous run left behind
+        reset();
 
-        // Configure UART_SETUP for 8N1, 1 Mbps
-        // The bootloader has already reset the UART, so we just configure it.
-        ptr::write_volatile(
+        // Configure UART_SETUP for the frame format at 1 Mbps
+        mmio::write32(
             REG_UART_SETUP,
-            UART_SETUP_VALUE | UART_EN_TX | UART_EN_RX,
+            config.setup_value(UART_BAUD) | UART_EN_TX | UART_EN_RX,
         );
 
         // Initialize TX buffer state
         TX_NEXT_BLOCK = 0;
+        TX_FILL_LEN = 0;
         TX_QUEUE_HEAD = 0;
         TX_IN_FLIGHT = false;
-        for i in 0..TX_BLOCK_COUNT {
+        TX_HEAD_SENT = 0;
+        TX_CHUNK_LEN = 0;
+        TX_STALL_COUNT = 0;
+        for i in 0..TX_BLOCKS_MAX {
             TX_BLOCK_LEN[i] = 0;
         }
+
+        // Initialize RX ring state
+        RX_HEAD = 0;
+        RX_TAIL = 0;
+        RX_COUNT = 0;
+        RX_OVERRUN = false;
+        LINE_LEN = 0;
+        LINE_LAST_CR = false;
+        // Reading ERROR clears any stale error flags
+        mmio::read32(REG_ERROR);
+        RX_ERROR_BITS = 0;
+
+        // Enable the RX interrupt and UART2_RX_CHAR event signalling in
+        // IRQARRAY5, clearing any stale pending event first
+        mmio::write32(REG_IRQ_EN, IRQ_EN_RX);
+        IRQARRAY5.clear_pending(UART2_RX_CHAR_BIT);
+        IRQARRAY5.enable(UART2_RX_CHAR_BIT);
     }
 }
 
+/// Hardware receive error flags, as returned by rx_errors().
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RxErrors {
+    /// A byte arrived before the previous one was read from DATA
+    pub overflow: bool,
+    /// A byte was received with the wrong parity bit
+    pub parity: bool,
+}
+
+impl RxErrors {
+    /// Return true if any error flag is set.
+    pub fn any(&self) -> bool {
+        self.overflow || self.parity
+    }
+}
+
+/// Error returned by init_with_tx_buffer().
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxBufferError {
+    /// Fewer than 2 blocks, or a block size outside 16-256 bytes
+    InvalidSize,
+    /// The blocks don't fit in the TX area of IFRAM0 (2KB, or 1.5KB once a
+    /// `serial` port is set up)
+    TooLarge,
+}
+
+/// Error returned by try_write().
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WriteError {
+    /// The TX buffer filled up after queueing this many bytes. The rest of
+    /// the data was not queued.
+    Full(usize),
+}
+
+/// Re-split the TX buffer into `blocks` blocks of `block_size` bytes.
+///
+/// The buffer has to stay in 0x50000000 - 0x500007FF, because the uDMA
+/// address registers only reach the first 4KB of IFRAM0 and the rest of
+/// that is used by the I2C and SPI drivers (see "TX DMA and Memory Layout"
+/// in the module docs). So this can trade block size for block count, but
+/// not make the buffer bigger than 2KB, or 1.5KB once a `serial` port is
+/// set up. `blocks` must be at
+/// least 2, so one block can fill while another is sent, and `block_size`
+/// must be 16-256 bytes.
+///
+/// Flushes queued TX data first, then sets up the new layout and calls
+/// init(), so the UART is back at 8N1, 1 Mbps. The layout is kept by later
+/// init() calls.
+pub fn init_with_tx_buffer(
+    blocks: usize,
+    block_size: usize,
+) -> Result<(), TxBufferError> {
+    if blocks < 2 || !(TX_BLOCK_MIN..=TX_BLOCK_MAX).contains(&block_size) {
+        return Err(TxBufferError::InvalidSize);
+    }
+    if blocks * block_size > unsafe { TX_AREA_LIMIT } {
+        return Err(TxBufferError::TooLarge);
+    }
+    flush();
+    {
+        let _cs = interrupt::critical_section();
+        unsafe {
+            TX_BLOCKS = blocks;
+            TX_BLOCK_BYTES = block_size;
+        }
+    }
+    init();
+    Ok(())
+}
+
 /// Queue data for transmission via DMA.
 ///
-/// Each call to write() uses a fresh block (or multiple blocks if data is
-/// large). Does not continue filling a block from a previous write() call.
-/// When a block fills (128 bytes) or when this call ends, it becomes
-/// eligible for DMA. Returns the number of bytes actually buffered. If no
-/// fresh blocks are available, remaining data is silently dropped.
+/// Works like try_write(), but returns the number of bytes actually
+/// buffered. If the TX buffer fills, remaining data is silently
+/// dropped.
 ///
 /// Non-blocking - returns immediately. Starts DMA if TX is idle.
 pub fn write(data: &[u8]) -> usize {
-    let was_enabled = interrupt::disable_irqs();
-    let mut written = 0;
+    match try_write(data) {
+        Ok(()) => data.len(),
+        Err(WriteError::Full(written)) => written,
+    }
+}
+
+/// Queue data for transmission via DMA, reporting backpressure.
+///
+/// Appends to the block left partially filled by the previous call, moving
+/// on to further blocks if data is large. A block becomes eligible for DMA
+/// when it fills (128 bytes by default), or when tick() finds DMA idle and
+/// hands off the partial block.
+///
+/// Returns Ok(()) if all of `data` was queued. If the TX buffer fills first,
+/// returns `Err(WriteError::Full(n))`, where `n` bytes were queued and the
+/// rest were not. Callers can call tick() and retry with `&data[n..]`.
+///
+/// Non-blocking - returns immediately. Starts DMA if TX is idle.
+pub fn try_write(data: &[u8]) -> Result<(), WriteError> {
+    let _cs = interrupt::critical_section();
+    let written;
     unsafe {
-        let mut block = TX_NEXT_BLOCK;
-        let mut offset: usize = 0;
+        // Append to the current block, marking blocks ready as they fill.
+        // Stops at a block that is still pending or in flight, which only
+        // happens when every block is in use (see `txblocks` for why it
+        // doesn't skip ahead).
+        let mut next_block = TX_NEXT_BLOCK;
+        let mut fill_len = TX_FILL_LEN;
+        let block_len = &raw mut TX_BLOCK_LEN;
+        written = txblocks::fill(
+            &mut (&mut *block_len)[..TX_BLOCKS],
+            TX_BLOCK_BYTES,
+            &mut next_block,
+            &mut fill_len,
+            data,
+            |offset, byte| {
+                ptr::write_volatile((IFRAM_TX_ADDR + offset) as *mut u8, byte)
+            },
+        );
+        TX_NEXT_BLOCK = next_block;
+        TX_FILL_LEN = fill_len;
+        // Ensure block state is visible to tick() before returning
+        core::sync::atomic::compiler_fence(
+            core::sync::atomic::Ordering::Release,
+        );
 
-        // Check if starting block has pending data
-        if TX_BLOCK_LEN[block] > 0 {
-            // Block is full and waiting to be sent, can't write
-            return 0;
+        // If TX is idle, start DMA for any ready blocks
+        if !TX_IN_FLIGHT {
+            tick();
         }
+    }
+    if written == data.len() { Ok(()) } else { Err(WriteError::Full(written)) }
+}
 
-        for &byte in data {
-            // Check if current block is full
-            if offset >= TX_BLOCK_SIZE {
-                // Mark block as ready and move to next
-                TX_BLOCK_LEN[block] = TX_BLOCK_SIZE as u8;
-                block = (block + 1) % TX_BLOCK_COUNT;
-                offset = 0;
-
-                // Check if next block is available (not pending or in-flight)
-                if TX_BLOCK_LEN[block] > 0 {
-                    // Block has pending data, buffer is full
-                    break;
-                }
+/// Return how many bytes write() could queue right now without dropping
+/// any.
+///
+/// Counts the free space left in the block being filled plus every empty
+/// block after it, stopping at the first block that is still queued or in
+/// flight, since write() stops there too. Free space can only grow until
+/// the next write().
+pub fn tx_free() -> usize {
+    let _cs = interrupt::critical_section();
+    let mut free = 0;
+    unsafe {
+        for k in 0..TX_BLOCKS {
+            let block = (TX_NEXT_BLOCK + k) % TX_BLOCKS;
+            if TX_BLOCK_LEN[block] > 0 {
+                break;
             }
+            let used = if k == 0 { TX_FILL_LEN } else { 0 };
+            free += TX_BLOCK_BYTES - used;
+        }
+    }
+    free
+}
+
+/// Return how many queued bytes have not finished sending yet.
+///
+/// Includes the partially filled block and the whole transfer that DMA is
+/// currently sending, since the hardware does not report how far into a
+/// transfer it has got.
+pub fn tx_pending() -> usize {
+    let _cs = interrupt::critical_section();
+    unsafe {
+        let blocks = &raw const TX_BLOCK_LEN;
+        let queued: usize = (*blocks).iter().map(|&len| len as usize).sum();
+        TX_FILL_LEN + queued - TX_HEAD_SENT
+    }
+}
+
+/// Queue all of `data` for transmission, blocking until it fits.
+///
+/// Calls write() for as much data as there is room for, then calls tick()
+/// until DMA frees up a block, and repeats until every byte is queued. This
+/// never drops data, but if TX is wedged it never returns either. Use
+/// write_all_timeout() when that matters.
+pub fn write_all(data: &[u8]) {
+    let mut offset = 0;
+    while offset < data.len() {
+        offset += write(&data[offset..]);
+        tick();
+    }
+}
 
-            // Write byte to current block
-            let addr = IFRAM_TX_ADDR + block * TX_BLOCK_SIZE + offset;
-            ptr::write_volatile(addr as *mut u8, byte);
-            offset += 1;
-            written += 1;
+/// Queue all of `data` for transmission, giving up after `timeout_ms`.
+///
+/// Works like write_all(), but stops waiting for free TX blocks once
+/// `timeout_ms` milliseconds (measured with `ticktimer::millis()`) have
+/// passed. Returns the number of bytes queued, which is less than
+/// `data.len()` if the timeout expired.
+pub fn write_all_timeout(data: &[u8], timeout_ms: u32) -> usize {
+    let start = ticktimer::millis();
+    let mut offset = 0;
+    while offset < data.len() {
+        offset += write(&data[offset..]);
+        if offset < data.len()
+            && ticktimer::elapsed_since(start) >= timeout_ms as u64
+        {
+            break;
         }
+        tick();
+    }
+    offset
+}
 
-        // Record how many bytes are in the current block
-        if offset > 0 {
-            TX_BLOCK_LEN[block] = offset as u8;
-            // Next write() will use a fresh block
-            TX_NEXT_BLOCK = (block + 1) % TX_BLOCK_COUNT;
-            // Ensure block state is visible to tick() before returning
-            core::sync::atomic::compiler_fence(
-                core::sync::atomic::Ordering::Release,
-            );
+/// Read one byte from RX if available.
+///
+/// Takes the oldest byte from the RX ring buffer. Returns Some(byte) if
+/// data is available, None otherwise. Non-blocking.
+pub fn getc() -> Option<u8> {
+    let _cs = interrupt::critical_section();
+    latch_rx_errors();
+    unsafe {
+        if RX_COUNT > 0 {
+            let byte = RX_RING[RX_TAIL];
+            RX_TAIL = (RX_TAIL + 1) % RX_RING_SIZE;
+            RX_COUNT -= 1;
+            update_rts();
+            Some(byte)
+        } else {
+            None
         }
+    }
+}
 
-        // If TX is idle, start DMA for any ready blocks
-        if !TX_IN_FLIGHT {
-            tick();
+/// Read one byte from RX, waiting up to `ms` milliseconds for it.
+///
+/// Calls getc() and tick() in a loop until a byte arrives or the deadline
+/// (measured with `ticktimer::millis()`) passes, so queued TX keeps moving
+/// while it waits. Returns None on timeout. With `ms` of 0 this is the same
+/// as getc(). Bytes reach the RX ring from the RX interrupt, so interrupts
+/// must be enabled while waiting.
+pub fn getc_timeout(ms: u32) -> Option<u8> {
+    let deadline = ticktimer::Deadline::after_ms(ms);
+    loop {
+        if let Some(byte) = getc() {
+            return Some(byte);
         }
+        if deadline.is_expired() {
+            return None;
+        }
+        tick();
     }
-    if was_enabled {
-        interrupt::enable_irqs();
+}
+
+/// Read a line of input into `buf`, blocking until it ends.
+///
+/// Collects bytes until a CR or LF arrives or `buf` fills, calling tick()
+/// while it waits. Returns the line length, not counting the terminator
+/// (which is not stored). A CR LF pair ends one line: the LF right after a
+/// CR is skipped, so the next call does not return an empty line.
+///
+/// Backspace (0x08) and DEL (0x7f) remove the last byte, if any. With
+/// `echo` set, received bytes are echoed back, backspace is echoed as
+/// backspace-space-backspace to erase the character on the terminal, and
+/// the terminator is echoed as CR LF.
+///
+/// ```ignore
+/// let mut line = [0u8; 80];
+/// loop {
+///     uart::write(b"> ");
+///     let n = uart::read_line(&mut line, true);
+///     // Handle the command in &line[..n]
+/// }
+/// ```
+pub fn read_line(buf: &mut [u8], echo: bool) -> usize {
+    let mut len = 0;
+    while len < buf.len() {
+        let Some(byte) = getc() else {
+            tick();
+            continue;
+        };
+        let after_cr =
+            unsafe { ptr::replace(&raw mut RX_LAST_CR, byte == b'\r') };
+        match byte {
+            b'\n' if after_cr => {}
+            b'\r' | b'\n' => {
+                if echo {
+                    write_all(b"\r\n");
+                }
+                return len;
+            }
+            0x08 | 0x7f => {
+                if len > 0 {
+                    len -= 1;
+                    if echo {
+                        write_all(b"\x08 \x08");
+                    }
+                }
+            }
+            _ => {
+                buf[len] = byte;
+                len += 1;
+                if echo {
+                    write_all(&[byte]);
+                }
+            }
+        }
     }
-    written
+    len
 }
 
-/// Read one byte from RX if available.
+/// Read as many bytes as are available into `buf`, without blocking.
 ///
-/// Directly polls the VALID register. Returns Some(byte) if data is
-/// available, None otherwise. Non-blocking.
+/// Takes the oldest bytes from the RX ring buffer, up to `buf.len()`, and
+/// returns the number of bytes read (0 if none are waiting).
+pub fn read(buf: &mut [u8]) -> usize {
+    let _cs = interrupt::critical_section();
+    latch_rx_errors();
+    let mut n = 0;
+    unsafe {
+        while n < buf.len() && RX_COUNT > 0 {
+            buf[n] = RX_RING[RX_TAIL];
+            RX_TAIL = (RX_TAIL + 1) % RX_RING_SIZE;
+            RX_COUNT -= 1;
+            n += 1;
+        }
+    }
+    update_rts();
+    n
+}
+
+/// Return the number of bytes waiting in the RX ring buffer.
 #[inline]
-pub fn getc() -> Option<u8> {
+pub fn rx_available() -> usize {
+    unsafe { ptr::read_volatile(&raw const RX_COUNT) }
+}
+
+/// Return true if RX bytes were dropped since the last call.
+///
+/// A byte is dropped when it arrives while the RX ring buffer is full. The
+/// bytes already in the ring are kept, so the newest data is what gets
+/// lost. Calling this clears the flag.
+pub fn rx_overrun() -> bool {
+    let _cs = interrupt::critical_section();
+    unsafe {
+        let overrun = RX_OVERRUN;
+        RX_OVERRUN = false;
+        overrun
+    }
+}
+
+/// Return the hardware RX errors seen since the last call.
+///
+/// Reports the ERROR register bits accumulated by the RX interrupt handler
+/// and getc(), then clears them. Check this after receiving a suspicious
+/// byte to see whether it arrived garbled. There is no framing error flag,
+/// and the software ring buffer overrun is reported by rx_overrun().
+pub fn rx_errors() -> RxErrors {
+    let _cs = interrupt::critical_section();
+    latch_rx_errors();
+    let bits = unsafe {
+        let bits = RX_ERROR_BITS;
+        RX_ERROR_BITS = 0;
+        bits
+    };
+    RxErrors {
+        overflow: bits & ERROR_RX_OVERFLOW != 0,
+        parity: bits & ERROR_RX_PARITY != 0,
+    }
+}
+
+/// Call `callback` with each received line ending in `delim`.
+///
+/// Switches RX from the ring buffer to line mode (see "Line Callback" in
+/// the module docs). The callback runs in interrupt context. Setting a new
+/// callback or delimiter keeps any partial line already received.
+pub fn set_line_callback(delim: u8, callback: fn(&[u8])) {
+    let _cs = interrupt::critical_section();
+    unsafe {
+        LINE_DELIM = delim;
+        LINE_CALLBACK = Some(callback);
+    }
+}
+
+/// Stop line mode and go back to buffering RX bytes in the ring.
+///
+/// Any partial line is discarded.
+pub fn clear_line_callback() {
+    let _cs = interrupt::critical_section();
+    unsafe {
+        LINE_CALLBACK = None;
+        LINE_LEN = 0;
+        LINE_LAST_CR = false;
+    }
+}
+
+// Drive RTS from the RX ring level, if flow control is enabled. Uses two
+// thresholds so RTS doesn't chatter while the level hovers near one.
+fn update_rts() {
+    unsafe {
+        if let Some(rts) = FLOW_RTS {
+            if RX_COUNT >= RTS_HIGH_WATER {
+                gpio::set(rts);
+            } else if RX_COUNT <= RTS_LOW_WATER {
+                gpio::clear(rts);
+            }
+        }
+    }
+}
+
+// Accumulate ERROR register bits, since reading the register clears them.
+// Call with interrupts disabled.
+fn latch_rx_errors() {
+    unsafe {
+        RX_ERROR_BITS |= mmio::read32(REG_ERROR);
+    }
+}
+
+// Check that no TX data is queued or in flight. TX_QUEUE_HEAD ==
+// TX_NEXT_BLOCK is not enough on its own, because that is also true when
+// every block is full. Call with interrupts disabled.
+fn tx_queue_empty() -> bool {
+    unsafe {
+        !TX_IN_FLIGHT && TX_BLOCK_LEN[TX_QUEUE_HEAD] == 0 && TX_FILL_LEN == 0
+    }
+}
+
+/// Handle UART2 RX interrupt.
+///
+/// Called by the trap handler when the UART2_RX_CHAR event fires. Moves
+/// every byte from the VALID/DATA registers into the RX ring buffer (or
+/// the line buffer, in line mode), then clears the IRQARRAY5 pending bit.
+pub(crate) fn handle_rx_interrupt() {
+    latch_rx_errors();
+    unsafe {
+        while (mmio::read32(REG_VALID) & VALID_DATA_AVAILABLE) != 0 {
+            let byte = mmio::read32(REG_DATA) as u8;
+            if let Some(callback) = LINE_CALLBACK {
+                line_byte(byte, callback);
+            } else if RX_COUNT < RX_RING_SIZE {
+                RX_RING[RX_HEAD] = byte;
+                RX_HEAD = (RX_HEAD + 1) % RX_RING_SIZE;
+                RX_COUNT += 1;
+            } else {
+                RX_OVERRUN = true;
+            }
+        }
+        update_rts();
+        IRQARRAY5.clear_pending(UART2_RX_CHAR_BIT);
+    }
+}
+
+// Add one byte to the line buffer, calling `callback` when the line is
+// complete. Called from the RX interrupt.
+fn line_byte(byte: u8, callback: fn(&[u8])) {
     unsafe {
-        if (ptr::read_volatile(REG_VALID) & VALID_DATA_AVAILABLE) != 0 {
-            Some(ptr::read_volatile(REG_DATA) as u8)
+        let delim = LINE_DELIM;
+        let after_cr = ptr::replace(&raw mut LINE_LAST_CR, byte == b'\r');
+        if byte == b'\n' && delim == b'\r' && after_cr {
+            // LF of a CR LF pair, after the line was already delivered
+            return;
+        }
+        if byte == delim {
+            let mut len = LINE_LEN;
+            if delim == b'\n' && len > 0 && LINE_BUF[len - 1] == b'\r' {
+                len -= 1;
+            }
+            LINE_LEN = 0;
+            let buf = &raw const LINE_BUF as *const u8;
+            callback(slice::from_raw_parts(buf, len));
+        } else if LINE_LEN < LINE_BUF_SIZE {
+            LINE_BUF[LINE_LEN] = byte;
+            LINE_LEN += 1;
         } else {
-            None
+            RX_OVERRUN = true;
         }
     }
 }
@@ -293,40 +1082,382 @@ pub fn getc() -> Option<u8> {
 /// Service TX DMA queue.
 ///
 /// Checks if the current DMA transfer is complete. If so, advances the
-/// queue head and starts DMA for the next ready block if available.
+/// queue head and starts DMA for the next ready block if available. A
+/// transfer that runs past the stall timeout is abandoned (see "TX Stall
+/// Recovery" in the module docs).
 ///
 /// Call periodically from the main event loop. Also called automatically
 /// by write() when needed.
 pub extern "C" fn tick() {
-    let was_enabled = interrupt::disable_irqs();
+    let cs = interrupt::critical_section();
+    let mut stalled = false;
     unsafe {
         // Ensure we see the latest DMA state
         core::sync::atomic::compiler_fence(
             core::sync::atomic::Ordering::Acquire,
         );
         // Check if current transfer is complete
-        let tx_saddr = ptr::read_volatile(REG_TX_SADDR);
+        let tx_saddr = mmio::read32(REG_TX_SADDR);
         if tx_saddr == 0 && TX_IN_FLIGHT {
-            // Transfer complete, mark this block as done
-            TX_BLOCK_LEN[TX_QUEUE_HEAD] = 0;
-            TX_QUEUE_HEAD = (TX_QUEUE_HEAD + 1) % TX_BLOCK_COUNT;
+            // Transfer complete. With flow control a block goes out in
+            // several chunks, so it is only done once all of it was sent.
+            TX_IN_FLIGHT = false;
+            TX_HEAD_SENT += TX_CHUNK_LEN;
+            if TX_HEAD_SENT >= TX_BLOCK_LEN[TX_QUEUE_HEAD] as usize {
+                TX_BLOCK_LEN[TX_QUEUE_HEAD] = 0;
+                TX_QUEUE_HEAD = (TX_QUEUE_HEAD + 1) % TX_BLOCKS;
+                TX_HEAD_SENT = 0;
+            }
+        } else if TX_IN_FLIGHT
+            && TX_STALL_TIMEOUT_MS > 0
+            && ticktimer::elapsed_since(TX_START_MS)
+                >= TX_STALL_TIMEOUT_MS as u64
+        {
+            // Stalled. Stop the channel and drop the rest of the block.
+            mmio::write32(REG_TX_CFG, CFG_CLR);
             TX_IN_FLIGHT = false;
+            TX_BLOCK_LEN[TX_QUEUE_HEAD] = 0;
+            TX_QUEUE_HEAD = (TX_QUEUE_HEAD + 1) % TX_BLOCKS;
+            TX_HEAD_SENT = 0;
+            TX_STALL_COUNT += 1;
+            stalled = true;
         }
 
-        // If idle, start DMA for next ready block
-        if !TX_IN_FLIGHT && TX_QUEUE_HEAD != TX_NEXT_BLOCK {
-            let len = TX_BLOCK_LEN[TX_QUEUE_HEAD];
+        // If idle with no ready blocks, hand off the partially filled block.
+        // Ready blocks run from TX_QUEUE_HEAD up to TX_NEXT_BLOCK, so an
+        // empty head block means the head is the block being filled.
+        if !TX_IN_FLIGHT && TX_BLOCK_LEN[TX_QUEUE_HEAD] == 0 && TX_FILL_LEN > 0
+        {
+            TX_BLOCK_LEN[TX_NEXT_BLOCK] = TX_FILL_LEN as u16;
+            TX_NEXT_BLOCK = (TX_NEXT_BLOCK + 1) % TX_BLOCKS;
+            TX_FILL_LEN = 0;
+        }
+
+        // If idle, start DMA for next ready block. This checks the block
+        // length rather than comparing TX_QUEUE_HEAD to TX_NEXT_BLOCK, since
+        // the two are also equal when every block is full. With flow
+        // control, wait for CTS and send the block in small chunks.
+        let cts = FLOW_CTS;
+        let clear_to_send = cts.is_none_or(gpio::is_low);
+        if !TX_IN_FLIGHT && clear_to_send {
+            let len = TX_BLOCK_LEN[TX_QUEUE_HEAD] as usize;
             if len > 0 {
-                let addr =
-                    (IFRAM_TX_ADDR + TX_QUEUE_HEAD * TX_BLOCK_SIZE) as u32;
-                ptr::write_volatile(REG_TX_SADDR, addr);
-                ptr::write_volatile(REG_TX_SIZE, len as u32);
-                ptr::write_volatile(REG_TX_CFG, CFG_EN);
+                let mut chunk = len - TX_HEAD_SENT;
+                if cts.is_some() {
+                    chunk = chunk.min(FLOW_TX_CHUNK);
+                }
+                let addr = IFRAM_TX_ADDR
+                    + TX_QUEUE_HEAD * TX_BLOCK_BYTES
+                    + TX_HEAD_SENT;
+                mmio::write32(REG_TX_SADDR, addr as u32);
+                mmio::write32(REG_TX_SIZE, chunk as u32);
+                mmio::write32(REG_TX_CFG, CFG_EN);
+                TX_CHUNK_LEN = chunk;
+                TX_START_MS = ticktimer::millis();
                 TX_IN_FLIGHT = true;
             }
         }
     }
-    if was_enabled {
-        interrupt::enable_irqs();
+    // Log after leaving the critical section, since logging writes to the
+    // queue that was just recovered
+    drop(cs);
+    if stalled {
+        crate::warn!("uart: TX DMA stalled, dropped a block\r\n");
+    }
+}
+
+/// Block until all queued TX data has been sent.
+///
+/// Calls tick() until the TX queue is empty and no DMA transfer is active,
+/// then waits for the UART to finish shifting out the last byte. Use this
+/// before a reset or a long stretch without calls to tick().
+pub fn flush() {
+    loop {
+        tick();
+        let _cs = interrupt::critical_section();
+        if tx_queue_empty() {
+            break;
+        }
+    }
+    unsafe { while mmio::read32(REG_STATUS) & STATUS_TX_BUSY != 0 {} }
+}
+
+/// Give the top 512 bytes of the TX area to the `serial` TX blocks.
+///
+/// Called when a serial port is set up. The first call flushes queued TX
+/// data, then drops blocks until the layout ends below
+/// `pac::IFRAM_SERIAL_TX`, so the default 16x128 becomes 12x128. Later
+/// calls do nothing.
+pub(crate) fn lend_tx_area_to_serial() {
+    let limit = pac::IFRAM_SERIAL_TX - IFRAM_TX_ADDR;
+    if unsafe { TX_AREA_LIMIT } == limit {
+        return;
+    }
+    loop {
+        flush();
+        let _cs = interrupt::critical_section();
+        // An interrupt handler may have queued more after flush()
+        if !tx_queue_empty() {
+            continue;
+        }
+        unsafe {
+            TX_AREA_LIMIT = limit;
+            if TX_BLOCKS * TX_BLOCK_BYTES > limit {
+                TX_BLOCKS = limit / TX_BLOCK_BYTES;
+                TX_NEXT_BLOCK = 0;
+                TX_QUEUE_HEAD = 0;
+            }
+        }
+        return;
+    }
+}
+
+/// Set how long a TX DMA transfer may run before tick() abandons it.
+///
+/// The default is 2000ms. Pass 0 to turn stall recovery off.
+pub fn set_tx_stall_timeout_ms(ms: u32) {
+    unsafe {
+        TX_STALL_TIMEOUT_MS = ms;
+    }
+}
+
+/// Return the number of stalled TX DMA transfers tick() has abandoned.
+///
+/// Counts from init(). A count that keeps rising points at a DMA or clock
+/// setup problem rather than a one-off glitch.
+pub fn tx_stall_count() -> u32 {
+    unsafe { TX_STALL_COUNT }
+}
+
+/// Send one byte, polling until the UART has sent it.
+///
+/// Bypasses the TX block queue (see "Polled TX" in the module docs), so it
+/// works even if the queue state is corrupt or interrupts are disabled.
+/// Waits for a DMA transfer that is already running to finish first, so
+/// queued output is not cut off in the middle of a block. If TX does not
+/// go idle within 10 ms (timed with the CPU cycle counter, which keeps
+/// counting with interrupts off), the byte is dropped rather than hanging.
+pub fn write_byte_blocking(byte: u8) {
+    let _cs = interrupt::critical_section();
+    let start = cycles::now();
+    let timeout = POLLED_TX_TIMEOUT_MS as u64 * clock::aclk_hz() as u64 / 1000;
+    let expired = || cycles::now().wrapping_sub(start) >= timeout;
+    let tx_idle = || unsafe {
+        mmio::read32(REG_TX_SADDR) == 0
+            && mmio::read32(REG_STATUS) & STATUS_TX_BUSY == 0
+    };
+    while !tx_idle() {
+        if expired() {
+            return;
+        }
+    }
+    unsafe {
+        ptr::write_volatile(IFRAM_POLLED_TX_ADDR as *mut u8, byte);
+        // Ensure the byte is in IFRAM before DMA reads it
+        core::sync::atomic::compiler_fence(
+            core::sync::atomic::Ordering::SeqCst,
+        );
+        mmio::write32(REG_TX_SADDR, IFRAM_POLLED_TX_ADDR as u32);
+        mmio::write32(REG_TX_SIZE, 1);
+        mmio::write32(REG_TX_CFG, CFG_EN);
+    }
+    while !tx_idle() {
+        if expired() {
+            return;
+        }
+    }
+}
+
+/// Send `data` by polling, bypassing the TX block queue.
+///
+/// Calls write_byte_blocking() for each byte, so it blocks until all of
+/// `data` is sent and is much slower than write(). It doesn't touch the
+/// queue state, so it is safe to call from interrupt context or a panic
+/// even if the interrupted code was using write() (see "Polled TX" in the
+/// module docs).
+pub fn write_polled(data: &[u8]) {
+    for &byte in data {
+        write_byte_blocking(byte);
+    }
+}
+
+/// Enable software RTS/CTS flow control on two GPIO pins.
+///
+/// UART_SETUP has no flow control bits, so this is done in software (see
+/// "Flow Control" in the module docs). `rts` becomes a GPIO output that
+/// this driver holds low while there is room in the RX ring. `cts` becomes
+/// a GPIO input with pull-up, and TX DMA only starts while it reads low.
+/// Any pins of ports B and C can be used.
+pub fn enable_flow_control(rts: GpioPin, cts: GpioPin) {
+    gpio::set_alternate_function(cts, AF::AF0);
+    gpio::disable_output(cts);
+    gpio::enable_pullup(cts);
+    gpio::set_alternate_function(rts, AF::AF0);
+    let _cs = interrupt::critical_section();
+    unsafe {
+        FLOW_RTS = Some(rts);
+        FLOW_CTS = Some(cts);
+        // Start deasserted, then let the RX level decide
+        gpio::set(rts);
+        gpio::enable_output(rts);
+        update_rts();
+    }
+}
+
+/// Disable flow control, leaving the RTS pin driven low (asserted).
+pub fn disable_flow_control() {
+    let _cs = interrupt::critical_section();
+    unsafe {
+        if let Some(rts) = FLOW_RTS {
+            gpio::clear(rts);
+        }
+        FLOW_RTS = None;
+        FLOW_CTS = None;
+    }
+}
+
+/// Change the baud rate, keeping the frame format and buffered data.
+///
+/// Waits for all queued TX data to finish sending (like flush()), then
+/// rewrites only the divisor field of UART_SETUP, so no byte is cut off
+/// in the middle. The TX and RX buffers and the enable and frame format
+/// bits are left as they are. The divisor is `PERCLK / baud`, limited to
+/// the 16-bit field. A later init() goes back to 1 Mbps.
+///
+/// Bytes that arrive while the rate is changing may be garbled, so switch
+/// at a point where the other side is quiet (e.g. right after it
+/// acknowledges the new rate).
+pub fn set_baud(baud: u32) {
+    flush();
+    let divisor = (clock::perclk_hz() / baud.max(1)).clamp(1, 0xffff);
+    let _cs = interrupt::critical_section();
+    unsafe {
+        mmio::modify32(
+            REG_UART_SETUP,
+            UART_DIVISOR_MASK,
+            divisor << UART_DIVISOR_SHIFT,
+        );
+    }
+}
+
+// Put UART2 into a known idle state before init applies its setup value,
+// so cold and warm starts behave the same (see "Reset Sequence")
+fn reset() {
+    unsafe {
+        // 1. Stop interrupts first, so the RX handler can't run mid-reset
+        mmio::write32(REG_IRQ_EN, 0);
+        IRQARRAY5.disable(UART2_RX_CHAR_BIT);
+        // 2. Disable TX and RX, and flush the RX FIFO
+        mmio::write32(REG_UART_SETUP, UART_RX_CLEAN_FIFO);
+        // 3. Stop both DMA channels and zero the TX transfer registers
+        mmio::write32(REG_TX_CFG, CFG_CLR);
+        mmio::write32(REG_RX_CFG, CFG_CLR);
+        mmio::write32(REG_TX_SADDR, 0);
+        mmio::write32(REG_TX_SIZE, 0);
+        // 4. Drop any stale error flags and pending RX event
+        mmio::read32(REG_ERROR);
+        IRQARRAY5.clear_pending(UART2_RX_CHAR_BIT);
+    }
+}
+
+// Claim RX_PIN and TX_PIN, unless this driver already holds them
+fn claim_pins() {
+    unsafe {
+        let tokens = &raw mut PIN_TOKENS;
+        if (*tokens).is_some() {
+            return;
+        }
+        let rx = gpio::try_claim(RX_PIN);
+        let tx = gpio::try_claim(TX_PIN);
+        debug_assert!(
+            rx.is_some() && tx.is_some(),
+            "uart: PB13/PB14 already claimed"
+        );
+        match (rx, tx) {
+            (Some(rx), Some(tx)) => *tokens = Some([rx, tx]),
+            // Give back a lone claim so the pin isn't left with no owner
+            (Some(rx), None) => gpio::release(rx),
+            (None, Some(tx)) => gpio::release(tx),
+            (None, None) => {}
+        }
+    }
+}
+
+// Release RX_PIN and TX_PIN, returning true if this driver held them
+fn release_pins() -> bool {
+    unsafe {
+        match ptr::replace(&raw mut PIN_TOKENS, None) {
+            Some([rx, tx]) => {
+                gpio::release(rx);
+                gpio::release(tx);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Send a break condition by holding TX low for `duration_ms`.
+///
+/// Waits for queued TX data to finish sending, drives PB14 low as a GPIO
+/// output for `duration_ms` milliseconds (using `crate::sleep`), then
+/// returns PB14 to UART2 TX (AF1). Blocks for the whole time.
+pub fn send_break(duration_ms: u32) {
+    let tx_pin = TX_PIN;
+    flush();
+    // Release the pin claims while PB14 is a GPIO
+    let claimed = release_pins();
+
+    // Drive low before switching to GPIO so the line does not glitch high
+    gpio::clear(tx_pin);
+    gpio::enable_output(tx_pin);
+    gpio::set_alternate_function(tx_pin, AF::AF0);
+
+    crate::sleep(duration_ms);
+
+    gpio::set_alternate_function(tx_pin, AF::AF1);
+    gpio::disable_output(tx_pin);
+    if claimed {
+        claim_pins();
+    }
+}
+
+// ============================================================================
+// Formatted Output
+// ============================================================================
+
+/// Zero-sized UART2 handle for use with `write!` and `writeln!`.
+///
+/// ```ignore
+/// use core::fmt::Write;
+///
+/// let mut u = uart::Uart;
+/// write!(u, "val={}\r\n", 42).ok();
+/// ```
+///
+/// Each `write_str()` call made by the format machinery is a separate
+/// try_write(). If the TX buffer fills, the rest of the string is dropped
+/// and `write_str()` returns
+/// `Err(fmt::Error)`, which stops the formatting early instead of
+/// spinning.
+pub struct Uart;
+
+impl fmt::Write for Uart {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        try_write(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
+
+/// Zero-sized UART2 handle that writes with write_byte_blocking().
+///
+/// For the panic handler and other paths where the TX queue or interrupts
+/// can't be trusted. Each byte is a separate polled DMA transfer, so this
+/// is much slower than Uart.
+pub struct PolledUart;
+
+impl fmt::Write for PolledUart {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        write_polled(s.as_bytes());
+        Ok(())
     }
 }
@@ -7,27 +7,23 @@
 //! controller. Implementation follows a phased approach starting with basic
 //! hardware detection and progressing toward full CDC-ACM serial support.
 
+use crate::irqarray::IRQARRAY1;
+use crate::mmio;
+use crate::pac;
 use core::ptr;
 
 // ============================================================================
-// IRQARRAY1 Register Addresses
+// IRQARRAY1 Events
 // ============================================================================
 
-const IRQARRAY1_EV_SOFT: *mut u32 = 0xe0005000 as *mut u32;
-//const IRQARRAY1_EV_EDGE_TRIGGERED: *mut u32 = 0xe0005004 as *mut u32;
-//const IRQARRAY1_EV_POLARITY: *mut u32 = 0xe0005008 as *mut u32;
-//const IRQARRAY1_EV_STATUS: *const u32 = 0xe000500c as *const u32;
-const IRQARRAY1_EV_PENDING: *mut u32 = 0xe0005010 as *mut u32;
-//const IRQARRAY1_EV_ENABLE: *mut u32 = 0xe0005014 as *mut u32;
-
 // Bit mask for USB controller in IRQARRAY1
-const USBC_BIT: u32 = 1 << 0;
+const USBC_BIT: u32 = pac::IRQARRAY1_USBC;
 
 // ============================================================================
 // Corigine USB Controller Register Addresses
 // ============================================================================
 
-const CORIGINE_DEVCAP: *const u32 = 0x5020_2400 as *const u32;
+const CORIGINE_DEVCAP: usize = pac::USB_CORIGINE_DEVCAP;
 //const CORIGINE_DEVCONFIG: *const u32 = 0x5020_2410 as *const u32;
 //const CORIGINE_USBCMD: *mut u32 = 0x5020_2420 as *mut u32;
 //const CORIGINE_USBSTS: *const u32 = 0x5020_2424 as *const u32;
@@ -49,105 +45,634 @@ const CORIGINE_DEVCAP: *const u32 = 0x5020_2400 as *const u32;
 /// 4. Logs detailed results for debugging
 #[allow(dead_code)]
 pub fn pending_write_test() {
-    unsafe {
-        crate::log!("IRQARRAY1 Writability Test\r\n");
+    crate::log!("IRQARRAY1 Writability Test\r\n");
 
-        // Use software trigger to set the pending bit
-        crate::log!("  Setting USBC_BIT via EV_SOFT...\r\n");
-        ptr::write_volatile(IRQARRAY1_EV_SOFT, USBC_BIT);
-        crate::sleep(1);
+    // Use software trigger to set the pending bit
+    crate::log!("  Setting USBC_BIT via EV_SOFT...\r\n");
+    IRQARRAY1.soft_trigger(USBC_BIT);
+    crate::sleep(1);
 
-        // Read pending to verify bit is set
-        let pending_after_set = ptr::read_volatile(IRQARRAY1_EV_PENDING);
-        crate::log!(
-            "  After EV_SOFT write, EV_PENDING = 0x{:08x}\r\n",
-            pending_after_set
-        );
+    // Read pending to verify bit is set
+    let pending_after_set = IRQARRAY1.pending();
+    crate::log!(
+        "  After EV_SOFT write, EV_PENDING = 0x{:08x}\r\n",
+        pending_after_set
+    );
 
-        if (pending_after_set & USBC_BIT) == 0 {
-            crate::log!("  ERROR: EV_SOFT didn't set pending bit!\r\n");
-            return;
-        }
+    if (pending_after_set & USBC_BIT) == 0 {
+        crate::log!("  ERROR: EV_SOFT didn't set pending bit!\r\n");
+        return;
+    }
 
-        // Attempt to clear by writing 1 (test RW1C semantics)
-        crate::log!("  Attempting to clear by writing 1 to bit...\r\n");
-        ptr::write_volatile(IRQARRAY1_EV_PENDING, USBC_BIT);
-        crate::sleep(1);
+    // Attempt to clear by writing 1 (test RW1C semantics)
+    crate::log!("  Attempting to clear by writing 1 to bit...\r\n");
+    IRQARRAY1.clear_pending(USBC_BIT);
+    crate::sleep(1);
 
-        // Check whether the bit was cleared
-        let pending_after_clear = ptr::read_volatile(IRQARRAY1_EV_PENDING);
+    // Check whether the bit was cleared
+    let pending_after_clear = IRQARRAY1.pending();
+    crate::log!(
+        "  After write, EV_PENDING = 0x{:08x}\r\n",
+        pending_after_clear
+    );
+
+    // Report results
+    if (pending_after_clear & USBC_BIT) == 0 {
+        crate::log!("  SUCCESS: Bit was cleared by write\r\n");
+    } else {
+        crate::log!("  WARNING: Bit was NOT cleared by write\r\n");
         crate::log!(
-            "  After write, EV_PENDING = 0x{:08x}\r\n",
-            pending_after_clear
+            "  Clearing may require different mechanism (e.g., peripheral action)\r\n"
         );
+    }
 
-        // Report results
-        if (pending_after_clear & USBC_BIT) == 0 {
-            crate::log!("  SUCCESS: Bit was cleared by write\r\n");
-        } else {
-            crate::log!("  WARNING: Bit was NOT cleared by write\r\n");
-            crate::log!(
-                "  Clearing may require different mechanism (e.g., peripheral action)\r\n"
-            );
-        }
-
-        // Clean up state
-        crate::log!("  Clearing EV_SOFT...\r\n");
-        ptr::write_volatile(IRQARRAY1_EV_SOFT, 0);
-        crate::sleep(1);
+    // Clean up state
+    crate::log!("  Clearing EV_SOFT...\r\n");
+    IRQARRAY1.soft_release(USBC_BIT);
+    crate::sleep(1);
 
-        let final_pending = ptr::read_volatile(IRQARRAY1_EV_PENDING);
-        crate::log!("  Final EV_PENDING = 0x{:08x}\r\n", final_pending);
-    }
+    let final_pending = IRQARRAY1.pending();
+    crate::log!("  Final EV_PENDING = 0x{:08x}\r\n", final_pending);
 }
 
 // ============================================================================
 // Phase 1: USB Controller Detection (CONFIRMED)
 // ============================================================================
 
+/// USB controller capabilities decoded from DEVCAP.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeviceCaps {
+    /// Controller version, bits [7:0]
+    pub version: u8,
+    /// Number of IN endpoints, bits [11:8]
+    pub in_endpoints: u8,
+    /// Number of OUT endpoints, bits [15:12]
+    pub out_endpoints: u8,
+    /// Number of event ring interrupters, bits [25:16]
+    pub interrupters: u16,
+    /// SuperSpeed (USB 3 Gen 1) support, bit 27
+    pub superspeed: bool,
+    /// SuperSpeed Plus (USB 3 Gen 2) support, bit 28
+    pub superspeed_plus: bool,
+    /// Isochronous transfer support, bit 29
+    pub isochronous: bool,
+}
+
+/// Reasons detect() can reject the DEVCAP register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DetectError {
+    /// DEVCAP read as all ones: nothing is driving the bus
+    Floating,
+    /// DEVCAP read as zero: the controller is powered off or held in reset
+    PoweredOff,
+    /// The controller version is not the one this driver was written for
+    UnexpectedVersion(u8),
+}
+
+// DEVCAP field layout
+const DEVCAP_VERSION_MASK: u32 = 0xff;
+const DEVCAP_EP_IN_SHIFT: u32 = 8;
+const DEVCAP_EP_OUT_SHIFT: u32 = 12;
+const DEVCAP_EP_MASK: u32 = 0xf;
+const DEVCAP_INTS_SHIFT: u32 = 16;
+const DEVCAP_INTS_MASK: u32 = 0x3ff;
+const DEVCAP_GEN1: u32 = 1 << 27;
+const DEVCAP_GEN2: u32 = 1 << 28;
+const DEVCAP_ISOCH: u32 = 1 << 29;
+
+// Controller version seen on hardware (DEVCAP = 0x20014401)
+const EXPECTED_VERSION: u8 = 0x01;
+
+impl DeviceCaps {
+    /// Decode the fields of a DEVCAP register value.
+    pub fn from_devcap(devcap: u32) -> Self {
+        DeviceCaps {
+            version: (devcap & DEVCAP_VERSION_MASK) as u8,
+            in_endpoints: ((devcap >> DEVCAP_EP_IN_SHIFT) & DEVCAP_EP_MASK)
+                as u8,
+            out_endpoints: ((devcap >> DEVCAP_EP_OUT_SHIFT) & DEVCAP_EP_MASK)
+                as u8,
+            interrupters: ((devcap >> DEVCAP_INTS_SHIFT) & DEVCAP_INTS_MASK)
+                as u16,
+            superspeed: devcap & DEVCAP_GEN1 != 0,
+            superspeed_plus: devcap & DEVCAP_GEN2 != 0,
+            isochronous: devcap & DEVCAP_ISOCH != 0,
+        }
+    }
+}
+
 /// Detect if USB controller is present and accessible.
 ///
 /// TESTED AND CONFIRMED: Reads DEVCAP register successfully from hardware.
 /// The controller responds with valid capability register value (0x20014401).
 /// This confirms the USB controller is present, clocked, and accessible via MMIO.
 ///
-/// Returns true if DEVCAP is not 0xffffffff (indicating valid response).
-/// A return value of false would indicate the controller is not responding
-/// or not clocked, suggesting initialization or hardware issues.
-pub fn detect() -> bool {
-    unsafe {
-        let devcap = ptr::read_volatile(CORIGINE_DEVCAP);
-        crate::log!("USB DEVCAP = 0x{:08x}\r\n", devcap);
-        // TODO: Validate DEVCAP version and features
-        devcap != 0xffffffff // Basic sanity check
+/// Decodes and logs the DEVCAP fields. On the hardware value above, that
+/// is version 0x01, 4 IN and 4 OUT endpoints, 1 interrupter, no
+/// SuperSpeed (so the controller runs at USB 2.0 high/full speed), and
+/// isochronous support. The field layout follows the Corigine controller
+/// as used on this chip and is consistent with that value.
+///
+/// Returns an error if DEVCAP reads as all ones (floating bus), zero
+/// (controller powered off), or reports a version other than 0x01.
+pub fn detect() -> Result<DeviceCaps, DetectError> {
+    let devcap = unsafe { mmio::read32(CORIGINE_DEVCAP) };
+    crate::log!("USB DEVCAP = 0x{:08x}\r\n", devcap);
+    match devcap {
+        0xffffffff => return Err(DetectError::Floating),
+        0 => return Err(DetectError::PoweredOff),
+        _ => {}
+    }
+    let caps = DeviceCaps::from_devcap(devcap);
+    crate::log!("  version:       0x{:02x}\r\n", caps.version);
+    crate::log!("  IN endpoints:  {}\r\n", caps.in_endpoints);
+    crate::log!("  OUT endpoints: {}\r\n", caps.out_endpoints);
+    crate::log!("  interrupters:  {}\r\n", caps.interrupters);
+    crate::log!("  SuperSpeed:    {}\r\n", caps.superspeed);
+    crate::log!("  SuperSpeed+:   {}\r\n", caps.superspeed_plus);
+    crate::log!("  isochronous:   {}\r\n", caps.isochronous);
+    if caps.version != EXPECTED_VERSION {
+        return Err(DetectError::UnexpectedVersion(caps.version));
     }
+    Ok(caps)
 }
 
 // ============================================================================
-// Phase 2: Minimal Enumeration Setup (Stub)
+// Phase 2: CDC-ACM Descriptors and EP0 Control Requests
 // ============================================================================
+//
+// STATUS: Descriptor tables and the control request state machine are
+// done. They do not depend on the controller, so they take SETUP packets
+// and OUT data stages as arguments and return what EP0 should do next.
+//
+// NOT DONE: the controller layer that moves packets on EP0 and the bulk
+// endpoints. The Corigine controller uses xHCI-style device contexts,
+// transfer rings, and an event ring, and the register and TRB layouts
+// beyond DEVCAP are not in the register descriptions this SDK is built
+// from. Until that layer exists the device does not attach to the bus, so
+// there is no CDC-ACM serial port yet. The state machine can still be
+// driven by hand (see examples/usb_acm.rs).
+//
+// Endpoint plan (the controller supports 4 endpoints, including EP0):
+// - EP0: control
+// - EP1 IN (0x81): CDC notifications (interrupt, 8 bytes)
+// - EP2 OUT (0x02): CDC data from host (bulk)
+// - EP2 IN (0x82): CDC data to host (bulk)
+
+// Openmoko VID/PID assigned to the Dabao board (see README)
+const USB_VID: u16 = 0x1d50;
+const USB_PID: u16 = 0x6197;
+
+// Bulk endpoint max packet sizes by bus speed
+const BULK_MPS_FS: u16 = 64;
+const BULK_MPS_HS: u16 = 512;
+
+const EP0_MPS: u8 = 64;
+const CONFIG_VALUE: u8 = 1;
+const CONFIG_DESCRIPTOR_LEN: usize = 67;
+
+// Descriptor types
+const DESC_DEVICE: u8 = 1;
+const DESC_CONFIGURATION: u8 = 2;
+const DESC_STRING: u8 = 3;
+const DESC_DEVICE_QUALIFIER: u8 = 6;
+
+// Standard requests
+const REQ_GET_STATUS: u8 = 0x00;
+const REQ_CLEAR_FEATURE: u8 = 0x01;
+const REQ_SET_FEATURE: u8 = 0x03;
+const REQ_SET_ADDRESS: u8 = 0x05;
+const REQ_GET_DESCRIPTOR: u8 = 0x06;
+const REQ_GET_CONFIGURATION: u8 = 0x08;
+const REQ_SET_CONFIGURATION: u8 = 0x09;
+const REQ_GET_INTERFACE: u8 = 0x0a;
+const REQ_SET_INTERFACE: u8 = 0x0b;
+
+// CDC-ACM class requests
+const REQ_SET_LINE_CODING: u8 = 0x20;
+const REQ_GET_LINE_CODING: u8 = 0x21;
+const REQ_SET_CONTROL_LINE_STATE: u8 = 0x22;
+const REQ_SEND_BREAK: u8 = 0x23;
+
+// bmRequestType type field (bits [6:5])
+const REQ_TYPE_MASK: u8 = 0x60;
+const REQ_TYPE_STANDARD: u8 = 0x00;
+const REQ_TYPE_CLASS: u8 = 0x20;
+
+// SET_CONTROL_LINE_STATE bits
+const CONTROL_LINE_DTR: u16 = 1 << 0;
+
+const DEVICE_DESCRIPTOR: [u8; 18] = [
+    18,          // bLength
+    DESC_DEVICE, // bDescriptorType
+    0x00,
+    0x02, // bcdUSB 2.00
+    0x02, // bDeviceClass: CDC
+    0x00, // bDeviceSubClass
+    0x00, // bDeviceProtocol
+    EP0_MPS,
+    USB_VID as u8,
+    (USB_VID >> 8) as u8,
+    USB_PID as u8,
+    (USB_PID >> 8) as u8,
+    0x00,
+    0x01, // bcdDevice 1.00
+    1,    // iManufacturer
+    2,    // iProduct
+    0,    // iSerialNumber
+    1,    // bNumConfigurations
+];
+
+// Device qualifier, for high speed capable devices
+const DEVICE_QUALIFIER: [u8; 10] = [
+    10,
+    DESC_DEVICE_QUALIFIER,
+    0x00,
+    0x02, // bcdUSB 2.00
+    0x02, // bDeviceClass: CDC
+    0x00,
+    0x00,
+    EP0_MPS,
+    1, // bNumConfigurations
+    0, // bReserved
+];
+
+// Configuration descriptor with both CDC-ACM interfaces. The bulk max
+// packet size depends on the bus speed, so this is built on request.
+#[rustfmt::skip]
+const fn config_descriptor(bulk_mps: u16) -> [u8; CONFIG_DESCRIPTOR_LEN] {
+    let lo = bulk_mps as u8;
+    let hi = (bulk_mps >> 8) as u8;
+    [
+        // Configuration: 2 interfaces, bus powered, 100 mA
+        9, DESC_CONFIGURATION, CONFIG_DESCRIPTOR_LEN as u8, 0,
+        2, CONFIG_VALUE, 0, 0x80, 50,
+        // Interface 0: CDC communication, ACM subclass, AT commands
+        9, 4, 0, 0, 1, 0x02, 0x02, 0x01, 0,
+        // CDC header functional descriptor (CDC 1.10)
+        5, 0x24, 0x00, 0x10, 0x01,
+        // Call management: no call management, data interface 1
+        5, 0x24, 0x01, 0x00, 1,
+        // ACM: supports line coding and control line state requests
+        4, 0x24, 0x02, 0x02,
+        // Union: control interface 0, data interface 1
+        5, 0x24, 0x06, 0, 1,
+        // EP1 IN: interrupt, 8 bytes, polling interval 9 (ms at FS)
+        7, 5, 0x81, 0x03, 8, 0, 9,
+        // Interface 1: CDC data
+        9, 4, 1, 0, 2, 0x0a, 0x00, 0x00, 0,
+        // EP2 OUT: bulk
+        7, 5, 0x02, 0x02, lo, hi, 0,
+        // EP2 IN: bulk
+        7, 5, 0x82, 0x02, lo, hi, 0,
+    ]
+}
+
+// String descriptor 0 (language IDs) and the strings it indexes
+const STRING_LANGIDS: [u8; 4] = [4, DESC_STRING, 0x09, 0x04]; // en-US
+const STRING_MANUFACTURER: &str = "Baochip";
+const STRING_PRODUCT: &str = "Dabao";
+
+/// Size of the EP0 data stage buffer passed to handle_setup()
+pub const EP0_BUF_SIZE: usize = 128;
+
+/// An 8-byte USB SETUP packet, as received on EP0.
+#[derive(Clone, Copy, Debug)]
+pub struct SetupPacket {
+    pub request_type: u8,
+    pub request: u8,
+    pub value: u16,
+    pub index: u16,
+    pub length: u16,
+}
+
+impl SetupPacket {
+    /// Parse a SETUP packet from its wire format (little-endian fields).
+    pub fn from_bytes(b: &[u8; 8]) -> Self {
+        SetupPacket {
+            request_type: b[0],
+            request: b[1],
+            value: u16::from_le_bytes([b[2], b[3]]),
+            index: u16::from_le_bytes([b[4], b[5]]),
+            length: u16::from_le_bytes([b[6], b[7]]),
+        }
+    }
+}
+
+/// CDC-ACM line coding, as set by the host.
+///
+/// The USB link runs at bus speed regardless of these values. They are
+/// stored so GET_LINE_CODING can echo them back, which some terminal
+/// programs check, and so a USB-to-UART bridge can match a real UART to
+/// them (see set_line_coding_callback()).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineCoding {
+    pub baud: u32,
+    pub stop_bits: u8, // 0 = 1, 1 = 1.5, 2 = 2
+    pub parity: u8,    // 0 = none, 1 = odd, 2 = even, 3 = mark, 4 = space
+    pub data_bits: u8,
+}
+
+impl LineCoding {
+    fn to_bytes(self) -> [u8; 7] {
+        let b = self.baud.to_le_bytes();
+        [b[0], b[1], b[2], b[3], self.stop_bits, self.parity, self.data_bits]
+    }
+}
+
+/// What EP0 should do next, as returned by handle_setup()
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControlResponse {
+    /// Send the first n bytes of the EP0 buffer as the IN data stage
+    Data(usize),
+    /// Receive an OUT data stage of n bytes, then call handle_control_out()
+    Receive(usize),
+    /// No data stage, just complete the status stage
+    Ack,
+    /// Stall EP0 (unsupported request)
+    Stall,
+}
+
+// Device state, following the USB 2.0 chapter 9 state machine
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DeviceState {
+    Default,
+    Address,
+    Configured,
+}
 
-/// Initialize USB controller for basic enumeration.
+static mut DEVICE_STATE: DeviceState = DeviceState::Default;
+static mut HIGH_SPEED: bool = false;
+static mut PENDING_ADDRESS: Option<u8> = None;
+static mut PENDING_OUT_REQUEST: u8 = 0;
+static mut DTR: bool = false;
+static mut LINE_CODING: LineCoding =
+    LineCoding { baud: 115_200, stop_bits: 0, parity: 0, data_bits: 8 };
+static mut LINE_CODING_CALLBACK: Option<fn(LineCoding)> = None;
+
+/// Return the device state machine to the default state after a bus reset.
 ///
-/// Sets up Device Context, Event Ring, and EP0 configuration.
+/// `high_speed` selects the bulk endpoint packet size reported in the
+/// configuration descriptor (512 bytes for high speed, 64 for full speed).
+/// The line coding and its callback are kept.
+pub fn bus_reset(high_speed: bool) {
+    let _cs = crate::interrupt::critical_section();
+    unsafe {
+        DEVICE_STATE = DeviceState::Default;
+        HIGH_SPEED = high_speed;
+        PENDING_ADDRESS = None;
+        PENDING_OUT_REQUEST = 0;
+        DTR = false;
+    }
+}
+
+/// Return true once the host has selected the CDC-ACM configuration.
+pub fn is_configured() -> bool {
+    let state = unsafe { ptr::read_volatile(&raw const DEVICE_STATE) };
+    state == DeviceState::Configured
+}
+
+/// Return true if the host has raised DTR (a terminal has the port open).
+pub fn dtr() -> bool {
+    unsafe { ptr::read_volatile(&raw const DTR) }
+}
+
+/// Return the line coding most recently set by the host.
+pub fn line_coding() -> LineCoding {
+    let _cs = crate::interrupt::critical_section();
+    unsafe { LINE_CODING }
+}
+
+/// Call `callback` each time the host sends SET_LINE_CODING.
 ///
-/// # Currently:
-/// This is a placeholder for Phase 2 implementation.
-pub fn init() {
-    crate::log!("USB init (stub)\r\n");
-    // TODO: Phase 2 implementation
+/// Without a callback the port is purely virtual: the line coding is
+/// stored for line_coding() and GET_LINE_CODING, and nothing else changes.
+/// A bridge to a real UART can use the callback to follow the baud rate
+/// the host asks for.
+///
+/// NOTE: this doesn't work on hardware yet. The callback is called from
+/// handle_control_out(), and until the controller layer exists (see the
+/// Phase 2 notes) nothing calls that except code feeding requests by hand,
+/// as examples/usb_acm.rs does.
+///
+/// The controller layer will call it in interrupt context, and hosts often
+/// repeat the same line coding when a port is opened, so record the new
+/// value and apply it from the main loop (uart::set_baud() waits for TX to
+/// drain, so it must not be called from the callback):
+///
+/// ```ignore
+/// static mut NEW_BAUD: u32 = 0;
+///
+/// fn on_line_coding(coding: usb::LineCoding) {
+///     unsafe { NEW_BAUD = coding.baud };
+/// }
+///
+/// usb::set_line_coding_callback(on_line_coding);
+/// ```
+pub fn set_line_coding_callback(callback: fn(LineCoding)) {
+    let _cs = crate::interrupt::critical_section();
+    unsafe {
+        LINE_CODING_CALLBACK = Some(callback);
+    }
+}
+
+/// Stop calling the line coding callback. line_coding() keeps working.
+pub fn clear_line_coding_callback() {
+    let _cs = crate::interrupt::critical_section();
+    unsafe {
+        LINE_CODING_CALLBACK = None;
+    }
+}
+
+/// Handle a SETUP packet on EP0.
+///
+/// Writes any IN data stage into `buf` and returns what EP0 should do
+/// next. Data stages are clamped to `setup.length`, as the host expects.
+pub fn handle_setup(
+    setup: &SetupPacket,
+    buf: &mut [u8; EP0_BUF_SIZE],
+) -> ControlResponse {
+    let _cs = crate::interrupt::critical_section();
+    match setup.request_type & REQ_TYPE_MASK {
+        REQ_TYPE_STANDARD => handle_standard_request(setup, buf),
+        REQ_TYPE_CLASS => handle_class_request(setup, buf),
+        _ => ControlResponse::Stall,
+    }
+}
+
+/// Handle the OUT data stage that handle_setup() asked for with
+/// `ControlResponse::Receive`. The status stage is then an ACK.
+pub fn handle_control_out(data: &[u8]) {
+    let callback = {
+        let _cs = crate::interrupt::critical_section();
+        unsafe {
+            let request = PENDING_OUT_REQUEST;
+            PENDING_OUT_REQUEST = 0;
+            if request != REQ_SET_LINE_CODING || data.len() < 7 {
+                return;
+            }
+            LINE_CODING = LineCoding {
+                baud: u32::from_le_bytes([data[0], data[1], data[2], data[3]]),
+                stop_bits: data[4],
+                parity: data[5],
+                data_bits: data[6],
+            };
+            LINE_CODING_CALLBACK
+        }
+    };
+    if let Some(callback) = callback {
+        callback(line_coding());
+    }
+}
+
+/// Finish the status stage of a control request.
+///
+/// Returns the device address to program if the request was SET_ADDRESS,
+/// which only takes effect once its status stage is done.
+pub fn status_stage_done() -> Option<u8> {
+    let _cs = crate::interrupt::critical_section();
+    unsafe {
+        let pending = ptr::replace(&raw mut PENDING_ADDRESS, None);
+        if pending.is_some() {
+            DEVICE_STATE = DeviceState::Address;
+        }
+        pending
+    }
+}
+
+fn handle_standard_request(
+    setup: &SetupPacket,
+    buf: &mut [u8; EP0_BUF_SIZE],
+) -> ControlResponse {
+    unsafe {
+        match setup.request {
+            REQ_GET_STATUS => {
+                buf[0] = 0;
+                buf[1] = 0;
+                data_stage(setup, 2)
+            }
+            REQ_CLEAR_FEATURE | REQ_SET_FEATURE => ControlResponse::Ack,
+            REQ_SET_ADDRESS => {
+                // The new address takes effect after the status stage
+                PENDING_ADDRESS = Some((setup.value & 0x7f) as u8);
+                ControlResponse::Ack
+            }
+            REQ_GET_DESCRIPTOR => get_descriptor(setup, buf),
+            REQ_GET_CONFIGURATION => {
+                let configured = DEVICE_STATE == DeviceState::Configured;
+                buf[0] = if configured { CONFIG_VALUE } else { 0 };
+                data_stage(setup, 1)
+            }
+            REQ_SET_CONFIGURATION => match setup.value as u8 {
+                0 => {
+                    DEVICE_STATE = DeviceState::Address;
+                    ControlResponse::Ack
+                }
+                CONFIG_VALUE => {
+                    DEVICE_STATE = DeviceState::Configured;
+                    ControlResponse::Ack
+                }
+                _ => ControlResponse::Stall,
+            },
+            REQ_GET_INTERFACE => {
+                buf[0] = 0;
+                data_stage(setup, 1)
+            }
+            REQ_SET_INTERFACE if setup.value == 0 => ControlResponse::Ack,
+            _ => ControlResponse::Stall,
+        }
+    }
+}
+
+fn handle_class_request(
+    setup: &SetupPacket,
+    buf: &mut [u8; EP0_BUF_SIZE],
+) -> ControlResponse {
+    unsafe {
+        match setup.request {
+            REQ_SET_LINE_CODING => {
+                PENDING_OUT_REQUEST = REQ_SET_LINE_CODING;
+                ControlResponse::Receive(7)
+            }
+            REQ_GET_LINE_CODING => {
+                buf[..7].copy_from_slice(&LINE_CODING.to_bytes());
+                data_stage(setup, 7)
+            }
+            REQ_SET_CONTROL_LINE_STATE => {
+                DTR = setup.value & CONTROL_LINE_DTR != 0;
+                ControlResponse::Ack
+            }
+            REQ_SEND_BREAK => ControlResponse::Ack,
+            _ => ControlResponse::Stall,
+        }
+    }
+}
+
+// Copy the requested descriptor into buf
+fn get_descriptor(
+    setup: &SetupPacket,
+    buf: &mut [u8; EP0_BUF_SIZE],
+) -> ControlResponse {
+    let desc_type = (setup.value >> 8) as u8;
+    let desc_index = setup.value as u8;
+    let config;
+    let data: &[u8] = match (desc_type, desc_index) {
+        (DESC_DEVICE, _) => &DEVICE_DESCRIPTOR,
+        (DESC_DEVICE_QUALIFIER, _) => &DEVICE_QUALIFIER,
+        (DESC_CONFIGURATION, 0) => {
+            let high_speed = unsafe { HIGH_SPEED };
+            let mps = if high_speed { BULK_MPS_HS } else { BULK_MPS_FS };
+            config = config_descriptor(mps);
+            &config
+        }
+        (DESC_STRING, 0) => &STRING_LANGIDS,
+        (DESC_STRING, 1) => {
+            return string_descriptor(setup, buf, STRING_MANUFACTURER);
+        }
+        (DESC_STRING, 2) => {
+            return string_descriptor(setup, buf, STRING_PRODUCT);
+        }
+        _ => return ControlResponse::Stall,
+    };
+    buf[..data.len()].copy_from_slice(data);
+    data_stage(setup, data.len())
+}
+
+// Build a UTF-16LE string descriptor in buf (ASCII strings only)
+fn string_descriptor(
+    setup: &SetupPacket,
+    buf: &mut [u8; EP0_BUF_SIZE],
+    s: &str,
+) -> ControlResponse {
+    let len = 2 + 2 * s.len();
+    buf[0] = len as u8;
+    buf[1] = DESC_STRING;
+    for (i, b) in s.bytes().enumerate() {
+        buf[2 + 2 * i] = b;
+        buf[3 + 2 * i] = 0;
+    }
+    data_stage(setup, len)
+}
+
+// Send len bytes of buf, or fewer if the host asked for less
+fn data_stage(setup: &SetupPacket, len: usize) -> ControlResponse {
+    ControlResponse::Data(len.min(setup.length as usize))
 }
 
 // ============================================================================
-// Phase 3: Interrupt Handler (Stub)
+// Phase 3: Interrupt Handler
 // ============================================================================
 
 /// Handle USB interrupt from IRQARRAY1.
 ///
-/// Called from trap handler when IRQARRAY1_EV_PENDING bit 0 fires.
+/// Called from the trap handler when IRQARRAY1_EV_PENDING bit 0 fires.
+/// Clears the pending bit (RW1C, confirmed in Phase 0).
 ///
 /// # Currently:
-/// This is a placeholder for Phase 3 implementation.
+/// There is no controller layer to read the event ring (see the Phase 2
+/// notes), so this only clears the bit. The USBC event stays disabled in
+/// IRQARRAY1 until there is, so only `IRQARRAY1.soft_trigger()` can raise
+/// this interrupt.
 pub fn handle_interrupt() {
-    // TODO: Phase 3 implementation
+    IRQARRAY1.clear_pending(USBC_BIT);
 }
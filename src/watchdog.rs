@@ -0,0 +1,166 @@
+// SPDX-License-Identifier: MIT
+// SPDX-FileCopyrightText: Copyright 2026 Sam Blenny
+//
+//! Watchdog for bao1x dabao evaluation board
+//!
+//! Resets the chip if the firmware stops feeding the watchdog for longer
+//! than a timeout, so an unattended device recovers from hangs.
+//!
+//! # Usage
+//!
+//! ```ignore
+//! use baochip_sdk::watchdog;
+//!
+//! watchdog::start(2000);
+//! loop {
+//!     // ... do work that should never take 2 seconds ...
+//!     watchdog::feed();
+//! }
+//! ```
+//!
+//! # Hardware Details
+//!
+//! The bao1x has a watchdog interface block, WDG_INTF, at 0x40041000. The
+//! register descriptions this SDK is built from list the block but none of
+//! its registers, so there is no documented way to program its timeout or
+//! its feed sequence. Until that is known, this module is a software
+//! watchdog built on TIMER0 and the TICKTIMER:
+//!
+//! - start() records a deadline and starts a periodic TIMER0 check every
+//!   100 ms
+//! - feed() moves the deadline to `timeout` ms from now
+//! - When the check finds that the deadline has passed, it prints a message
+//!   with polled UART output (`uart::PolledUart`, since the check runs in
+//!   the TIMER0 interrupt and the queued TX would never drain before the
+//!   reset) and calls `reset()`, which resets the whole SoC through SYSCTRL
+//!   SFR_RCURST0 (0x40040080)
+//!
+//! # Timeout Range
+//!
+//! The deadline is kept in TICKTIMER milliseconds, so the timeout can be
+//! anything from 1 ms to `u32::MAX` ms (about 49.7 days). The check runs
+//! every 100 ms, so the reset happens up to 100 ms after the deadline.
+//! (A single TIMER0 countdown at 350 MHz ACLK tops out at about 12.27 s,
+//! which is why the check is periodic rather than one long alarm.)
+//!
+//! # Limitations
+//!
+//! A software watchdog only catches hangs where interrupts still run, such
+//! as a main loop stuck waiting for a device. It does not catch code that
+//! spins with interrupts disabled, or a crash in the trap handler itself.
+//!
+//! The watchdog owns TIMER0 while it is running. Calling
+//! `timer0::set_alarm_ms()`, `timer0::cancel()`, or `sleep_low_power()`
+//! replaces the TIMER0 callback and silently stops the watchdog. Use
+//! `sleep()` and the `timers` module (polled from the main loop) instead.
+//!
+//! If the TICKTIMER isn't counting, TIMER0 is the fallback clock behind
+//! `millis()` (see `ticktimer::using_fallback()`), and start() logs a
+//! warning and leaves the watchdog stopped rather than take TIMER0 over.
+//!
+//! # API Design
+//!
+//! - start(): Arm the watchdog with a timeout in milliseconds
+//! - feed(): Push the deadline back by the timeout
+//! - stop(): Disarm the watchdog
+
+use crate::interrupt;
+use crate::ticktimer;
+use crate::timer0;
+use crate::uart::PolledUart;
+use crate::warn;
+use core::fmt::Write;
+use core::ptr;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+// How often TIMER0 checks the deadline
+const CHECK_INTERVAL_MS: u32 = 100;
+
+// ============================================================================
+// Internal State
+// ============================================================================
+
+// Timeout given to start(), 0 = watchdog stopped
+static mut TIMEOUT_MS: u32 = 0;
+
+// millis() value at which the watchdog resets the chip
+static mut DEADLINE_MS: u64 = 0;
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Start the watchdog with a timeout of `timeout_ms` milliseconds.
+///
+/// The chip resets if feed() is not called within the timeout. Calling
+/// start() again changes the timeout and counts as a feed. A timeout of 0
+/// is treated as 1 ms. Does nothing but log a warning while TIMER0 drives
+/// the TICKTIMER fallback clock.
+pub fn start(timeout_ms: u32) {
+    if ticktimer::using_fallback() {
+        warn!("watchdog: TIMER0 in use by the ticktimer fallback\r\n");
+        return;
+    }
+    let timeout_ms = timeout_ms.max(1);
+    {
+        let _cs = interrupt::critical_section();
+        unsafe {
+            TIMEOUT_MS = timeout_ms;
+            DEADLINE_MS = ticktimer::deadline_ms(timeout_ms);
+        }
+    }
+    timer0::set_periodic_ms(CHECK_INTERVAL_MS, check);
+}
+
+/// Feed the watchdog, moving the deadline to the timeout from now.
+///
+/// Does nothing if the watchdog is stopped.
+pub fn feed() {
+    let _cs = interrupt::critical_section();
+    unsafe {
+        let timeout_ms = ptr::read_volatile(&raw const TIMEOUT_MS);
+        if timeout_ms != 0 {
+            DEADLINE_MS = ticktimer::deadline_ms(timeout_ms);
+        }
+    }
+}
+
+/// Stop the watchdog and release TIMER0.
+///
+/// Does nothing if the watchdog is not running, so it never cancels a
+/// TIMER0 alarm that belongs to something else.
+pub fn stop() {
+    let was_running = {
+        let _cs = interrupt::critical_section();
+        unsafe { ptr::replace(&raw mut TIMEOUT_MS, 0) != 0 }
+    };
+    // start() never took TIMER0 if the fallback clock has it
+    if was_running && !ticktimer::using_fallback() {
+        timer0::cancel();
+    }
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+// TIMER0 callback: reset the chip once the deadline has passed
+fn check() {
+    let (timeout_ms, deadline_ms) = unsafe {
+        (
+            ptr::read_volatile(&raw const TIMEOUT_MS),
+            ptr::read_volatile(&raw const DEADLINE_MS),
+        )
+    };
+    if timeout_ms != 0 && ticktimer::millis() >= deadline_ms {
+        let _ = write!(
+            PolledUart,
+            "\r\nWATCHDOG: not fed for {} ms, resetting\r\n",
+            timeout_ms
+        );
+        crate::reset();
+    }
+}